@@ -0,0 +1,58 @@
+//! Dense bit grid against the sparse set on a big random soup
+//!
+//! Run with `cargo bench --bench dense`. Both maps start from the same 1024×1024 soup, and are
+//! checked to still agree at the end.
+
+use std::time::{Duration, Instant};
+
+use cellular_automata::conway::conway_map::{Map, ResizeAnchor};
+use cellular_automata::conway::engine::EngineKind;
+
+const SIZE: i32 = 1024;
+const GENERATIONS: u32 = 20;
+
+/// A `SIZE`×`SIZE` torus running on `engine`, with updates unthrottled
+fn map(engine: EngineKind) -> Map {
+    let mut map = Map::new();
+    map.resize(SIZE, SIZE, ResizeAnchor::TopLeft);
+    map.engine = engine;
+    map.speed = 0;
+    map
+}
+
+/// Run `map` for `GENERATIONS` generations, returning the average time per generation
+fn run(map: &mut Map) -> Duration {
+    let start = Instant::now();
+    for _ in 0..GENERATIONS {
+        map.update();
+    }
+    start.elapsed() / GENERATIONS
+}
+
+fn main() {
+    let mut sparse = map(EngineKind::Sparse);
+    sparse.soup.seed = "dense".to_owned();
+    sparse.soup.width = SIZE as u32;
+    sparse.soup.height = SIZE as u32;
+    sparse.gen_random();
+    let mut dense = map(EngineKind::Dense);
+    sparse
+        .cells()
+        .iter()
+        .for_each(|&cell| dense.set_cell_alive(cell));
+    println!(
+        "{SIZE}×{SIZE} soup, {} live cells, {GENERATIONS} generations",
+        sparse.cells().len()
+    );
+
+    let sparse_time = run(&mut sparse);
+    println!("sparse set:     {sparse_time:>12.2?} per generation");
+    let dense_time = run(&mut dense);
+    println!("dense bit grid: {dense_time:>12.2?} per generation");
+    println!(
+        "speedup: {:.1}×",
+        sparse_time.as_secs_f64() / dense_time.as_secs_f64()
+    );
+
+    assert_eq!(sparse.cells(), dense.cells(), "engines disagree");
+}
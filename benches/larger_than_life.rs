@@ -0,0 +1,45 @@
+//! Bosco's Rule on a 500×500 soup, to check Larger than Life keeps up with the frame rate
+//!
+//! Run with `cargo bench --bench larger_than_life`. A range 5 neighborhood is 121 cells, so
+//! counting them one at a time would make this about 30 times slower.
+
+use std::time::{Duration, Instant};
+
+use cellular_automata::conway::conway_map::{Map, ResizeAnchor};
+use cellular_automata::soup::{Soup, Symmetry};
+
+const SIZE: i32 = 500;
+const GENERATIONS: u32 = 50;
+
+fn main() {
+    let mut map = Map::new();
+    map.resize(SIZE, SIZE, ResizeAnchor::TopLeft);
+    map.rule = "R5,C0,M1,S34..58,B34..45,NM"
+        .parse()
+        .expect("Bosco's Rule is valid");
+    map.speed = 0;
+    // Bosco's Rule needs a thick soup to get going, about half full
+    map.soup = Soup {
+        seed: "bosco".to_owned(),
+        density: 50.0,
+        width: SIZE as u32,
+        height: SIZE as u32,
+        symmetry: Symmetry::C1,
+    };
+    map.gen_random();
+    println!(
+        "{SIZE}×{SIZE} soup, {} live cells, {GENERATIONS} generations",
+        map.cells().len()
+    );
+
+    let start = Instant::now();
+    for _ in 0..GENERATIONS {
+        map.update();
+    }
+    let per_generation: Duration = start.elapsed() / GENERATIONS;
+    println!(
+        "{per_generation:>12.2?} per generation, {:.0} generations a second, {} live cells left",
+        1.0 / per_generation.as_secs_f64(),
+        map.cells().len()
+    );
+}
@@ -0,0 +1,34 @@
+//! Lenia and Smooth Life steps at each grid size, to check the FFT keeps up with a frame rate
+//!
+//! Run with `cargo bench --bench lenia`. A step has to come in under a frame's time at the sizes
+//! people will actually pick for the side panel's speed slider to mean anything.
+
+use std::time::Instant;
+
+use cellular_automata::continuous::{Continuous, Kind, PRESETS};
+use rand::SeedableRng as _;
+use rand::rngs::StdRng;
+
+const STEPS: u32 = 20;
+
+fn main() {
+    for preset in [&PRESETS[0], &PRESETS[2]] {
+        for size in [128, 256, 512] {
+            let mut ca = Continuous::new(Kind::Lenia, size);
+            ca.load_preset(preset, &mut StdRng::seed_from_u64(20));
+            // The first step works out the kernel transforms
+            ca.step();
+
+            let start = Instant::now();
+            for _ in 0..STEPS {
+                ca.step();
+            }
+            let elapsed = start.elapsed();
+            println!(
+                "{:<20} {size:>4}×{size:<4} {:>10.2?} a step",
+                preset.name,
+                elapsed / STEPS
+            );
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! Langton's Ant for ten million steps, to check the ants mode can run into the millions
+//!
+//! Run with `cargo bench --bench turmites`. The fastest speed setting asks for a million steps a
+//! frame, which needs to come in well under a frame's time to keep the window responsive.
+
+use std::time::Instant;
+
+use cellular_automata::turmite::Turmites;
+
+const SIZE: usize = 1000;
+const STEPS: u64 = 10_000_000;
+
+fn main() {
+    let mut turmites = Turmites::new("RL".parse().expect("Langton's Ant is valid"), SIZE, SIZE);
+    println!("{SIZE}×{SIZE} grid, {STEPS} steps of RL");
+
+    let start = Instant::now();
+    turmites.run(STEPS);
+    let elapsed = start.elapsed();
+    println!(
+        "{elapsed:>12.2?} in all, {:.0} million steps a second, {} cells painted",
+        STEPS as f64 / elapsed.as_secs_f64() / 1e6,
+        turmites.grid().cells().iter().filter(|&&c| c != 0).count()
+    );
+}
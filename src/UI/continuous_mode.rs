@@ -0,0 +1,193 @@
+//! Side panel and grid for the Lenia and Smooth Life mode
+
+use super::grid_view::GridView;
+use super::playback;
+use crate::continuous::{Colormap, Continuous, Kind, MAX_SIZE, MIN_SIZE, PRESETS};
+
+/// Most rings a Lenia kernel can be given from the side panel
+const MAX_PEAKS: usize = 4;
+
+/// Everything that goes in the side panel in Lenia and Smooth Life mode
+pub fn side_panel(ui: &mut egui::Ui, ca: &mut Continuous, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            ca.step();
+        }
+        playback::speed(ui, "steps", &mut ca.pace, 1..=60);
+        ui.label(format!("Step {}", ca.generation()));
+        ui.label(format!("Mass {:.1}", ca.mass()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📜 Rule").strong());
+        ui.add_space(6.0);
+        ui.horizontal_wrapped(|ui| {
+            for preset in &PRESETS {
+                if ui.button(preset.name).on_hover_text(preset.about).clicked() {
+                    ca.load_preset(preset, &mut rand::thread_rng());
+                }
+            }
+        });
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            for kind in Kind::ALL {
+                ui.selectable_value(&mut ca.kind, kind, kind.name());
+            }
+        });
+        match ca.kind {
+            Kind::Lenia => lenia_controls(ui, ca),
+            Kind::SmoothLife => smooth_life_controls(ui, ca),
+        }
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Grid").strong());
+        ui.add_space(6.0);
+        let mut size = ca.size();
+        egui::ComboBox::from_label("Size")
+            .selected_text(format!("{size}×{size}"))
+            .show_ui(ui, |ui| {
+                let mut option = MIN_SIZE;
+                while option <= MAX_SIZE {
+                    ui.selectable_value(&mut size, option, format!("{option}×{option}"));
+                    option *= 2;
+                }
+            });
+        if size != ca.size() {
+            ca.resize(size);
+        }
+        egui::ComboBox::from_label("Colors")
+            .selected_text(ca.colormap.name())
+            .show_ui(ui, |ui| {
+                for colormap in Colormap::ALL {
+                    ui.selectable_value(&mut ca.colormap, colormap, colormap.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button("🎲 Random soup").clicked() {
+                ca.randomize(&mut rand::thread_rng());
+            }
+            if ui.button("🗑 Clear").clicked() {
+                ca.clear();
+                *running = false;
+            }
+        });
+        ui.small("💡 Left click paints while paused, right click erases");
+    });
+}
+
+/// Kernel rings, growth function and time step
+fn lenia_controls(ui: &mut egui::Ui, ca: &mut Continuous) {
+    let lenia = &mut ca.lenia;
+    ui.add(egui::Slider::new(&mut lenia.radius, 2..=60).text("Radius R"));
+    ui.horizontal(|ui| {
+        ui.label(format!("Peaks β ({})", lenia.peaks.len()));
+        if ui
+            .add_enabled(lenia.peaks.len() < MAX_PEAKS, egui::Button::new("➕"))
+            .clicked()
+        {
+            lenia.peaks.push(1.0);
+        }
+        if ui
+            .add_enabled(lenia.peaks.len() > 1, egui::Button::new("➖"))
+            .clicked()
+        {
+            lenia.peaks.pop();
+        }
+    });
+    for (ring, peak) in lenia.peaks.iter_mut().enumerate() {
+        ui.add(egui::Slider::new(peak, 0.0..=1.0).text(format!("Ring {}", ring + 1)));
+    }
+    ui.add(
+        egui::Slider::new(&mut lenia.mu, 0.0..=0.5)
+            .step_by(0.001)
+            .text("Growth μ"),
+    );
+    ui.add(
+        egui::Slider::new(&mut lenia.sigma, 0.001..=0.1)
+            .step_by(0.0005)
+            .text("Width σ"),
+    );
+    ui.add(
+        egui::Slider::new(&mut lenia.dt, 0.01..=1.0)
+            .logarithmic(true)
+            .text("Time step dt"),
+    );
+}
+
+/// Radii, birth and survival ranges, smoothness and time step
+fn smooth_life_controls(ui: &mut egui::Ui, ca: &mut Continuous) {
+    let smooth = &mut ca.smooth_life;
+    ui.add(egui::Slider::new(&mut smooth.outer_radius, 3.0..=60.0).text("Outer radius"));
+    ui.add(
+        egui::Slider::new(&mut smooth.inner_radius, 1.0..=smooth.outer_radius).text("Inner radius"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.birth.0, 0.0..=1.0)
+            .step_by(0.001)
+            .text("Birth from"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.birth.1, 0.0..=1.0)
+            .step_by(0.001)
+            .text("Birth to"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.survival.0, 0.0..=1.0)
+            .step_by(0.001)
+            .text("Survival from"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.survival.1, 0.0..=1.0)
+            .step_by(0.001)
+            .text("Survival to"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.outer_smoothness, 0.001..=0.5)
+            .logarithmic(true)
+            .text("Ring smoothness α_n"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.inner_smoothness, 0.001..=0.5)
+            .logarithmic(true)
+            .text("Disk smoothness α_m"),
+    );
+    ui.add(
+        egui::Slider::new(&mut smooth.dt, 0.01..=1.0)
+            .logarithmic(true)
+            .text("Time step dt"),
+    )
+    .on_hover_text("At 1 every cell jumps straight to its new state");
+}
+
+/// The grid in the chosen colors, painted on with a brush a third of the kernel across
+pub fn grid(ui: &mut egui::Ui, ca: &mut Continuous, view: &mut GridView, running: bool) {
+    let colormap = ca.colormap;
+    let pixels = ca
+        .grid()
+        .cells()
+        .iter()
+        .map(|&v| colormap.color(v))
+        .collect();
+    let grid = view.show(ui, (ca.size(), ca.size()), pixels);
+
+    if running || ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    let Some((x, y)) = grid.hovered else {
+        return;
+    };
+    if grid.response.is_pointer_button_down_on() {
+        let (primary, secondary) =
+            ui.input(|i| (i.pointer.primary_down(), i.pointer.secondary_down()));
+        let brush = match ca.kind {
+            Kind::Lenia => ca.lenia.radius as f32 / 3.0,
+            Kind::SmoothLife => ca.smooth_life.inner_radius,
+        };
+        if primary || secondary {
+            ca.paint(x, y, brush, if primary { 1.0 } else { 0.0 });
+        }
+    }
+}
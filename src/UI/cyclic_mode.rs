@@ -0,0 +1,184 @@
+//! Side panel and grid for the cyclic CA and Rock-Paper-Scissors mode
+
+use egui::vec2;
+
+use super::grid_view::GridView;
+use super::playback;
+use crate::cyclic::{self, Cyclic, Kind, MAX_RANGE, MAX_STATES, PRESETS};
+use crate::grid::Neighborhood;
+use crate::soup;
+
+/// Everything that goes in the side panel in cyclic mode
+pub fn side_panel(ui: &mut egui::Ui, ca: &mut Cyclic, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            ca.step();
+        }
+        playback::speed(ui, "generations", &mut ca.pace, 1..=120);
+        ui.label(format!("Generation {}", ca.generation()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| rule_controls(ui, ca));
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Grid").strong());
+        ui.add_space(6.0);
+        let (mut width, mut height) = ca.grid().size();
+        let mut resized = ui
+            .add(egui::Slider::new(&mut width, 16..=1000).text("Width"))
+            .changed();
+        resized |= ui
+            .add(egui::Slider::new(&mut height, 16..=1000).text("Height"))
+            .changed();
+        if resized {
+            ca.resize(width, height);
+        }
+        ui.small("💡 Left click paints the next state round, right click the one before");
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| soup_controls(ui, ca, running));
+}
+
+/// Random soup buttons and the seed and density they come from
+fn soup_controls(ui: &mut egui::Ui, ca: &mut Cyclic, running: &mut bool) {
+    ui.label(egui::RichText::new("🎲 Generation").strong());
+    ui.add_space(6.0);
+    ui.horizontal(|ui| {
+        if ui
+            .button("🎲 Random")
+            .on_hover_text("A new soup from a new seed")
+            .clicked()
+        {
+            ca.soup.seed = soup::new_seed();
+            ca.randomize();
+        }
+        if ui
+            .button("🌱 Replay")
+            .on_hover_text("The soup from the seed below, again")
+            .clicked()
+        {
+            ca.randomize();
+        }
+        if ui.button("🗑 Clear").clicked() {
+            ca.clear();
+            *running = false;
+        }
+    });
+    ui.add_space(4.0);
+    ui.horizontal(|ui| {
+        ui.label("Seed");
+        ui.add(egui::TextEdit::singleline(&mut ca.soup.seed).desired_width(140.0));
+        if ui.button("📋").on_hover_text("Copy the seed").clicked() {
+            ui.ctx().copy_text(ca.soup.seed.clone());
+        }
+    });
+    ui.add(
+        egui::Slider::new(&mut ca.soup.density, 0.0..=100.0)
+            .suffix("%")
+            .text("Density"),
+    );
+}
+
+/// Presets, the kind of game and its settings
+fn rule_controls(ui: &mut egui::Ui, ca: &mut Cyclic) {
+    ui.label(egui::RichText::new("📜 Rule").strong());
+    ui.add_space(6.0);
+    ui.horizontal_wrapped(|ui| {
+        for preset in &PRESETS {
+            let selected = ca.kind == preset.kind
+                && ca.states == preset.states
+                && ca.range == preset.range
+                && ca.threshold == preset.threshold
+                && ca.neighborhood == preset.neighborhood
+                && ca.randomness == preset.randomness;
+            if ui
+                .selectable_label(selected, preset.name)
+                .on_hover_text(preset.about)
+                .clicked()
+            {
+                ca.load_preset(preset);
+                ca.randomize();
+            }
+        }
+    });
+
+    ui.add_space(4.0);
+    ui.horizontal(|ui| {
+        for kind in Kind::ALL {
+            ui.selectable_value(&mut ca.kind, kind, kind.name());
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Neighborhood");
+        for neighborhood in Neighborhood::ALL {
+            ui.selectable_value(&mut ca.neighborhood, neighborhood, neighborhood.name());
+        }
+    });
+    let mut states = ca.states;
+    if ui
+        .add(egui::Slider::new(&mut states, 2..=MAX_STATES).text("States"))
+        .changed()
+    {
+        ca.set_states(states);
+    }
+    ui.add(egui::Slider::new(&mut ca.range, 1..=MAX_RANGE).text("Range"));
+    let max_threshold = ca.max_threshold();
+    ca.threshold = ca.threshold.clamp(1, max_threshold);
+    ui.add(egui::Slider::new(&mut ca.threshold, 1..=max_threshold).text("Threshold"))
+        .on_hover_text("How many neighbors have to be in the next state for a cell to move on");
+    if ca.kind == Kind::RockPaperScissors {
+        ui.add(egui::Slider::new(&mut ca.randomness, 0..=max_threshold).text("Randomness"))
+            .on_hover_text("Up to this many more neighbors are needed each step, picked at random");
+    }
+
+    // The states in order, each eaten by the one after it
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        for state in 0..ca.states {
+            let (rect, response) = ui.allocate_exact_size(vec2(12.0, 12.0), egui::Sense::hover());
+            ui.painter()
+                .rect_filled(rect, 2.0, cyclic::color(state, ca.states));
+            response.on_hover_text(format!("State {state}"));
+        }
+    });
+}
+
+/// The grid, with every state its own color round the wheel
+pub fn grid(ui: &mut egui::Ui, ca: &mut Cyclic, view: &mut GridView, running: bool) {
+    let palette: Vec<_> = (0..ca.states)
+        .map(|state| cyclic::color(state, ca.states))
+        .collect();
+    let pixels = ca
+        .grid()
+        .cells()
+        .iter()
+        .map(|&cell| palette[usize::from(cell)])
+        .collect();
+    let grid = view.show(ui, ca.grid().size(), pixels);
+
+    if running || ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    let Some((x, y)) = grid.hovered else {
+        return;
+    };
+    // One state per click, so a drag doesn't spin the cell round
+    let (primary, secondary) = ui.input(|i| {
+        (
+            i.pointer.button_pressed(egui::PointerButton::Primary),
+            i.pointer.button_pressed(egui::PointerButton::Secondary),
+        )
+    });
+    if grid.response.hovered() && (primary || secondary) {
+        let state = ca.grid().get(x, y);
+        let state = if primary {
+            state + 1
+        } else {
+            state + ca.states - 1
+        };
+        ca.set_cell(x, y, state);
+    }
+}
@@ -0,0 +1,314 @@
+//! Side panel and space-time diagram for the elementary (1D) mode
+
+use super::playback;
+use egui::ecolor::Hsva;
+use egui::{Color32, Rect, Sense, Stroke, StrokeKind, Vec2, pos2, vec2};
+
+use crate::elementary::rule::{self, CodeKind, Rule};
+use crate::elementary::{Edges, Elementary, InitialRow};
+
+/// Rules worth knowing about, as (label, states, radius, kind, code, what it's known for)
+const PRESETS: [(&str, u8, u8, CodeKind, &str, &str); 7] = [
+    (
+        "30",
+        2,
+        1,
+        CodeKind::General,
+        "30",
+        "chaotic, used as a random number generator",
+    ),
+    ("90", 2, 1, CodeKind::General, "90", "Sierpiński triangle"),
+    ("110", 2, 1, CodeKind::General, "110", "Turing complete"),
+    ("184", 2, 1, CodeKind::General, "184", "traffic flow"),
+    (
+        "73",
+        2,
+        1,
+        CodeKind::General,
+        "73",
+        "walls with chaos between",
+    ),
+    (
+        "T1599",
+        3,
+        1,
+        CodeKind::Totalistic,
+        "1599",
+        "3 colors, takes 8282 steps to settle down",
+    ),
+    (
+        "T10",
+        2,
+        1,
+        CodeKind::Totalistic,
+        "10",
+        "rule 150 as a totalistic code",
+    ),
+];
+
+/// Background and foreground colors for the current theme
+pub(crate) fn colors(ui: &egui::Ui) -> (Color32, Color32) {
+    if ui.visuals().dark_mode {
+        (Color32::BLACK, Color32::WHITE)
+    } else {
+        (Color32::WHITE, Color32::BLACK)
+    }
+}
+
+/// Color a cell in `state` is drawn with, out of `states`. Blank is the background, and with only
+/// two states the other is the foreground, past that they're spread around the color wheel
+pub(crate) fn state_color(ui: &egui::Ui, state: u8, states: u8) -> Color32 {
+    let (background, foreground) = colors(ui);
+    match (state, states) {
+        (0, _) => background,
+        (_, 2) => foreground,
+        _ => {
+            let hue = f32::from(state - 1) / f32::from(states - 1) * 0.85;
+            Hsva::new(hue, 0.75, 0.95, 1.0).into()
+        }
+    }
+}
+
+/// Everything that goes in the side panel in elementary mode
+pub fn side_panel(ui: &mut egui::Ui, ca: &mut Elementary, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            ca.step();
+        }
+        playback::speed(ui, "rows", &mut ca.pace, 1..=120);
+        ui.label(format!("Generation {}", ca.generation()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| rule_controls(ui, ca));
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("🎲 First row").strong());
+        ui.add_space(6.0);
+        let mut restart = false;
+        egui::ComboBox::from_label("Start from")
+            .selected_text(ca.initial.name())
+            .show_ui(ui, |ui| {
+                for initial in InitialRow::ALL {
+                    restart |= ui
+                        .selectable_value(&mut ca.initial, initial, initial.name())
+                        .clicked();
+                }
+            });
+        egui::ComboBox::from_label("Edges")
+            .selected_text(ca.edges.name())
+            .show_ui(ui, |ui| {
+                for edges in Edges::ALL {
+                    ui.selectable_value(&mut ca.edges, edges, edges.name());
+                }
+            });
+        restart |= ui
+            .add(egui::Slider::new(&mut ca.width, 16..=1000).text("Width"))
+            .changed();
+        restart |= ui.button("🔄 Restart").clicked();
+        if restart {
+            ca.restart();
+        }
+        ui.small("💡 Click the top row while paused to paint it, again for the next color");
+    });
+}
+
+/// Shape of the rule, its code number, the table of outputs and presets
+fn rule_controls(ui: &mut egui::Ui, ca: &mut Elementary) {
+    ui.label(egui::RichText::new("📜 Rule").strong());
+    ui.add_space(6.0);
+    let (mut states, mut radius, mut kind) = (ca.rule.states(), ca.rule.radius(), ca.rule.kind());
+    let mut reshaped = ui
+        .add(egui::Slider::new(&mut states, rule::STATES).text("Colors (k)"))
+        .changed();
+    reshaped |= ui
+        .add(egui::Slider::new(&mut radius, 1..=rule::MAX_RADIUS).text("Radius (r)"))
+        .changed();
+    egui::ComboBox::from_label("Code")
+        .selected_text(kind.name())
+        .show_ui(ui, |ui| {
+            for k in CodeKind::ALL {
+                reshaped |= ui.selectable_value(&mut kind, k, k.name()).changed();
+            }
+        });
+    if reshaped {
+        // Keep the code number if it still fits, start from nothing otherwise
+        match Rule::from_code(states, radius, kind, &ca.code_text)
+            .or_else(|_| Rule::from_code(states, radius, kind, "0"))
+        {
+            Ok(rule) => ca.set_rule(rule),
+            Err(err) => ca.code_error = Some(err),
+        }
+    }
+
+    ui.add_space(4.0);
+    let response = ui.add(
+        egui::TextEdit::multiline(&mut ca.code_text)
+            .desired_rows(1)
+            .desired_width(f32::INFINITY)
+            .hint_text("code number"),
+    );
+    if response.changed() {
+        match Rule::from_code(states, radius, kind, &ca.code_text) {
+            Ok(rule) => {
+                ca.rule = rule;
+                ca.code_error = None;
+            }
+            Err(err) => ca.code_error = Some(err),
+        }
+    }
+    if let Some(err) = &ca.code_error {
+        ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+    }
+
+    ui.add_space(4.0);
+    ui.label("Click an output to change it");
+    if rule_table(ui, &mut ca.rule) {
+        ca.code_text = ca.rule.code();
+        ca.code_error = None;
+    }
+    ui.add_space(4.0);
+    ui.horizontal_wrapped(|ui| {
+        if ui.button("🎲 Random rule").clicked() {
+            if let Ok(rule) = Rule::random(states, radius, kind, &mut rand::thread_rng()) {
+                ca.set_rule(rule);
+            }
+        }
+        for (label, states, radius, kind, code, about) in PRESETS {
+            let Ok(preset) = Rule::from_code(states, radius, kind, code) else {
+                continue;
+            };
+            if ui
+                .selectable_label(ca.rule == preset, label)
+                .on_hover_text(about)
+                .clicked()
+            {
+                ca.set_rule(preset);
+            }
+        }
+    });
+}
+
+/// Every neighborhood (or neighborhood sum) and the state it turns into, highest first like the
+/// digits of the code number. Clicking an output moves it on to the next state. Returns whether
+/// anything changed
+fn rule_table(ui: &mut egui::Ui, rule: &mut Rule) -> bool {
+    /// More neighborhoods than this take up the whole panel
+    const MAX_SHOWN: usize = 64;
+    let entries = rule.table().len();
+    if rule.kind() == CodeKind::General && entries > MAX_SHOWN {
+        ui.small(format!("{entries} neighborhoods, too many to show"));
+        return false;
+    }
+
+    let size = 9.0;
+    let states = rule.states();
+    let width = match rule.kind() {
+        CodeKind::General => (2 * usize::from(rule.radius()) + 1) as f32 * size,
+        CodeKind::Totalistic => 2.0 * size,
+    };
+    let stroke = ui.visuals().widgets.inactive.fg_stroke;
+    let mut changed = false;
+    ui.horizontal_wrapped(|ui| {
+        for entry in (0..entries).rev() {
+            let (rect, response) =
+                ui.allocate_exact_size(vec2(width, 2.0 * size + 3.0), Sense::click());
+            let output = rule.table()[entry];
+            if response.clicked() {
+                rule.set_output(entry, output + 1);
+                changed = true;
+            }
+            let painter = ui.painter();
+            let top = Rect::from_min_size(rect.min, vec2(width, size));
+            let label = match rule.kind() {
+                CodeKind::General => {
+                    for (i, state) in rule.neighborhood(entry).into_iter().enumerate() {
+                        let cell = Rect::from_min_size(
+                            rect.min + vec2(i as f32 * size, 0.0),
+                            Vec2::splat(size),
+                        );
+                        let fill = state_color(ui, state, states);
+                        painter.rect(cell, 0.0, fill, stroke, StrokeKind::Inside);
+                    }
+                    rule.neighborhood(entry)
+                        .iter()
+                        .map(u8::to_string)
+                        .collect::<String>()
+                }
+                CodeKind::Totalistic => {
+                    painter.text(
+                        top.center(),
+                        egui::Align2::CENTER_CENTER,
+                        entry.to_string(),
+                        egui::FontId::monospace(size),
+                        ui.visuals().text_color(),
+                    );
+                    format!("sum {entry}")
+                }
+            };
+            let output_rect = Rect::from_center_size(
+                pos2(rect.center().x, rect.top() + 1.5 * size + 3.0),
+                Vec2::splat(size),
+            );
+            let fill = state_color(ui, rule.table()[entry], states);
+            painter.rect(output_rect, 0.0, fill, stroke, StrokeKind::Inside);
+            response.on_hover_text(format!("{label} → {}", rule.table()[entry]));
+            ui.add_space(2.0);
+        }
+    });
+    changed
+}
+
+/// The generations so far stacked top to bottom, scrolling once they fill the space
+pub fn diagram(ui: &mut egui::Ui, ca: &mut Elementary, running: bool) {
+    let rect = ui.available_rect_before_wrap();
+    let response = ui.allocate_rect(rect, Sense::click());
+    let painter = ui.painter_at(rect);
+    let (background, _) = colors(ui);
+    painter.rect_filled(rect, 0.0, background);
+    let states = ca.rule.states();
+
+    let cell = rect.width() / ca.width as f32;
+    let visible = ((rect.height() / cell) as usize).max(1);
+    let skip = ca.rows().len().saturating_sub(visible);
+    for (r, row) in ca.rows().iter().skip(skip).enumerate() {
+        let y = rect.top() + r as f32 * cell;
+        // Runs of the same state as one rectangle each, far fewer shapes that way
+        let mut x = 0;
+        while x < row.len() {
+            let (start, state) = (x, row[x]);
+            while x < row.len() && row[x] == state {
+                x += 1;
+            }
+            if state != 0 {
+                let min = pos2(rect.left() + start as f32 * cell, y);
+                let run = Rect::from_min_size(min, vec2((x - start) as f32 * cell, cell));
+                painter.rect_filled(run, 0.0, state_color(ui, state, states));
+            }
+        }
+    }
+
+    // The first row can be painted on, as long as it's still on screen
+    if running || skip > 0 || ca.first_generation() != 0 {
+        return;
+    }
+    let cell_at = |pos: egui::Pos2| {
+        let index = ((pos.x - rect.left()) / cell) as usize;
+        (pos.y - rect.top() < cell && index < ca.width).then_some(index)
+    };
+    if let Some(index) = response.hover_pos().and_then(cell_at) {
+        let min = pos2(rect.left() + index as f32 * cell, rect.top());
+        painter.rect_stroke(
+            Rect::from_min_size(min, Vec2::splat(cell)),
+            0.0,
+            Stroke::new(1.0, Color32::from_rgb(0, 100, 255)),
+            StrokeKind::Outside,
+        );
+    }
+    if response.clicked() {
+        if let Some(index) = response.interact_pointer_pos().and_then(cell_at) {
+            ca.cycle_painted(index);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! A zoomable, pannable view of a fixed size grid of cells, for the modes that keep their cells
+//! in a flat array rather than a set
+//!
+//! The whole grid gets drawn as one texture, a pixel per cell, so it costs the same however many
+//! cells are filled in. Controls match the 2D map: Ctrl + scroll zooms and Shift + drag pans.
+
+use egui::{Color32, ColorImage, Rect, Sense, Stroke, StrokeKind, TextureHandle, Vec2, pos2, vec2};
+
+/// How the grid is framed in the view, remembered between frames
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GridView {
+    /// Size of a cell on screen, in points
+    pub cell_size: f32,
+    /// How far the grid has been dragged away from the middle of the view, in points
+    pub pan: Vec2,
+    #[serde(skip)]
+    texture: Option<TextureHandle>,
+}
+
+impl Default for GridView {
+    fn default() -> Self {
+        Self {
+            cell_size: 6.0,
+            pan: Vec2::ZERO,
+            texture: None,
+        }
+    }
+}
+
+/// What happened to the grid this frame
+pub struct GridResponse {
+    pub response: egui::Response,
+    /// Cell under the pointer, as (column, row)
+    pub hovered: Option<(usize, usize)>,
+    /// Where the grid ended up on screen
+    pub rect: Rect,
+}
+
+impl GridView {
+    /// Draw a `width`×`height` grid with `colors` (row by row) filling the rest of `ui`
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        (width, height): (usize, usize),
+        colors: Vec<Color32>,
+    ) -> GridResponse {
+        let view = ui.available_rect_before_wrap();
+        let response = ui.allocate_rect(view, Sense::click_and_drag());
+        let painter = ui.painter_at(view);
+
+        let shift = ui.input(|i| i.modifiers.shift);
+        if shift && response.dragged() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+            self.pan += response.drag_delta();
+        }
+        if response.hovered() {
+            let (scroll, zoom) = ui.input(|i| (i.smooth_scroll_delta.y, i.modifiers.command));
+            if zoom && scroll != 0.0 {
+                self.cell_size = (self.cell_size * (1.0 + scroll * 0.01))
+                    .clamp(crate::CELL_MIN, crate::CELL_MAX);
+            }
+        }
+
+        let size = vec2(width as f32, height as f32) * self.cell_size;
+        let rect = Rect::from_center_size(view.center() + self.pan, size);
+        let image = ColorImage::new([width, height], colors);
+        let options = egui::TextureOptions::NEAREST;
+        let texture = match &mut self.texture {
+            Some(texture) => {
+                texture.set(image, options);
+                texture
+            }
+            None => self
+                .texture
+                .insert(ui.ctx().load_texture("grid", image, options)),
+        };
+        let uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+        painter.image(texture.id(), rect, uv, Color32::WHITE);
+        painter.rect_stroke(
+            rect,
+            0.0,
+            ui.visuals().widgets.noninteractive.bg_stroke,
+            StrokeKind::Outside,
+        );
+
+        let hovered = response.hover_pos().and_then(|pos| {
+            let cell = (pos - rect.min) / self.cell_size;
+            let (x, y) = (cell.x.floor(), cell.y.floor());
+            let inside = (0.0..width as f32).contains(&x) && (0.0..height as f32).contains(&y);
+            inside.then_some((x as usize, y as usize))
+        });
+        if let Some((x, y)) = hovered.filter(|_| !shift) {
+            let min = rect.min + vec2(x as f32, y as f32) * self.cell_size;
+            painter.rect_stroke(
+                Rect::from_min_size(min, Vec2::splat(self.cell_size)),
+                0.0,
+                Stroke::new(1.0, Color32::from_rgb(0, 100, 255)),
+                StrokeKind::Outside,
+            );
+        }
+        GridResponse {
+            response,
+            hovered,
+            rect,
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Side panel and grid for the Margolus block mode
+
+use egui::{Color32, Rect, Sense, Stroke, StrokeKind, Vec2, pos2, vec2};
+
+use super::elementary_mode::colors;
+use super::grid_view::GridView;
+use super::playback;
+use crate::margolus::{Margolus, PRESETS, Rule};
+
+/// How much of the grid a random soup covers, and how full it is
+const SOUP_FRACTION: f64 = 0.5;
+const SOUP_DENSITY: f64 = 0.5;
+
+/// Everything that goes in the side panel in Margolus mode
+pub fn side_panel(ui: &mut egui::Ui, ca: &mut Margolus, running: &mut bool) {
+    ui.group(|ui| {
+        let (paused, reversible) = (!*running, ca.rule.is_reversible());
+        let mut back = false;
+        let step = playback::buttons(ui, running, |ui| {
+            back = ui
+                .add_enabled(paused && reversible, egui::Button::new("⏮ Back"))
+                .on_disabled_hover_text(
+                    "Only rules where no two blocks turn into the same one run backwards",
+                )
+                .clicked();
+        });
+        if back {
+            ca.step_back();
+        }
+        if step {
+            ca.step();
+        }
+        playback::speed(ui, "generations", &mut ca.pace, 1..=120);
+        ui.label(format!("Generation {}", ca.generation()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| rule_controls(ui, ca));
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Grid").strong());
+        ui.add_space(6.0);
+        let (mut width, mut height) = ca.grid().size();
+        let mut resized = ui
+            .add(egui::Slider::new(&mut width, 16..=1000).text("Width"))
+            .changed();
+        resized |= ui
+            .add(egui::Slider::new(&mut height, 16..=1000).text("Height"))
+            .changed();
+        if resized {
+            ca.resize(width, height);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("🎲 Random soup").clicked() {
+                ca.randomize(SOUP_FRACTION, SOUP_DENSITY, &mut rand::thread_rng());
+            }
+            if ui.button("🗑 Clear").clicked() {
+                ca.clear();
+                *running = false;
+            }
+        });
+        ui.small("💡 Left click draws cells while paused, right click erases them");
+    });
+}
+
+/// The rulestring, presets and the table of blocks
+fn rule_controls(ui: &mut egui::Ui, ca: &mut Margolus) {
+    ui.label(egui::RichText::new("📜 Rule").strong());
+    ui.add_space(6.0);
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut ca.rule_text)
+            .desired_width(f32::INFINITY)
+            .hint_text("MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15"),
+    );
+    if response.changed() {
+        match ca.rule_text.parse::<Rule>() {
+            Ok(rule) => {
+                ca.rule = rule;
+                ca.rule_error = None;
+            }
+            Err(err) => ca.rule_error = Some(err),
+        }
+    }
+    if let Some(err) = &ca.rule_error {
+        ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+    }
+    ui.horizontal_wrapped(|ui| {
+        for (name, rule, about) in PRESETS {
+            let Ok(preset) = rule.parse::<Rule>() else {
+                continue;
+            };
+            if ui
+                .selectable_label(ca.rule == preset, name)
+                .on_hover_text(about)
+                .clicked()
+            {
+                ca.rule = preset;
+                ca.rule_text = preset.to_string();
+                ca.rule_error = None;
+            }
+        }
+    });
+
+    ui.add_space(4.0);
+    ui.label("Click a cell on the right of a block to change what it turns into");
+    if block_table(ui, &mut ca.rule) {
+        ca.rule_text = ca.rule.to_string();
+        ca.rule_error = None;
+    }
+    if !ca.rule.is_reversible() {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            "⚠ Two blocks turn into the same one, so this can't run backwards",
+        );
+    }
+}
+
+/// Every block and the one it turns into, side by side. Returns whether anything changed
+fn block_table(ui: &mut egui::Ui, rule: &mut Rule) -> bool {
+    let size = 8.0;
+    let (background, foreground) = colors(ui);
+    let stroke = ui.visuals().widgets.inactive.fg_stroke;
+    let mut changed = false;
+    ui.horizontal_wrapped(|ui| {
+        for block in 0..16u8 {
+            let (rect, _) =
+                ui.allocate_exact_size(vec2(5.0 * size + 4.0, 2.0 * size), Sense::hover());
+            let output = rule.apply(block);
+            for (side, value) in [(0.0, block), (3.0 * size, output)] {
+                for bit in 0..4u8 {
+                    let offset = vec2(f32::from(bit % 2), f32::from(bit / 2)) * size;
+                    let cell =
+                        Rect::from_min_size(rect.min + vec2(side, 0.0) + offset, Vec2::splat(size));
+                    let fill = if value & (1 << bit) != 0 {
+                        foreground
+                    } else {
+                        background
+                    };
+                    ui.painter()
+                        .rect(cell, 0.0, fill, stroke, StrokeKind::Inside);
+                    if side > 0.0 {
+                        let id = ui.id().with(("margolus block", block, bit));
+                        if ui.interact(cell, id, Sense::click()).clicked() {
+                            rule.set(block, output ^ (1 << bit));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            ui.painter().text(
+                pos2(rect.min.x + 2.5 * size, rect.center().y),
+                egui::Align2::CENTER_CENTER,
+                "→",
+                egui::FontId::monospace(size),
+                ui.visuals().text_color(),
+            );
+            ui.add_space(6.0);
+        }
+    });
+    changed
+}
+
+/// The grid, with the current blocks outlined once they're big enough to make out
+pub fn grid(ui: &mut egui::Ui, ca: &mut Margolus, view: &mut GridView, running: bool) {
+    let (background, foreground) = colors(ui);
+    let pixels = ca
+        .grid()
+        .cells()
+        .iter()
+        .map(|&cell| if cell != 0 { foreground } else { background })
+        .collect();
+    let grid = view.show(ui, ca.grid().size(), pixels);
+
+    let size = view.cell_size;
+    if size >= 6.0 {
+        // Blocks for the next step are cut one cell in on odd generations
+        let offset = ca.generation().rem_euclid(2) as f32 * size;
+        let painter = ui.painter_at(grid.response.rect);
+        let stroke = Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 100, 255, 80));
+        for x in (0..ca.grid().width()).step_by(2) {
+            let x = grid.rect.min.x + x as f32 * size + offset;
+            painter.vline(x, grid.rect.y_range(), stroke);
+        }
+        for y in (0..ca.grid().height()).step_by(2) {
+            let y = grid.rect.min.y + y as f32 * size + offset;
+            painter.hline(grid.rect.x_range(), y, stroke);
+        }
+    }
+
+    if running || ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    let Some((x, y)) = grid.hovered else {
+        return;
+    };
+    if grid.response.is_pointer_button_down_on() {
+        let (primary, secondary) =
+            ui.input(|i| (i.pointer.primary_down(), i.pointer.secondary_down()));
+        if primary || secondary {
+            ca.set_cell(x, y, primary);
+        }
+    }
+}
@@ -1,2 +1,11 @@
+pub mod continuous_mode;
+pub mod cyclic_mode;
 pub mod elementary_mode;
-pub mod two_dimensional_mode;
\ No newline at end of file
+pub mod grid_view;
+pub mod margolus_mode;
+pub mod playback;
+pub mod sandpile_mode;
+pub mod stochastic_mode;
+pub mod turmite_mode;
+pub mod two_dimensional_mode;
+pub mod wireworld_mode;
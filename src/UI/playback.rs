@@ -0,0 +1,34 @@
+//! The "Simulation" group every mode's side panel starts with
+
+use std::ops::RangeInclusive;
+
+use egui::vec2;
+
+use crate::timing::Pace;
+
+/// The group heading with Play/Pause and Step buttons under it, plus whatever `extra` adds in
+/// between them. Returns whether Step was clicked
+pub fn buttons(ui: &mut egui::Ui, running: &mut bool, extra: impl FnOnce(&mut egui::Ui)) -> bool {
+    ui.label(egui::RichText::new("⏯ Simulation").strong());
+    ui.add_space(6.0);
+    ui.horizontal(|ui| {
+        let play_button_text = if *running { "⏸ Pause" } else { "▶ Play" };
+        if ui
+            .add(egui::Button::new(play_button_text).min_size(vec2(100.0, 32.0)))
+            .clicked()
+        {
+            *running = !*running;
+        }
+        extra(ui);
+        ui.add_enabled(!*running, egui::Button::new("⏭ Step"))
+            .clicked()
+    })
+    .inner
+}
+
+/// A slider for how many `steps` (generations, rows, drops) run every second
+pub fn speed(ui: &mut egui::Ui, steps: &str, pace: &mut Pace, range: RangeInclusive<u32>) {
+    ui.add_space(8.0);
+    ui.label(format!("Speed ({steps} per second)"));
+    ui.add(egui::Slider::new(&mut pace.fps, range));
+}
@@ -0,0 +1,153 @@
+//! Side panel and grid for the sandpile mode
+//!
+//! The sandpile is laid out on the 2D map's grid, the same size and with the same zoom and
+//! position. It's drawn like the other fixed size grids though, as square cells whatever the
+//! map's topology, and nothing wraps round: grains past the edges are lost.
+
+use egui::{Color32, vec2};
+
+use super::elementary_mode::colors;
+use super::grid_view::GridView;
+use super::playback;
+use crate::continuous::colormap::Colormap;
+use crate::conway::conway_map::Map;
+use crate::grid::Neighborhood;
+use crate::plot;
+use crate::sandpile::{Coloring, Drop, Sandpile};
+
+/// Everything that goes in the side panel in sandpile mode
+pub fn side_panel(ui: &mut egui::Ui, sandpile: &mut Sandpile, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            sandpile.step();
+        }
+        playback::speed(ui, "drops", &mut sandpile.pace, 1..=240);
+        ui.label(format!(
+            "Step {}, {} grains",
+            sandpile.steps(),
+            sandpile.total()
+        ));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📜 Rule").strong());
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.label("Neighborhood");
+            for neighborhood in Neighborhood::ALL {
+                if ui
+                    .selectable_label(sandpile.neighborhood == neighborhood, neighborhood.name())
+                    .clicked()
+                {
+                    sandpile.set_neighborhood(neighborhood);
+                }
+            }
+        });
+        let min = sandpile.min_threshold();
+        let mut threshold = sandpile.threshold();
+        if ui
+            .add(egui::Slider::new(&mut threshold, min..=4 * min).text("Topples at"))
+            .on_hover_text("Grains over one per neighbor are lost on every topple")
+            .changed()
+        {
+            sandpile.set_threshold(threshold);
+        }
+        ui.horizontal(|ui| {
+            ui.label("Drop");
+            for drop in Drop::ALL {
+                ui.selectable_value(&mut sandpile.drop, drop, drop.name());
+            }
+        });
+        ui.add(egui::Slider::new(&mut sandpile.grains_per_drop, 1..=10_000).logarithmic(true))
+            .on_hover_text("Grains dropped each step, and with every click");
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("🏔 Avalanches").strong());
+        ui.add_space(6.0);
+        ui.label(format!(
+            "Last one toppled {} times",
+            sandpile.last_avalanche()
+        ));
+        let bars: Vec<_> = sandpile
+            .histogram()
+            .iter()
+            .enumerate()
+            .map(|(bin, &count)| (Sandpile::histogram_label(bin), count))
+            .collect();
+        plot::bar_plot(ui, &bars, Color32::from_rgb(230, 160, 60), 100.0);
+        ui.small("Sizes are binned by powers of two");
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Grid").strong());
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.label("Color by");
+            for coloring in Coloring::ALL {
+                ui.selectable_value(&mut sandpile.coloring, coloring, coloring.name());
+            }
+        });
+        if ui.button("🗑 Clear").clicked() {
+            sandpile.clear();
+            *running = false;
+        }
+        ui.small("💡 Click to drop grains, the grid is the 2D map's size");
+    });
+}
+
+/// The sandpile over the 2D map's grid, framed at the map's zoom and position. Zooming or
+/// panning here moves the map's view too
+pub fn grid(ui: &mut egui::Ui, sandpile: &mut Sandpile, view: &mut GridView, map: &mut Map) {
+    let cell_size = map.cell_size;
+    view.cell_size = cell_size;
+    view.pan = -vec2(map.x_axis as f32, map.y_axis as f32) * cell_size;
+
+    let (background, _) = colors(ui);
+    let cell_color = cell_colors(sandpile, background);
+    let pixels = (0..sandpile.grains().cells().len())
+        .map(cell_color)
+        .collect();
+    let grid = view.show(ui, sandpile.grains().size(), pixels);
+
+    // The map only moves by whole cells
+    map.x_axis = (-view.pan.x / cell_size).round() as i32;
+    map.y_axis = (-view.pan.y / cell_size).round() as i32;
+    map.cell_size = view.cell_size;
+
+    if ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    let Some((x, y)) = grid.hovered else {
+        return;
+    };
+    if grid.response.clicked() || grid.response.dragged() {
+        sandpile.drop_at(x, y, sandpile.grains_per_drop);
+    }
+}
+
+/// How to color the cell at each index, going by grains or by topples
+fn cell_colors(sandpile: &Sandpile, background: Color32) -> impl Fn(usize) -> Color32 + '_ {
+    let most_grains = sandpile.threshold().saturating_sub(1).max(1) as f32;
+    let most_topples = sandpile
+        .topples()
+        .cells()
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    move |i| match sandpile.coloring {
+        Coloring::Grains => match sandpile.grains().cells()[i] {
+            0 => background,
+            grains => Colormap::Viridis.color(grains as f32 / most_grains),
+        },
+        Coloring::Topples => match sandpile.topples().cells()[i] {
+            0 => background,
+            topples => Colormap::Magma.color(0.2 + 0.8 * topples as f32 / most_topples),
+        },
+    }
+}
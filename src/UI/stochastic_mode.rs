@@ -0,0 +1,172 @@
+//! Side panel and grid for the forest fire and SIR epidemic mode
+
+use rand::Rng as _;
+
+use super::grid_view::GridView;
+use super::playback;
+use crate::grid::Neighborhood;
+use crate::plot::{self, Series};
+use crate::stochastic::{Kind, Stochastic};
+
+/// How many of the most recent generations the compartment plot shows
+const PLOT_WINDOW: usize = 1000;
+
+/// Everything that goes in the side panel in forest fire and epidemic mode
+pub fn side_panel(ui: &mut egui::Ui, model: &mut Stochastic, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            model.step();
+        }
+        playback::speed(ui, "generations", &mut model.pace, 1..=120);
+        ui.label(format!("Generation {}", model.generation()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| rule_controls(ui, model));
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📈 Compartments").strong());
+        ui.add_space(6.0);
+        compartment_plot(ui, model);
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Grid").strong());
+        ui.add_space(6.0);
+        let (mut width, mut height) = model.grid().size();
+        let mut resized = ui
+            .add(egui::Slider::new(&mut width, 16..=1000).text("Width"))
+            .changed();
+        resized |= ui
+            .add(egui::Slider::new(&mut height, 16..=1000).text("Height"))
+            .changed();
+        if resized {
+            model.resize(width, height);
+        }
+        ui.horizontal(|ui| {
+            ui.label("Seed");
+            ui.add(egui::DragValue::new(&mut model.seed));
+            if ui
+                .button("🎲")
+                .on_hover_text("Pick a new seed and start over")
+                .clicked()
+            {
+                model.seed = rand::thread_rng().r#gen();
+                model.restart();
+            }
+        });
+        if ui
+            .button("🔄 Restart")
+            .on_hover_text("Start over from the seed, the same seed and settings run the same way")
+            .clicked()
+        {
+            model.restart();
+            *running = false;
+        }
+        ui.small("💡 Left click plants trees or infects, right click starts fires or makes immune");
+    });
+}
+
+/// Which model, and its chances
+fn rule_controls(ui: &mut egui::Ui, model: &mut Stochastic) {
+    ui.label(egui::RichText::new("📜 Rule").strong());
+    ui.add_space(6.0);
+    ui.horizontal(|ui| {
+        for kind in Kind::ALL {
+            if ui
+                .selectable_label(model.kind == kind, kind.name())
+                .clicked()
+                && model.kind != kind
+            {
+                model.kind = kind;
+                model.restart();
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Neighborhood");
+        for neighborhood in Neighborhood::ALL {
+            ui.selectable_value(&mut model.neighborhood, neighborhood, neighborhood.name());
+        }
+    });
+    match model.kind {
+        Kind::ForestFire => {
+            let fire = &mut model.forest_fire;
+            ui.add(
+                egui::Slider::new(&mut fire.growth, 1e-4..=1.0)
+                    .logarithmic(true)
+                    .text("Growth p"),
+            )
+            .on_hover_text("Chance of a tree growing on empty ground");
+            ui.add(
+                egui::Slider::new(&mut fire.lightning, 1e-7..=1e-1)
+                    .logarithmic(true)
+                    .text("Lightning f"),
+            )
+            .on_hover_text("Chance of lightning setting a tree alight");
+            ui.add(egui::Slider::new(&mut fire.density, 0.0..=1.0).text("Starting trees"));
+        }
+        Kind::Epidemic => {
+            let epidemic = &mut model.epidemic;
+            ui.add(egui::Slider::new(&mut epidemic.infection, 0.0..=1.0).text("Infection"))
+                .on_hover_text("Chance of catching it from each infected neighbor");
+            ui.add(egui::Slider::new(&mut epidemic.recovery, 0.0..=1.0).text("Recovery"))
+                .on_hover_text("Chance of an infected cell recovering");
+            ui.add(
+                egui::Slider::new(&mut epidemic.infected, 1e-5..=1.0)
+                    .logarithmic(true)
+                    .text("Starting infected"),
+            );
+        }
+    }
+}
+
+/// How many cells are in each state over the recent generations
+fn compartment_plot(ui: &mut egui::Ui, model: &Stochastic) {
+    let history = model.history();
+    let recent = &history[history.len().saturating_sub(PLOT_WINDOW)..];
+    // The history gets trimmed from the front, so count back from the latest generation
+    let first = (model.generation() + 1).saturating_sub(recent.len() as u64);
+    let names = model.kind.compartments();
+    let colors = model.kind.colors();
+    let series: Vec<_> = (0..3)
+        .map(|state| Series {
+            name: names[state],
+            color: colors[state],
+            points: recent
+                .iter()
+                .enumerate()
+                .map(|(i, counts)| [(first + i as u64) as f64, counts[state] as f64])
+                .collect(),
+        })
+        .collect();
+    plot::line_plot(ui, &series, 120.0);
+}
+
+/// The grid, colored by compartment
+pub fn grid(ui: &mut egui::Ui, model: &mut Stochastic, view: &mut GridView, running: bool) {
+    let colors = model.kind.colors();
+    let pixels = model
+        .grid()
+        .cells()
+        .iter()
+        .map(|&cell| colors[usize::from(cell)])
+        .collect();
+    let grid = view.show(ui, model.grid().size(), pixels);
+
+    if running || ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    let Some((x, y)) = grid.hovered else {
+        return;
+    };
+    if grid.response.is_pointer_button_down_on() {
+        let (primary, secondary) =
+            ui.input(|i| (i.pointer.primary_down(), i.pointer.secondary_down()));
+        if primary || secondary {
+            model.set_cell(x, y, if primary { 1 } else { 2 });
+        }
+    }
+}
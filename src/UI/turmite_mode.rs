@@ -0,0 +1,172 @@
+//! Side panel and grid for the Langton's Ant and turmites mode
+
+use egui::{Color32, Stroke, vec2};
+
+use super::elementary_mode::state_color;
+use super::grid_view::GridView;
+use super::playback;
+use crate::turmite::rule::Rule;
+use crate::turmite::{Heading, Turmites};
+
+/// Rules worth knowing about, as (label, rule, what it's known for)
+const PRESETS: [(&str, &str, &str); 7] = [
+    (
+        "RL",
+        "RL",
+        "Langton's Ant, builds a highway after about 10,000 steps",
+    ),
+    ("RLR", "RLR", "grows chaotically forever"),
+    ("LLRR", "LLRR", "grows symmetrically"),
+    ("LRRRRRLLR", "LRRRRRLLR", "fills a square around itself"),
+    (
+        "LLRRRLRLRLLR",
+        "LLRRRLRLRLLR",
+        "builds a convoluted highway",
+    ),
+    (
+        "RRLLLRLLLRRR",
+        "RRLLLRLLLRRR",
+        "a triangle that grows and moves",
+    ),
+    (
+        "Fibonacci",
+        "{{{1, 8, 1}, {1, 8, 1}}, {{1, 2, 1}, {0, 1, 0}}}",
+        "two state turmite that grows a Fibonacci spiral",
+    ),
+];
+
+/// Most steps a frame, enough to run into the millions in a few seconds
+const MAX_STEPS_PER_FRAME: u32 = 1_000_000;
+
+const ANT_COLOR: Color32 = Color32::from_rgb(255, 40, 40);
+
+/// Everything that goes in the side panel in turmite mode
+pub fn side_panel(ui: &mut egui::Ui, turmites: &mut Turmites, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            turmites.step();
+        }
+        ui.add_space(8.0);
+        ui.label("Speed (steps per frame)");
+        ui.add(
+            egui::Slider::new(&mut turmites.steps_per_frame, 1..=MAX_STEPS_PER_FRAME)
+                .logarithmic(true),
+        );
+        ui.label(format!("Step {}", turmites.steps()));
+        ui.label(format!("{} ants", turmites.ants().len()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📜 Rule").strong());
+        ui.add_space(6.0);
+        let response = ui.add(
+            egui::TextEdit::multiline(&mut turmites.rule_text)
+                .desired_rows(1)
+                .desired_width(f32::INFINITY)
+                .hint_text("RL or {{{1, 2, 0}, {0, 8, 0}}}"),
+        );
+        if response.changed() {
+            match turmites.rule_text.parse::<Rule>() {
+                Ok(rule) => {
+                    let text = turmites.rule_text.clone();
+                    turmites.set_rule(rule);
+                    // Keep what's being typed as it is, rather than tidied up
+                    turmites.rule_text = text;
+                }
+                Err(err) => turmites.rule_error = Some(err),
+            }
+        }
+        response.on_hover_text(
+            "An ant's turn for each color (L, R, N for none or U), or a turmite's table of \
+             {color, turn, state} in Ed Pegg's notation, turns being 1 none, 2 right, 4 U-turn \
+             and 8 left",
+        );
+        if let Some(err) = &turmites.rule_error {
+            ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+        }
+        ui.label(format!(
+            "{} colors, {} states",
+            turmites.rule().colors(),
+            turmites.rule().states()
+        ));
+        ui.add_space(4.0);
+        ui.horizontal_wrapped(|ui| {
+            for (label, rule, about) in PRESETS {
+                let Ok(preset) = rule.parse::<Rule>() else {
+                    continue;
+                };
+                if ui
+                    .selectable_label(*turmites.rule() == preset, label)
+                    .on_hover_text(about)
+                    .clicked()
+                {
+                    turmites.set_rule(preset);
+                }
+            }
+        });
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Grid").strong());
+        ui.add_space(6.0);
+        let (mut width, mut height) = turmites.grid().size();
+        let mut resized = ui
+            .add(egui::Slider::new(&mut width, 16..=1000).text("Width"))
+            .changed();
+        resized |= ui
+            .add(egui::Slider::new(&mut height, 16..=1000).text("Height"))
+            .changed();
+        if resized {
+            turmites.resize(width, height);
+        }
+        if ui.button("🔄 Restart").clicked() {
+            turmites.restart();
+        }
+        ui.small("💡 Click to put down another ant while paused, right click to pick them up");
+    });
+}
+
+/// The grid colored by cell, with every ant drawn on top pointing the way it's heading
+pub fn grid(ui: &mut egui::Ui, turmites: &mut Turmites, view: &mut GridView, running: bool) {
+    let colors = turmites.rule().colors();
+    let pixels = turmites
+        .grid()
+        .cells()
+        .iter()
+        .map(|&color| state_color(ui, color, colors))
+        .collect();
+    let grid = view.show(ui, turmites.grid().size(), pixels);
+
+    let painter = ui.painter_at(grid.response.rect);
+    let size = view.cell_size;
+    for ant in turmites.ants() {
+        let center = grid.rect.min + vec2(ant.x as f32 + 0.5, ant.y as f32 + 0.5) * size;
+        if size < 4.0 {
+            painter.circle_filled(center, 2.0, ANT_COLOR);
+            continue;
+        }
+        let (dx, dy) = ant.heading.offset();
+        let forward = vec2(dx as f32, dy as f32) * size * 0.45;
+        let side = vec2(-forward.y, forward.x) * 0.8;
+        let tip = center + forward;
+        let back = center - forward * 0.8;
+        painter.add(egui::Shape::convex_polygon(
+            vec![tip, back + side, back - side],
+            ANT_COLOR,
+            Stroke::new(1.0, Color32::BLACK),
+        ));
+    }
+
+    if running || ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    if let Some((x, y)) = grid.hovered {
+        if grid.response.clicked() {
+            turmites.add_ant(x, y, Heading::North);
+        } else if grid.response.secondary_clicked() {
+            turmites.remove_ants_at(x, y);
+        }
+    }
+}
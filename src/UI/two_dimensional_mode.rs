@@ -1 +1 @@
-//move over all of the ui inline functions here
\ No newline at end of file
+//move over all of the ui inline functions here
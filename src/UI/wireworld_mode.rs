@@ -0,0 +1,102 @@
+//! Side panel and board for the Wireworld mode
+
+use egui::{Color32, Sense, StrokeKind, vec2};
+
+use super::grid_view::GridView;
+use super::playback;
+use crate::wireworld::{Cell, WireWorld};
+
+/// Colors of the four cells, Golly's except for the tail, which would vanish on a light background
+fn cell_color(ui: &egui::Ui, cell: Cell) -> Color32 {
+    match cell {
+        Cell::Empty if ui.visuals().dark_mode => Color32::BLACK,
+        Cell::Empty => Color32::WHITE,
+        Cell::Head => Color32::from_rgb(0, 128, 255),
+        Cell::Tail => Color32::from_rgb(230, 60, 60),
+        Cell::Conductor => Color32::from_rgb(255, 160, 0),
+    }
+}
+
+/// Everything that goes in the side panel in Wireworld mode
+pub fn side_panel(ui: &mut egui::Ui, ww: &mut WireWorld, running: &mut bool) {
+    ui.group(|ui| {
+        if playback::buttons(ui, running, |_| {}) {
+            ww.step();
+        }
+        playback::speed(ui, "generations", &mut ww.pace, 1..=120);
+        ui.label(format!("Generation {}", ww.generation()));
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("🎨 Drawing").strong());
+        ui.add_space(6.0);
+        for cell in Cell::ALL {
+            ui.horizontal(|ui| {
+                let (rect, _) = ui.allocate_exact_size(vec2(14.0, 14.0), Sense::hover());
+                let stroke = ui.visuals().widgets.inactive.fg_stroke;
+                ui.painter()
+                    .rect(rect, 2.0, cell_color(ui, cell), stroke, StrokeKind::Inside);
+                ui.radio_value(&mut ww.paint, cell, cell.name());
+            });
+        }
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            if ui
+                .button("⚡ Strip electrons")
+                .on_hover_text("Turn every electron head and tail back into conductor")
+                .clicked()
+            {
+                ww.strip_electrons();
+            }
+            if ui.button("🗑 Clear").clicked() {
+                ww.clear();
+                *running = false;
+            }
+        });
+        ui.small("💡 Left click paints, right click erases, both while paused");
+    });
+
+    ui.add_space(8.0);
+    ui.group(|ui| {
+        ui.label(egui::RichText::new("📐 Board").strong());
+        ui.add_space(6.0);
+        let (mut width, mut height) = ww.grid().size();
+        let mut resized = ui
+            .add(egui::Slider::new(&mut width, 8..=1000).text("Width"))
+            .changed();
+        resized |= ui
+            .add(egui::Slider::new(&mut height, 8..=1000).text("Height"))
+            .changed();
+        if resized {
+            ww.resize(width, height);
+        }
+        ui.checkbox(&mut ww.wrap, "Wrap around the edges");
+    });
+}
+
+/// The board, painted on with the mouse while paused
+pub fn board(ui: &mut egui::Ui, ww: &mut WireWorld, view: &mut GridView, running: bool) {
+    let colors = ww
+        .grid()
+        .cells()
+        .iter()
+        .map(|&cell| cell_color(ui, cell))
+        .collect();
+    let grid = view.show(ui, ww.grid().size(), colors);
+    if running || ui.input(|i| i.modifiers.shift) {
+        return;
+    }
+    let Some((x, y)) = grid.hovered else {
+        return;
+    };
+    let (primary, secondary) = ui.input(|i| (i.pointer.primary_down(), i.pointer.secondary_down()));
+    if !grid.response.is_pointer_button_down_on() {
+        return;
+    }
+    if primary {
+        ww.set_cell(x, y, ww.paint);
+    } else if secondary {
+        ww.set_cell(x, y, Cell::Empty);
+    }
+}
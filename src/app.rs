@@ -1,9 +1,36 @@
-use crate::consts::*;
-use crate::conway;
 use crate::RunModes;
+use crate::RunStatistics;
 use crate::UserInterface;
 use crate::Viewport;
+use crate::consts::*;
+use crate::continuous::Continuous;
+use crate::conway;
+use crate::conway::boundary::Boundary;
+use crate::conway::engine::EngineKind;
+use crate::conway::rule::{PRESETS, Rule};
+use crate::conway::rule_file::RuleFile;
+use crate::cyclic::Cyclic;
+use crate::elementary::Elementary;
+use crate::margolus::Margolus;
+use crate::pattern::io::{self as pattern_io, Format};
+use crate::plot::{self, Series};
+use crate::sandpile::Sandpile;
+use crate::soup::{self, Symmetry};
+use crate::stochastic::{self, Stochastic};
+use crate::turmite::Turmites;
+use crate::ui::continuous_mode;
+use crate::ui::cyclic_mode;
+use crate::ui::elementary_mode;
+use crate::ui::grid_view::GridView;
+use crate::ui::margolus_mode;
+use crate::ui::sandpile_mode;
+use crate::ui::stochastic_mode;
+use crate::ui::turmite_mode;
+use crate::ui::wireworld_mode;
+use crate::wireworld::{self, WireWorld};
 use conway::conway_map;
+use conway::conway_map::ResizeAnchor;
+use conway::worker::Worker;
 use eframe::egui;
 use egui::Id;
 
@@ -46,8 +73,55 @@ pub struct ConwaySim {
     is_painting: bool,
     #[serde(skip)]
     paint_mode: bool, // true = paint alive cells, false = erase cells
+    /// State new cells get painted in, only ever not 1 under a Generations rule or a rule file
+    paint_state: u8,
+    resize_anchor: ResizeAnchor,
+    export_format: Format,
+    /// Golly `.rule` file to load from the File menu
+    rule_filename: String,
+    #[serde(skip)]
+    rule_text: String,
+    #[serde(skip)]
+    rule_error: Option<String>,
+    /// Outcome of the last pattern import/export, shown in the File menu
+    #[serde(skip)]
+    file_status: Option<Result<String, String>>,
+    /// Steps the map in the background while it's running
+    #[serde(skip)]
+    worker: Worker,
+    /// State of the elementary (1D) mode
+    elementary: Elementary,
+    /// State of the Wireworld mode
+    wireworld: WireWorld,
+    wireworld_view: GridView,
+    /// State of the Langton's Ant and turmites mode
+    turmites: Turmites,
+    turmite_view: GridView,
+    /// State of the Margolus block mode
+    margolus: Margolus,
+    margolus_view: GridView,
+    /// State of the Lenia and Smooth Life mode
+    continuous: Continuous,
+    continuous_view: GridView,
+    /// State of the cyclic CA and Rock-Paper-Scissors mode
+    cyclic: Cyclic,
+    cyclic_view: GridView,
+    /// State of the sandpile mode, laid out on the 2D map's grid
+    sandpile: Sandpile,
+    sandpile_view: GridView,
+    /// State of the forest fire and SIR epidemic mode
+    stochastic: Stochastic,
+    stochastic_view: GridView,
+    /// Where the statistics CSV gets saved
+    stats_filename: String,
+    /// Outcome of the last statistics export, shown in the stats window
+    #[serde(skip)]
+    stats_status: Option<Result<String, String>>,
 }
 
+/// How many of the most recent updates the stats window plots
+const STATS_PLOT_WINDOW: usize = 500;
+
 // TODO: implement feature so that the user can click and drag on the main view window to move
 // their view around instead of using sliders, cause sliders are janky as fuck
 
@@ -56,12 +130,15 @@ impl Default for ConwaySim {
     fn default() -> Self {
         let mut map = conway_map::Map::new();
         map.gen_random();
+        let mut cyclic = Cyclic::default();
+        cyclic.randomize();
         Self {
             // Example stuff:
             map,
             running: false,
             label: "Cellular Automata".to_owned(),
             filename: "".to_owned(),
+            rule_filename: String::new(),
             rect: None,
             fps: 0.0,
             value: 2.7,
@@ -75,6 +152,35 @@ impl Default for ConwaySim {
             hovered_cell: None,
             is_painting: false,
             paint_mode: true,
+            paint_state: 1,
+            resize_anchor: ResizeAnchor::default(),
+            export_format: Format::default(),
+            rule_text: Rule::default().to_string(),
+            rule_error: None,
+            file_status: None,
+            worker: Worker::default(),
+            elementary: Elementary::default(),
+            wireworld: WireWorld::default(),
+            wireworld_view: GridView::default(),
+            turmites: Turmites::default(),
+            turmite_view: GridView::default(),
+            margolus: Margolus::default(),
+            margolus_view: GridView::default(),
+            continuous: Continuous::default(),
+            continuous_view: GridView::default(),
+            cyclic,
+            cyclic_view: GridView::default(),
+            sandpile: Sandpile::default(),
+            sandpile_view: GridView::default(),
+            stochastic: Stochastic::new(
+                stochastic::Kind::default(),
+                stochastic::DEFAULT_SIZE,
+                stochastic::DEFAULT_SIZE,
+                rand::Rng::r#gen(&mut rand::thread_rng()),
+            ),
+            stochastic_view: GridView::default(),
+            stats_filename: "stats.csv".to_owned(),
+            stats_status: None,
         }
     }
 }
@@ -87,7 +193,19 @@ impl ConwaySim {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            let mut app: Self = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            app.rule_text = app.map.rule.to_string();
+            app.elementary.code_text = app.elementary.rule.code();
+            app.elementary.restart();
+            app.turmites.rule_text = app.turmites.rule().to_string();
+            app.turmites.restart();
+            app.margolus.rule_text = app.margolus.rule.to_string();
+            app.margolus.clear();
+            app.continuous.clear();
+            app.cyclic.randomize();
+            app.sandpile.clear();
+            app.stochastic.restart();
+            return app;
         }
 
         Default::default()
@@ -98,13 +216,15 @@ impl ConwaySim {
             if i.key_pressed(egui::Key::Space) {
                 self.running = !self.running;
             }
+            // Everything else only means something for the 2D map
+            if self.mode != RunModes::TwoDimensional {
+                return;
+            }
             
             // R key to generate random pattern
             if i.key_pressed(egui::Key::R) && !self.running {
-                self.map.gen_random();
-                if self.first_run {
-                    self.map.cache_initial_state();
-                }
+                self.map.soup.seed = soup::new_seed();
+                self.new_soup();
             }
             
             // C key to clear
@@ -175,7 +295,130 @@ impl ConwaySim {
         // to avoid interfering with UI interactions
     }
 
+    /// Steps and draws the elementary mode, in place of the 2D map
+    fn update_elementary(&mut self, ctx: &egui::Context) {
+        if self.running && self.elementary.pace.due() {
+            self.elementary.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            elementary_mode::diagram(ui, &mut self.elementary, self.running);
+        });
+    }
+
+    /// Steps and draws the Wireworld mode, in place of the 2D map
+    fn update_wireworld(&mut self, ctx: &egui::Context) {
+        if self.running && self.wireworld.pace.due() {
+            self.wireworld.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            wireworld_mode::board(
+                ui,
+                &mut self.wireworld,
+                &mut self.wireworld_view,
+                self.running,
+            );
+        });
+    }
+
+    /// Steps and draws the turmites mode, in place of the 2D map
+    fn update_turmites(&mut self, ctx: &egui::Context) {
+        if self.running {
+            self.turmites.update();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            turmite_mode::grid(ui, &mut self.turmites, &mut self.turmite_view, self.running);
+        });
+    }
+
+    /// Steps and draws the Margolus block mode, in place of the 2D map
+    fn update_margolus(&mut self, ctx: &egui::Context) {
+        if self.running && self.margolus.pace.due() {
+            self.margolus.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            margolus_mode::grid(
+                ui,
+                &mut self.margolus,
+                &mut self.margolus_view,
+                self.running,
+            );
+        });
+    }
+
+    /// Steps and draws the Lenia and Smooth Life mode, in place of the 2D map
+    fn update_continuous(&mut self, ctx: &egui::Context) {
+        if self.running && self.continuous.pace.due() {
+            self.continuous.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            continuous_mode::grid(
+                ui,
+                &mut self.continuous,
+                &mut self.continuous_view,
+                self.running,
+            );
+        });
+    }
+
+    /// Steps and draws the cyclic CA and Rock-Paper-Scissors mode, in place of the 2D map
+    fn update_cyclic(&mut self, ctx: &egui::Context) {
+        if self.running && self.cyclic.pace.due() {
+            self.cyclic.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            cyclic_mode::grid(ui, &mut self.cyclic, &mut self.cyclic_view, self.running);
+        });
+    }
+
+    /// Steps and draws the sandpile, on the 2D map's grid and view
+    fn update_sandpile(&mut self, ctx: &egui::Context) {
+        let (width, height) = (
+            self.map.width.max(1) as usize,
+            self.map.height.max(1) as usize,
+        );
+        if self.sandpile.grains().size() != (width, height) {
+            self.sandpile.resize(width, height);
+        }
+        if self.running && self.sandpile.pace.due() {
+            self.sandpile.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            sandpile_mode::grid(
+                ui,
+                &mut self.sandpile,
+                &mut self.sandpile_view,
+                &mut self.map,
+            );
+        });
+    }
+
+    /// Steps and draws the forest fire and SIR epidemic mode, in place of the 2D map
+    fn update_stochastic(&mut self, ctx: &egui::Context) {
+        if self.running && self.stochastic.pace.due() {
+            self.stochastic.step();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            stochastic_mode::grid(
+                ui,
+                &mut self.stochastic,
+                &mut self.stochastic_view,
+                self.running,
+            );
+        });
+    }
+
     fn update_simulation(&mut self, ctx: &egui::Context) {
+        match self.mode {
+            RunModes::Elementary => return self.update_elementary(ctx),
+            RunModes::WireWorld => return self.update_wireworld(ctx),
+            RunModes::Turmites => return self.update_turmites(ctx),
+            RunModes::Margolus => return self.update_margolus(ctx),
+            RunModes::Continuous => return self.update_continuous(ctx),
+            RunModes::Cyclic => return self.update_cyclic(ctx),
+            RunModes::Sandpile => return self.update_sandpile(ctx),
+            RunModes::Stochastic => return self.update_stochastic(ctx),
+            RunModes::TwoDimensional => {}
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             let viewport_rect = ui.available_rect_before_wrap();
             self.viewport.rect = viewport_rect;
@@ -219,9 +462,6 @@ impl ConwaySim {
             }
             
             painter.extend(shapes);
-            if self.running {
-                self.map.update();
-            }
             if self.map.lines {
                 let mut lines = vec![egui::Shape::Noop];
                 self.map.draw_lines(self.rect.unwrap(), &mut lines);
@@ -249,7 +489,7 @@ impl ConwaySim {
                     let delta = response.drag_delta();
                     if delta.length() > 0.0 {
                         // Convert screen delta to grid delta
-                        let grid_delta_x = (delta.x / self.map.cell_size) as i32;
+                        let grid_delta_x = (delta.x / self.map.cell_width()) as i32;
                         let grid_delta_y = (delta.y / self.map.cell_size) as i32;
                         
                         // Update viewport position (invert because we're moving the view)
@@ -259,9 +499,12 @@ impl ConwaySim {
                             self.map.y_axis -= grid_delta_y;
                         } else {
                             // In standard mode, limit panning to reasonable bounds
-                            let max_pan = self.map.map_size * 2; // Allow panning 2x map size in each direction
-                            self.map.x_axis = (self.map.x_axis - grid_delta_x).clamp(-max_pan, max_pan);
-                            self.map.y_axis = (self.map.y_axis - grid_delta_y).clamp(-max_pan, max_pan);
+                            // Allow panning 2x map size in each direction
+                            let (max_pan_x, max_pan_y) = (self.map.width * 2, self.map.height * 2);
+                            self.map.x_axis =
+                                (self.map.x_axis - grid_delta_x).clamp(-max_pan_x, max_pan_x);
+                            self.map.y_axis =
+                                (self.map.y_axis - grid_delta_y).clamp(-max_pan_y, max_pan_y);
                         }
                     }
                 } else if response.hovered() {
@@ -277,8 +520,9 @@ impl ConwaySim {
                         if let Some(pos) = response.interact_pointer_pos() {
                             if let Some(rect) = self.rect {
                                 if let Some(grid_pos) = self.map.screen_to_grid(pos, rect) {
-                                    // Set paint mode: if cell is alive, we'll be erasing; if dead, we'll be painting
-                                    self.paint_mode = !self.map.is_cell_alive(grid_pos);
+                                    // Set paint mode: if cell is already in the paint state, we'll be erasing; otherwise we'll be painting
+                                    self.paint_mode =
+                                        self.map.cell_state(grid_pos) != self.paint_state;
                                     // Paint/erase the first cell
                                     self.paint_cell(grid_pos);
                                 }
@@ -300,7 +544,7 @@ impl ConwaySim {
                         if let Some(pos) = response.interact_pointer_pos() {
                             if let Some(rect) = self.rect {
                                 if let Some(grid_pos) = self.map.screen_to_grid(pos, rect) {
-                                    self.map.toggle_cell(grid_pos);
+                                    self.map.toggle_cell(grid_pos, self.paint_state);
                                 }
                             }
                         }
@@ -323,10 +567,100 @@ impl ConwaySim {
         });
         // Remove the old dragging debug code since we have proper panning now
 
-        if self.view_stats {
-            egui::Window::new("Stats").show(ctx, |ui| {
-                ui.label("TODO :(");
+        self.show_stats_window(ctx);
+    }
+
+    /// Scrolling plots of the recent statistics, and exporting all of them
+    fn show_stats_window(&mut self, ctx: &egui::Context) {
+        if !self.view_stats {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Stats")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let history = self.map.history();
+                let recent = &history[history.len().saturating_sub(STATS_PLOT_WINDOW)..];
+                let series = |name, color, value: fn(&RunStatistics) -> u64| Series {
+                    name,
+                    color,
+                    points: recent
+                        .iter()
+                        .map(|s| [s.generations as f64, value(s) as f64])
+                        .collect(),
+                };
+
+                ui.label(egui::RichText::new("Population").strong());
+                plot::line_plot(
+                    ui,
+                    &[series(
+                        "population",
+                        egui::Color32::from_rgb(66, 133, 244),
+                        |s| s.population,
+                    )],
+                    90.0,
+                );
+                ui.label(egui::RichText::new("Births and deaths").strong());
+                plot::line_plot(
+                    ui,
+                    &[
+                        series("births", egui::Color32::from_rgb(52, 168, 83), |s| s.births),
+                        series("deaths", egui::Color32::from_rgb(234, 67, 53), |s| s.deaths),
+                    ],
+                    90.0,
+                );
+                ui.label(egui::RichText::new("Bounding box").strong());
+                plot::line_plot(
+                    ui,
+                    &[
+                        series("width", egui::Color32::from_rgb(251, 188, 5), |s| {
+                            u64::from(s.bounding_box.0)
+                        }),
+                        series("height", egui::Color32::from_rgb(171, 71, 188), |s| {
+                            u64::from(s.bounding_box.1)
+                        }),
+                    ],
+                    90.0,
+                );
+
+                ui.separator();
+                self.stats_export_controls(ui);
             });
+        self.view_stats &= open;
+    }
+
+    /// Copying or saving the full statistics history as CSV
+    fn stats_export_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("{} updates recorded", self.map.history().len()));
+        ui.horizontal(|ui| {
+            if ui.button("📋 Copy CSV").clicked() {
+                ui.ctx()
+                    .copy_text(RunStatistics::to_csv(self.map.history()));
+                self.stats_status = Some(Ok("Copied to clipboard".to_owned()));
+            }
+            if !cfg!(target_arch = "wasm32") {
+                ui.text_edit_singleline(&mut self.stats_filename);
+                if ui.button("💾 Save CSV").clicked() {
+                    self.stats_status = Some(
+                        std::fs::write(
+                            &self.stats_filename,
+                            RunStatistics::to_csv(self.map.history()),
+                        )
+                        .map(|()| format!("Saved to {}", self.stats_filename))
+                        .map_err(|e| e.to_string()),
+                    );
+                }
+            }
+        });
+        match &self.stats_status {
+            Some(Ok(msg)) => {
+                ui.label(msg);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {err}"));
+            }
+            None => {}
         }
     }
 
@@ -343,11 +677,370 @@ impl ConwaySim {
         self.map.y_axis = 0;
     }
 
+    /// Parse whatever is in the rule text box, and switch the map over to it if it's valid
+    fn apply_rule_text(&mut self) {
+        match self.rule_text.parse::<Rule>() {
+            Ok(rule) => {
+                self.map.rule = rule;
+                self.map.rule_file = None;
+                self.map.clamp_states();
+                self.paint_state = self.paint_state.clamp(1, self.map.states() - 1);
+                self.rule_error = None;
+            }
+            Err(e) => self.rule_error = Some(e.to_string()),
+        }
+    }
+
+    /// Preset picker and rulestring text box for the side panel
+    fn rule_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new("📜 Rule").strong());
+        ui.add_space(6.0);
+
+        let current = PRESETS
+            .iter()
+            .find(|(_, rule)| rule.parse::<Rule>().as_ref() == Ok(&self.map.rule))
+            .map_or("Custom", |(name, _)| *name);
+        egui::ComboBox::from_label("Preset")
+            .selected_text(current)
+            .show_ui(ui, |ui| {
+                for (name, rule) in PRESETS {
+                    if ui.selectable_label(current == *name, *name).clicked() {
+                        self.rule_text = (*rule).to_owned();
+                        self.apply_rule_text();
+                    }
+                }
+            });
+
+        ui.add_space(4.0);
+
+        ui.label("Rulestring");
+        let response = ui.text_edit_singleline(&mut self.rule_text).on_hover_text(
+            "B/S notation like B36/S23, S/B like 23/3, Hensel letters like B2-ak3ijr/S1c23-ak, \
+                 add V for von Neumann or H for hexagonal, /C3 for 3 states. Larger than Life \
+                 rules look like R5,C0,M1,S34..58,B34..45,NM",
+        );
+        if response.changed() {
+            self.apply_rule_text();
+        }
+        if response.lost_focus() && self.rule_error.is_none() {
+            // Tidy up whatever was typed into the canonical form
+            self.rule_text = self.map.rule.to_string();
+        }
+        if let Some(err) = &self.rule_error {
+            ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {err}"));
+        }
+
+        if let Some(rule_file) = &self.map.rule_file {
+            ui.add_space(4.0);
+            let mut unload = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("📄 Running {} from a rule file", rule_file.name()))
+                    .on_hover_text(rule_file.description());
+                unload = ui
+                    .button("✖")
+                    .on_hover_text("Go back to the rulestring")
+                    .clicked();
+            });
+            if unload {
+                self.map.rule_file = None;
+                self.map.clamp_states();
+                self.paint_state = self.paint_state.clamp(1, self.map.states() - 1);
+            }
+        }
+
+        if self.map.states() > 2 {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                let states = self.map.states();
+                ui.label("Paint with state");
+                ui.add(egui::Slider::new(&mut self.paint_state, 1..=states - 1));
+                let (swatch, _) =
+                    ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                ui.painter()
+                    .rect_filled(swatch, 2.0, self.map.cell_color(self.paint_state));
+            })
+            .response
+            .on_hover_text(
+                "1 is alive, under a Generations rule higher states are on their way to dying",
+            );
+        }
+    }
+
+    /// The numbers from the last update, and the button for the stats window
+    fn statistics_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new("📊 Statistics").strong());
+        ui.add_space(6.0);
+        let stats = self.map.stats();
+        egui::Grid::new("statistics").num_columns(2).show(ui, |ui| {
+            ui.label("Generation");
+            ui.label(stats.generations.to_string());
+            ui.end_row();
+            ui.label("Population");
+            ui.label(self.map.cells().len().to_string());
+            ui.end_row();
+            ui.label("Births / deaths");
+            ui.label(format!("{} / {}", stats.births, stats.deaths));
+            ui.end_row();
+            ui.label("Bounding box");
+            ui.label(format!("{}×{}", stats.bounding_box.0, stats.bounding_box.1));
+            ui.end_row();
+        });
+        ui.add_space(4.0);
+        ui.toggle_value(&mut self.view_stats, "📈 Graphs");
+    }
+
+    /// FPS slider, max speed toggle and the speed actually being reached
+    fn speed_controls(&mut self, ui: &mut egui::Ui) {
+        // Speed control with better labeling
+        ui.label("Speed (FPS)");
+        ui.add_enabled(
+            !self.map.max_speed,
+            egui::Slider::new(&mut self.map.fps, 1..=60)
+                .step_by(1.0)
+                .show_value(true),
+        );
+        self.map.update_speed();
+        ui.checkbox(&mut self.map.max_speed, "Max speed")
+            .on_hover_text("Run generations as fast as the CPU allows");
+        if self.running {
+            ui.label(format!("{:.1} gens/s", self.worker.gens_per_sec()));
+        }
+    }
+
+    /// Random soup buttons, the seed they come from and how they're made
+    fn soup_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new("🎲 Generation").strong());
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!self.running, |ui| {
+                if ui
+                    .button("🎲 Random")
+                    .on_hover_text("A new soup from a new seed")
+                    .clicked()
+                {
+                    self.map.soup.seed = soup::new_seed();
+                    self.new_soup();
+                }
+                if ui
+                    .button("🌱 Replay")
+                    .on_hover_text("The soup from the seed below, again")
+                    .clicked()
+                {
+                    self.new_soup();
+                }
+            });
+            if ui.button("🗑 Clear").clicked() {
+                self.map.clear();
+                self.running = false;
+            }
+        });
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.label("Seed");
+            ui.add(egui::TextEdit::singleline(&mut self.map.soup.seed).desired_width(140.0));
+            if ui.button("📋").on_hover_text("Copy the seed").clicked() {
+                ui.ctx().copy_text(self.map.soup.seed.clone());
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut self.map.soup.density, 0.0..=100.0)
+                .suffix("%")
+                .text("Density"),
+        );
+        let (max_width, max_height) = (self.map.width.max(1) as u32, self.map.height.max(1) as u32);
+        ui.add(egui::Slider::new(&mut self.map.soup.width, 1..=max_width).text("Soup width"));
+        ui.add(egui::Slider::new(&mut self.map.soup.height, 1..=max_height).text("Soup height"));
+        egui::ComboBox::from_label("Symmetry")
+            .selected_text(self.map.soup.symmetry.name())
+            .show_ui(ui, |ui| {
+                for symmetry in Symmetry::ALL {
+                    ui.selectable_value(&mut self.map.soup.symmetry, symmetry, symmetry.name())
+                        .on_hover_text(symmetry.about());
+                }
+            });
+        let soup = &self.map.soup;
+        if soup.symmetry.is_square() && soup.width != soup.height {
+            ui.small("💡 C4 and D8 soups get cut down to a square");
+        }
+    }
+
+    /// Fill the map from the soup settings, keeping it as the state to reset to before the first run
+    fn new_soup(&mut self) {
+        self.map.gen_random();
+        if self.first_run {
+            self.map.cache_initial_state();
+        }
+    }
+
+    /// Engine picker and how many generations each update jumps
+    fn engine_controls(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Engine")
+            .selected_text(self.map.engine.name())
+            .show_ui(ui, |ui| {
+                for engine in EngineKind::ALL {
+                    ui.selectable_value(&mut self.map.engine, engine, engine.name());
+                }
+            });
+        if !self.map.engine_supported() {
+            let engine = self.map.engine.name();
+            let ignored = if self.map.rule_file.is_some() {
+                Some("rule files")
+            } else if self.map.rule.is_generations() {
+                Some("Generations rules")
+            } else if self.map.rule.range().is_some() {
+                Some("Larger than Life rules")
+            } else {
+                None
+            };
+            let warning = match ignored {
+                Some(rules) => {
+                    format!("⚠ {engine} is ignored, {rules} always run on the sparse set")
+                }
+                None => {
+                    format!("⚠ {engine} can't run this rule with these edges, using the sparse set")
+                }
+            };
+            ui.colored_label(ui.visuals().warn_fg_color, warning).on_hover_text(
+                "Only the sparse set handles more than two states and wide neighborhoods. Hashlife \
+                 needs an unbounded plane, and B0 rules on an unbounded plane only run on the \
+                 sparse set",
+            );
+        }
+
+        let max_step = self.map.engine.max_step_log2();
+        self.map.step_log2 = self.map.step_log2.min(max_step);
+        ui.add(
+            egui::Slider::new(&mut self.map.step_log2, 0..=max_step)
+                .text("Step (2^k)")
+                .custom_formatter(|k, _| format!("{}", 1u64 << k as u32)),
+        )
+        .on_hover_text("Generations computed per update");
+    }
+
+    /// Width/height sliders plus the anchor that decides where the pattern ends up on resize
+    fn board_size_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!(
+            "Board Size ({}×{})",
+            self.map.width, self.map.height
+        ));
+        let (mut width, mut height) = (self.map.width, self.map.height);
+        ui.add(egui::Slider::new(&mut width, 10..=500).text("Width"));
+        ui.add(egui::Slider::new(&mut height, 10..=500).text("Height"));
+        if (width, height) != (self.map.width, self.map.height) {
+            self.map.resize(width, height, self.resize_anchor);
+        }
+
+        egui::ComboBox::from_label("Anchor")
+            .selected_text(self.resize_anchor.name())
+            .show_ui(ui, |ui| {
+                for anchor in ResizeAnchor::ALL {
+                    ui.selectable_value(&mut self.resize_anchor, anchor, anchor.name());
+                }
+            })
+            .response
+            .on_hover_text("Which part of the pattern stays in place when the board is resized");
+    }
+
+    /// Read the pattern file named in the File menu and put it on the map, the format is worked
+    /// out from the contents of the file
+    fn import_pattern(&mut self) {
+        let pattern = pattern_io::load(&self.filename).map_err(|e| e.to_string());
+        // WireWorld circuits go to their own mode, the 2D map can't run them
+        if let Ok(pattern) = &pattern {
+            if pattern
+                .rule
+                .as_deref()
+                .is_some_and(wireworld::is_wireworld_rule)
+            {
+                self.wireworld.load_pattern(pattern);
+                self.mode = RunModes::WireWorld;
+                self.running = false;
+                self.file_status = Some(Ok(format!(
+                    "Loaded {}",
+                    pattern.name.as_deref().unwrap_or(&self.filename)
+                )));
+                return;
+            }
+        }
+        self.file_status = Some(pattern.and_then(|pattern| {
+            self.find_rule_file(&pattern);
+            self.map
+                .load_pattern(&pattern)
+                .map_err(|e| format!("unsupported rule: {e}"))?;
+            Ok(format!(
+                "Loaded {}",
+                pattern.name.as_deref().unwrap_or(&self.filename)
+            ))
+        }));
+        if matches!(self.file_status, Some(Ok(_))) {
+            self.running = false;
+            self.rule_text = self.map.rule.to_string();
+            self.rule_error = None;
+            self.paint_state = self.paint_state.clamp(1, self.map.states() - 1);
+            self.map.cache_initial_state();
+            self.mode = RunModes::TwoDimensional;
+        }
+    }
+
+    /// Patterns for a custom rule name it rather than spelling it out. Like Golly, look for its
+    /// rule file next to the pattern when the name isn't a rulestring or the rule already loaded
+    fn find_rule_file(&mut self, pattern: &crate::pattern::Pattern) {
+        let Some(rule) = &pattern.rule else {
+            return;
+        };
+        let loaded = self
+            .map
+            .rule_file
+            .as_ref()
+            .is_some_and(|file| file.name().eq_ignore_ascii_case(rule));
+        if loaded || rule.parse::<Rule>().is_ok() {
+            return;
+        }
+        let path = std::path::Path::new(&self.filename).with_file_name(format!("{rule}.rule"));
+        if path.exists() {
+            // A broken rule file shows up as the pattern's rule not being supported
+            self.read_rule_file(&path).ok();
+        }
+    }
+
+    /// Read the Golly rule file named in the File menu and run the map on it
+    fn import_rule_file(&mut self) {
+        let path = self.rule_filename.clone();
+        self.file_status = Some(self.read_rule_file(std::path::Path::new(&path)));
+        if matches!(self.file_status, Some(Ok(_))) {
+            self.running = false;
+            self.mode = RunModes::TwoDimensional;
+        }
+    }
+
+    fn read_rule_file(&mut self, path: &std::path::Path) -> Result<String, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let rule_file = RuleFile::parse(&source).map_err(|e| e.to_string())?;
+        let status = format!("Loaded rule {}", rule_file.name());
+        self.map.rule_file = Some(rule_file);
+        self.map.clamp_states();
+        self.paint_state = self.paint_state.clamp(1, self.map.states() - 1);
+        Ok(status)
+    }
+
+    /// Write whatever is on the map to the file named in the File menu, in the chosen format
+    fn export_pattern(&mut self) {
+        let pattern = match self.mode {
+            RunModes::WireWorld => self.wireworld.to_pattern(),
+            _ => self.map.to_pattern(),
+        };
+        self.file_status = Some(
+            pattern_io::save(&self.filename, &pattern, self.export_format)
+                .map(|()| format!("Saved to {}", self.filename))
+                .map_err(|e| e.to_string()),
+        );
+    }
+
     /// Paint or erase a cell based on the current paint mode
     fn paint_cell(&mut self, grid_pos: crate::Pos) {
         if self.paint_mode {
-            // Paint mode: make cell alive
-            self.map.set_cell_alive(grid_pos);
+            // Paint mode: put the cell in the chosen state
+            self.map.set_cell_state(grid_pos, self.paint_state);
         } else {
             // Erase mode: make cell dead
             self.map.set_cell_dead(grid_pos);
@@ -366,6 +1059,38 @@ impl UserInterface for ConwaySim {
                     ui.add_space(8.0);
                     ui.heading("🧬 Cellular Automata");
                     ui.add_space(12.0);
+                    if self.mode == RunModes::Elementary {
+                        elementary_mode::side_panel(ui, &mut self.elementary, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::WireWorld {
+                        wireworld_mode::side_panel(ui, &mut self.wireworld, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::Turmites {
+                        turmite_mode::side_panel(ui, &mut self.turmites, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::Margolus {
+                        margolus_mode::side_panel(ui, &mut self.margolus, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::Continuous {
+                        continuous_mode::side_panel(ui, &mut self.continuous, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::Cyclic {
+                        cyclic_mode::side_panel(ui, &mut self.cyclic, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::Sandpile {
+                        sandpile_mode::side_panel(ui, &mut self.sandpile, &mut self.running);
+                        return;
+                    }
+                    if self.mode == RunModes::Stochastic {
+                        stochastic_mode::side_panel(ui, &mut self.stochastic, &mut self.running);
+                        return;
+                    }
                     
                     // Simulation Controls Section
                     ui.group(|ui| {
@@ -383,58 +1108,22 @@ impl UserInterface for ConwaySim {
                         
                         ui.add_space(8.0);
                         
-                        // Speed control with better labeling
-                        ui.label("Speed (FPS)");
-                        ui.add(
-                            egui::Slider::new(&mut self.map.fps, 1..=60)
-                                .step_by(1.0)
-                                .show_value(true)
-                        );
-                        self.map.update_speed();
+                        self.speed_controls(ui);
+
+                        ui.add_space(8.0);
+                        self.engine_controls(ui);
                     });
                     
+                    ui.add_space(8.0);
+
+                    // Rule Section
+                    ui.group(|ui| self.rule_controls(ui));
+
                     ui.add_space(8.0);
                     
                     // Generation Controls Section
-                    ui.group(|ui| {
-                        ui.label(egui::RichText::new("🎲 Generation").strong());
-                        ui.add_space(6.0);
-                        
-                        ui.horizontal(|ui| {
-                            ui.add_enabled_ui(!self.running, |ui| {
-                                if ui.add(egui::Button::new("🎲 Random")).clicked() {
-                                    self.map.gen_random();
-                                    if self.first_run {
-                                        self.map.cache_initial_state();
-                                    }
-                                }
-                            });
-                            
-                            if ui.add(egui::Button::new("🗑 Clear")).clicked() {
-                                self.map.clear();
-                                self.running = false;
-                            }
-                        });
-                        
-                        ui.add_space(4.0);
-                        
-                        // Scarcity control
-                        ui.label("Cell Density");
-                        ui.add(
-                            egui::Slider::new(&mut self.map.rand_scarcity, 0..=10)
-                                .step_by(1.0)
-                                .show_value(false)
-                                .custom_formatter(|n, _| {
-                                    match n as i32 {
-                                        0..=2 => "Dense".to_string(),
-                                        3..=5 => "Medium".to_string(),
-                                        6..=8 => "Sparse".to_string(),
-                                        _ => "Very Sparse".to_string(),
-                                    }
-                                })
-                        );
-                    });
-                    
+                    ui.group(|ui| self.soup_controls(ui));
+
                     ui.add_space(8.0);
                     
                     // State Management Section
@@ -484,13 +1173,7 @@ impl UserInterface for ConwaySim {
                         ui.add_space(4.0);
                         
                         // Board size
-                        ui.label("Board Size");
-                        ui.add(
-                            egui::Slider::new(&mut self.map.map_size, 10..=500)
-                                .step_by(1.0)
-                                .show_value(true)
-                                .custom_formatter(|n, _| format!("{}×{}", n as i32, n as i32))
-                        );
+                        self.board_size_controls(ui);
                         
                         ui.add_space(8.0);
                         
@@ -513,10 +1196,34 @@ impl UserInterface for ConwaySim {
                         
                         ui.add_space(4.0);
                         
+                        // Edge topology
+                        egui::ComboBox::from_label("Edges")
+                            .selected_text(self.map.boundary.name())
+                            .show_ui(ui, |ui| {
+                                for boundary in Boundary::ALL {
+                                    ui.selectable_value(
+                                        &mut self.map.boundary,
+                                        boundary,
+                                        boundary.name(),
+                                    );
+                                }
+                            });
+
+                        ui.add_space(4.0);
+
                         // Toroidal display toggle
                         let old_toroidal = self.map.toroidal_display;
-                        if ui.checkbox(&mut self.map.toroidal_display, "🌐 Toroidal Display")
-                            .on_hover_text("Show the map wrapping infinitely (matches simulation behavior)")
+                        if ui
+                            .add_enabled(
+                                self.map.boundary.is_wrapping(),
+                                egui::Checkbox::new(
+                                    &mut self.map.toroidal_display,
+                                    "🌐 Toroidal Display",
+                                ),
+                            )
+                            .on_hover_text(
+                                "Show the map wrapping infinitely (matches simulation behavior)",
+                            )
                             .changed() 
                         {
                             // If we're turning off toroidal mode, reset to center
@@ -569,14 +1276,9 @@ impl UserInterface for ConwaySim {
                         }
                     });
                     
-                    // Statistics section (placeholder for future)
+                    // Statistics section
                     ui.add_space(8.0);
-                    ui.group(|ui| {
-                        ui.label(egui::RichText::new("📊 Statistics").strong());
-                        ui.add_space(6.0);
-                        ui.label("Coming soon...");
-                        // TODO: Add generation count, population, etc.
-                    });
+                    ui.group(|ui| self.statistics_controls(ui));
                 });
             });
     }
@@ -590,6 +1292,18 @@ impl UserInterface for ConwaySim {
                     // App title and status
                     ui.label(egui::RichText::new("🧬 Cellular Automata").strong().size(16.0));
                     
+                    ui.separator();
+
+                    // Mode tabs, switching pauses whatever was running
+                    for mode in RunModes::ALL {
+                        if ui
+                            .selectable_value(&mut self.mode, mode, mode.name())
+                            .changed()
+                        {
+                            self.running = false;
+                        }
+                    }
+
                     ui.separator();
                     
                     // Status indicator
@@ -613,7 +1327,13 @@ impl UserInterface for ConwaySim {
                     
                     // FPS display
                     ui.separator();
-                    ui.label(format!("⚡ {}fps", self.map.fps));
+                    if self.running {
+                        ui.label(format!("⚡ {:.0} gens/s", self.worker.gens_per_sec()));
+                    } else if self.map.max_speed {
+                        ui.label("⚡ max speed");
+                    } else {
+                        ui.label(format!("⚡ {}fps", self.map.fps));
+                    }
                     
                     // Push remaining items to the right
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -637,11 +1357,42 @@ impl UserInterface for ConwaySim {
                         if !is_web {
                             ui.separator();
                             ui.menu_button("📁 File", |ui| {
+                                ui.label("Pattern file (.rle, .cells, .lif, .mcl)");
+                                ui.text_edit_singleline(&mut self.filename);
+                                egui::ComboBox::from_label("Export as")
+                                    .selected_text(self.export_format.name())
+                                    .show_ui(ui, |ui| {
+                                        for format in Format::ALL {
+                                            ui.selectable_value(
+                                                &mut self.export_format,
+                                                format,
+                                                format.name(),
+                                            );
+                                        }
+                                    });
                                 if ui.button("💾 Export Pattern").clicked() {
-                                    // TODO: Export current pattern
+                                    self.export_pattern();
                                 }
                                 if ui.button("📂 Import Pattern").clicked() {
-                                    // TODO: Import pattern
+                                    self.import_pattern();
+                                }
+                                ui.separator();
+                                ui.label("Golly rule file (.rule)");
+                                ui.text_edit_singleline(&mut self.rule_filename);
+                                if ui.button("📜 Load Rule File").clicked() {
+                                    self.import_rule_file();
+                                }
+                                match &self.file_status {
+                                    Some(Ok(msg)) => {
+                                        ui.label(msg);
+                                    }
+                                    Some(Err(err)) => {
+                                        ui.colored_label(
+                                            ui.visuals().error_fg_color,
+                                            format!("⚠ {err}"),
+                                        );
+                                    }
+                                    None => {}
                                 }
                                 ui.separator();
                                 if ui.button("❌ Quit").clicked() {
@@ -670,10 +1421,17 @@ impl eframe::App for ConwaySim {
         self.handle_mouse_events(ctx);
 
         self.map.light_mode = ctx.style().visuals == egui::Visuals::light();
-        ctx.request_repaint();
+        if self.running {
+            // New generations keep arriving from the worker, so keep drawing them
+            ctx.request_repaint();
+        }
         
         self.update_side_panel(ctx);
         self.update_menu_bar(ctx);
+        self.worker.sync(
+            &mut self.map,
+            self.running && self.mode == RunModes::TwoDimensional,
+        );
         self.update_simulation(ctx);
         
         // Show help and about dialogs if requested
@@ -0,0 +1,68 @@
+//! Colors for states between 0 and 1
+
+use egui::Color32;
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Magma,
+    Jet,
+    Grayscale,
+}
+
+impl Colormap {
+    pub const ALL: [Self; 4] = [Self::Viridis, Self::Magma, Self::Jet, Self::Grayscale];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Viridis => "Viridis",
+            Self::Magma => "Magma",
+            Self::Jet => "Jet",
+            Self::Grayscale => "Grayscale",
+        }
+    }
+
+    /// Evenly spaced colors from 0 to 1, blended in between
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            Self::Viridis => &[
+                [68, 1, 84],
+                [59, 82, 139],
+                [33, 145, 140],
+                [94, 201, 98],
+                [253, 231, 37],
+            ],
+            Self::Magma => &[
+                [0, 0, 4],
+                [81, 18, 124],
+                [183, 55, 121],
+                [252, 137, 97],
+                [252, 253, 191],
+            ],
+            Self::Jet => &[
+                [0, 0, 128],
+                [0, 0, 255],
+                [0, 255, 255],
+                [255, 255, 0],
+                [255, 0, 0],
+                [128, 0, 0],
+            ],
+            Self::Grayscale => &[[0, 0, 0], [255, 255, 255]],
+        }
+    }
+
+    /// The color for `value`, which gets clamped to between 0 and 1
+    pub fn color(self, value: f32) -> Color32 {
+        let stops = self.stops();
+        let position = value.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let below = (position as usize).min(stops.len() - 2);
+        let t = position - below as f32;
+        let (from, to) = (stops[below], stops[below + 1]);
+        let blend =
+            |i: usize| (f32::from(from[i]) + (f32::from(to[i]) - f32::from(from[i])) * t) as u8;
+        Color32::from_rgb(blend(0), blend(1), blend(2))
+    }
+}
@@ -0,0 +1,191 @@
+//! A plain radix-2 fast Fourier transform, and convolution on a wrapped grid with it
+//!
+//! Convolving a `n`×`n` grid with a kernel as wide as it costs O(n⁴) done directly, which is
+//! hopeless for Lenia's big kernels. Multiplying in frequency space instead costs O(n² log n):
+//! transform the grid, multiply it point by point with the kernel's transform (which only has to
+//! be worked out once), and transform back. Convolving like this wraps around the edges, which
+//! is just what a torus wants anyway.
+//!
+//! Sizes have to be powers of two.
+
+use std::f32::consts::TAU;
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const ZERO: Self = Self { re: 0.0, im: 0.0 };
+
+    pub fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    /// The point at `angle` radians round the unit circle
+    pub fn from_angle(angle: f32) -> Self {
+        Self::new(angle.cos(), angle.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// Transforms of one length, with the twiddle factors and bit reversal worked out up front
+#[derive(Clone, Debug)]
+pub struct Fft {
+    len: usize,
+    /// `e^(-2πik/len)` for the first half of `k`
+    twiddles: Vec<Complex>,
+    /// Where every index goes in the bit reversed order the butterflies want
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    /// Transforms of `len` numbers, which must be a power of two
+    pub fn new(len: usize) -> Self {
+        assert!(
+            len.is_power_of_two(),
+            "FFT length {len} isn't a power of two"
+        );
+        let bits = len.trailing_zeros();
+        let reversed = (0..len)
+            .map(|i| {
+                i.reverse_bits()
+                    .checked_shr(usize::BITS - bits)
+                    .unwrap_or(0)
+            })
+            .collect();
+        let twiddles = (0..len / 2)
+            .map(|k| Complex::from_angle(-TAU * k as f32 / len as f32))
+            .collect();
+        Self {
+            len,
+            twiddles,
+            reversed,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Transform `data` in place. The inverse leaves out the `1/len` scaling
+    pub fn transform(&self, data: &mut [Complex], inverse: bool) {
+        debug_assert_eq!(
+            data.len(),
+            self.len,
+            "data is the wrong length for this FFT"
+        );
+        for (i, &j) in self.reversed.iter().enumerate() {
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+        let mut half = 1;
+        while half < self.len {
+            let stride = self.len / (2 * half);
+            for start in (0..self.len).step_by(2 * half) {
+                for k in 0..half {
+                    let mut twiddle = self.twiddles[k * stride];
+                    if inverse {
+                        twiddle.im = -twiddle.im;
+                    }
+                    let (a, b) = (data[start + k], data[start + k + half] * twiddle);
+                    data[start + k] = a + b;
+                    data[start + k + half] = a - b;
+                }
+            }
+            half *= 2;
+        }
+    }
+}
+
+/// Transforms of a `size`×`size` grid, rows first and then columns
+#[derive(Clone, Debug)]
+pub struct Fft2d {
+    size: usize,
+    fft: Fft,
+}
+
+impl Fft2d {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            fft: Fft::new(size),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Transform a grid stored row by row in place, scaling the inverse so it round trips
+    pub fn transform(&self, data: &mut [Complex], inverse: bool) {
+        let n = self.size;
+        for row in data.chunks_mut(n) {
+            self.fft.transform(row, inverse);
+        }
+        let mut column = vec![Complex::ZERO; n];
+        for x in 0..n {
+            for (y, c) in column.iter_mut().enumerate() {
+                *c = data[y * n + x];
+            }
+            self.fft.transform(&mut column, inverse);
+            for (y, c) in column.iter().enumerate() {
+                data[y * n + x] = *c;
+            }
+        }
+        if inverse {
+            let scale = 1.0 / (n * n) as f32;
+            for c in data {
+                *c = Complex::new(c.re * scale, c.im * scale);
+            }
+        }
+    }
+
+    /// The transform of a real grid
+    pub fn forward_real(&self, grid: &[f32]) -> Vec<Complex> {
+        let mut data: Vec<Complex> = grid.iter().map(|&v| Complex::new(v, 0.0)).collect();
+        self.transform(&mut data, false);
+        data
+    }
+
+    /// Convolve the grid whose transform is `grid` with the kernel whose transform is `kernel`,
+    /// giving back real values
+    pub fn convolve(&self, grid: &[Complex], kernel: &[Complex]) -> Vec<f32> {
+        let mut product: Vec<Complex> = grid.iter().zip(kernel).map(|(&g, &k)| g * k).collect();
+        self.transform(&mut product, true);
+        product.into_iter().map(|c| c.re).collect()
+    }
+}
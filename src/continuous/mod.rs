@@ -0,0 +1,418 @@
+//! Continuous automata: Lenia and Smooth Life
+//!
+//! Rather than being alive or dead, every cell holds a state anywhere from 0 to 1, and rather than
+//! counting a handful of neighbors each cell takes a weighted average of everything within a
+//! radius of it, the kernel. How that average compares to what the rule likes decides how the cell
+//! changes.
+//!
+//! In Lenia the kernel is one or more smooth rings, the peaks, and a bell shaped growth function of
+//! the average gets added to every cell a small time step `dt` at a time. Smooth Life averages over
+//! a disk and the ring around it separately, and treats them like a cell and its neighbors in Life
+//! with the counts smoothed out.
+//!
+//! Kernels are dozens of cells across, so the averages are taken as convolutions with an FFT, see
+//! [`fft`]. That needs the grid to be square with a power of two side, and makes it a torus.
+
+use rand::Rng;
+
+use crate::continuous::fft::{Complex, Fft2d};
+use crate::grid::Grid;
+use crate::timing::Pace;
+
+pub mod colormap;
+pub mod fft;
+pub mod presets;
+
+pub use colormap::Colormap;
+pub use presets::{PRESETS, Preset, Start};
+
+pub const DEFAULT_SIZE: usize = 256;
+pub const MIN_SIZE: usize = 32;
+pub const MAX_SIZE: usize = 1024;
+
+/// Which kind of continuous automaton is running
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Kind {
+    #[default]
+    Lenia,
+    SmoothLife,
+}
+
+impl Kind {
+    pub const ALL: [Self; 2] = [Self::Lenia, Self::SmoothLife];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lenia => "Lenia",
+            Self::SmoothLife => "SmoothLife",
+        }
+    }
+}
+
+/// Lenia's parameters
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Lenia {
+    /// How far the kernel reaches, in cells
+    pub radius: u32,
+    /// How strong each ring of the kernel is, from the middle out
+    pub peaks: Vec<f32>,
+    /// The average the growth function likes best
+    pub mu: f32,
+    /// How far from `mu` the average can be before cells start shrinking
+    pub sigma: f32,
+    /// How much of the growth gets added each step
+    pub dt: f32,
+}
+
+impl Default for Lenia {
+    fn default() -> Self {
+        PRESETS[0].lenia()
+    }
+}
+
+impl Lenia {
+    /// The kernel for a `size`×`size` grid, centred on the top left cell and wrapping round, adding
+    /// up to 1
+    pub fn kernel(&self, size: usize) -> Vec<f32> {
+        let radius = self.radius.max(1) as f32;
+        let rings = self.peaks.len().max(1);
+        kernel(size, self.radius as usize, |distance| {
+            let r = distance / radius;
+            if r >= 1.0 {
+                return 0.0;
+            }
+            // Each ring is the same exponential bump, scaled by its peak
+            let ring = r * rings as f32;
+            let peak = self.peaks.get(ring as usize).copied().unwrap_or(1.0);
+            peak * bump(ring.fract())
+        })
+    }
+
+    /// How much a cell with `average` around it grows, from -1 to 1
+    pub fn growth(&self, average: f32) -> f32 {
+        let sigma = self.sigma.max(f32::EPSILON);
+        2.0 * (-(average - self.mu).powi(2) / (2.0 * sigma * sigma)).exp() - 1.0
+    }
+}
+
+/// The exponential bump Lenia's kernel rings are made of, 1 at the middle and 0 at both ends
+fn bump(r: f32) -> f32 {
+    if r <= 0.0 || r >= 1.0 {
+        return 0.0;
+    }
+    (4.0 - 1.0 / (r * (1.0 - r))).exp()
+}
+
+/// Smooth Life's parameters, named after Rafler's paper where there's a choice
+#[derive(Copy, Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SmoothLife {
+    /// The disk that stands in for the cell itself
+    pub inner_radius: f32,
+    /// The ring round it that stands in for the neighbors
+    pub outer_radius: f32,
+    /// How full the ring has to be for a dead cell to come alive
+    pub birth: (f32, f32),
+    /// How full the ring has to be for a live cell to stay alive
+    pub survival: (f32, f32),
+    /// How soft the edges of the birth and survival ranges are, `α_n`
+    pub outer_smoothness: f32,
+    /// How soft the line between dead and alive is, `α_m`
+    pub inner_smoothness: f32,
+    /// At 1 cells jump straight to their new state, below it they move a step towards it
+    pub dt: f32,
+}
+
+impl Default for SmoothLife {
+    fn default() -> Self {
+        presets::SMOOTH_LIFE_GLIDERS
+    }
+}
+
+/// A step from 0 to 1 around `at`, about `width` wide
+fn sigmoid(x: f32, at: f32, width: f32) -> f32 {
+    1.0 / (1.0 + (-(x - at) * 4.0 / width.max(f32::EPSILON)).exp())
+}
+
+impl SmoothLife {
+    /// The disk and the ring round it for a `size`×`size` grid, each adding up to 1, with their
+    /// edges anti-aliased
+    pub fn kernels(&self, size: usize) -> [Vec<f32>; 2] {
+        let (inner, outer) = (self.inner_radius, self.outer_radius.max(self.inner_radius));
+        let reach = outer.ceil() as usize + 1;
+        // How much of a cell `distance` away falls inside a circle of `radius`
+        let inside = |distance: f32, radius: f32| (radius + 0.5 - distance).clamp(0.0, 1.0);
+        [
+            kernel(size, reach, |d| inside(d, inner)),
+            kernel(size, reach, |d| inside(d, outer) - inside(d, inner)),
+        ]
+    }
+
+    /// What a cell becomes with its disk `inner` full and its ring `outer` full
+    pub fn transition(&self, outer: f32, inner: f32) -> f32 {
+        let alive = sigmoid(inner, 0.5, self.inner_smoothness);
+        let low = self.birth.0 * (1.0 - alive) + self.survival.0 * alive;
+        let high = self.birth.1 * (1.0 - alive) + self.survival.1 * alive;
+        sigmoid(outer, low, self.outer_smoothness)
+            * (1.0 - sigmoid(outer, high, self.outer_smoothness))
+    }
+}
+
+/// A kernel `weight` of the distance from the middle, for a `size`×`size` grid wrapping round so
+/// the middle is the top left cell. Only cells up to `reach` away get looked at
+fn kernel(size: usize, reach: usize, weight: impl Fn(f32) -> f32) -> Vec<f32> {
+    let mut kernel = vec![0.0; size * size];
+    let reach = reach.min(size / 2) as isize;
+    for dy in -reach..=reach {
+        for dx in -reach..=reach {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt();
+            let (x, y) = (
+                dx.rem_euclid(size as isize) as usize,
+                dy.rem_euclid(size as isize) as usize,
+            );
+            kernel[y * size + x] += weight(distance);
+        }
+    }
+    let total: f32 = kernel.iter().sum();
+    if total > 0.0 {
+        kernel.iter_mut().for_each(|k| *k /= total);
+    }
+    kernel
+}
+
+/// What the kernel transforms were worked out for, so they only get redone when it changes
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+    Lenia(u32, Vec<f32>),
+    SmoothLife(f32, f32),
+}
+
+/// The transforms of the kernels for one grid size and shape
+struct Kernels {
+    shape: Shape,
+    fft: Fft2d,
+    transforms: Vec<Vec<Complex>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Continuous {
+    pub kind: Kind,
+    pub lenia: Lenia,
+    pub smooth_life: SmoothLife,
+    pub colormap: Colormap,
+    /// Every cell, from 0 to 1. Always square, and a power of two across
+    grid: Grid<f32>,
+    /// Steps per second
+    pub pace: Pace,
+    #[serde(skip)]
+    kernels: Option<Kernels>,
+    #[serde(skip)]
+    generation: u64,
+}
+
+impl Default for Continuous {
+    fn default() -> Self {
+        Self::new(Kind::default(), DEFAULT_SIZE)
+    }
+}
+
+impl Continuous {
+    /// An empty grid `size` cells square, rounded up to a power of two
+    pub fn new(kind: Kind, size: usize) -> Self {
+        let mut continuous = Self {
+            kind,
+            lenia: Lenia::default(),
+            smooth_life: SmoothLife::default(),
+            colormap: Colormap::default(),
+            grid: Grid::default(),
+            pace: Pace::new(30),
+            kernels: None,
+            generation: 0,
+        };
+        continuous.resize(size);
+        continuous
+    }
+
+    /// The side of the grid, which is always a power of two
+    pub fn size(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Every cell, from 0 to 1
+    pub fn grid(&self) -> &Grid<f32> {
+        &self.grid
+    }
+
+    /// Set the cell at column `x` of row `y`, anything past the edges is ignored
+    pub fn set_cell(&mut self, x: usize, y: usize, value: f32) {
+        self.grid.set(x, y, value.clamp(0.0, 1.0));
+    }
+
+    /// The sum of every cell, which Lenia's creatures keep roughly steady as they move
+    pub fn mass(&self) -> f32 {
+        self.grid.cells().iter().sum()
+    }
+
+    /// Change the size of the grid, wiping it. The size gets rounded up to a power of two
+    pub fn resize(&mut self, size: usize) {
+        let size = size.clamp(MIN_SIZE, MAX_SIZE).next_power_of_two();
+        self.grid = Grid::new(size, size);
+        self.generation = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.grid.clear();
+        self.generation = 0;
+    }
+
+    /// Set every cell within `radius` of (`x`, `y`) to `value`, wrapping round the edges
+    pub fn paint(&mut self, x: usize, y: usize, radius: f32, value: f32) {
+        let reach = radius.ceil() as isize;
+        let size = self.size() as isize;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if ((dx * dx + dy * dy) as f32).sqrt() <= radius {
+                    let (cx, cy) = (
+                        (x as isize + dx).rem_euclid(size) as usize,
+                        (y as isize + dy).rem_euclid(size) as usize,
+                    );
+                    self.set_cell(cx, cy, value);
+                }
+            }
+        }
+    }
+
+    /// Put `creature` down with its middle at (`x`, `y`), wrapping round the edges
+    pub fn place(&mut self, creature: &[&[f32]], x: usize, y: usize) {
+        let height = creature.len();
+        let width = creature.iter().map(|row| row.len()).max().unwrap_or(0);
+        let (x0, y0) = (
+            (x + self.size() - width / 2 % self.size()) % self.size(),
+            (y + self.size() - height / 2 % self.size()) % self.size(),
+        );
+        for (dy, row) in creature.iter().enumerate() {
+            for (dx, &value) in row.iter().enumerate() {
+                self.set_cell((x0 + dx) % self.size(), (y0 + dy) % self.size(), value);
+            }
+        }
+    }
+
+    /// Wipe the grid and fill it with noise the right sort for the kind running: a patch of random
+    /// states for Lenia, and scattered full squares the size of the ring for Smooth Life
+    pub fn randomize(&mut self, rng: &mut impl Rng) {
+        self.clear();
+        match self.kind {
+            Kind::Lenia => {
+                let patch = (self.lenia.radius as usize * 6).min(self.size() / 2);
+                let start = (self.size() - patch) / 2;
+                for y in start..start + patch {
+                    for x in start..start + patch {
+                        self.grid.set(x, y, rng.r#gen());
+                    }
+                }
+            }
+            Kind::SmoothLife => {
+                let side = (self.smooth_life.outer_radius as usize).max(1);
+                let squares = (self.size() * self.size()) / (side * side * 4);
+                for _ in 0..squares {
+                    let (x, y) = (rng.gen_range(0..self.size()), rng.gen_range(0..self.size()));
+                    for dy in 0..side {
+                        for dx in 0..side {
+                            self.set_cell((x + dx) % self.size(), (y + dy) % self.size(), 1.0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Switch to `preset`'s rule and start it off the way it says
+    pub fn load_preset(&mut self, preset: &Preset, rng: &mut impl Rng) {
+        self.kind = preset.kind;
+        self.lenia = preset.lenia();
+        self.smooth_life = preset.smooth_life;
+        match preset.start {
+            Start::Creature(creature) => {
+                self.clear();
+                self.place(creature, self.size() / 2, self.size() / 2);
+            }
+            Start::Soup => self.randomize(rng),
+        }
+    }
+
+    /// What shape the kernels should be for the kind running
+    fn shape(&self) -> Shape {
+        match self.kind {
+            Kind::Lenia => Shape::Lenia(self.lenia.radius, self.lenia.peaks.clone()),
+            Kind::SmoothLife => {
+                Shape::SmoothLife(self.smooth_life.inner_radius, self.smooth_life.outer_radius)
+            }
+        }
+    }
+
+    /// The kernel transforms, worked out again if the size or shape has changed since last time
+    fn refresh_kernels(&mut self) {
+        let shape = self.shape();
+        let stale = self
+            .kernels
+            .as_ref()
+            .is_none_or(|k| k.shape != shape || k.fft.size() != self.size());
+        if stale {
+            let fft = Fft2d::new(self.size());
+            let kernels = match self.kind {
+                Kind::Lenia => vec![self.lenia.kernel(self.size())],
+                Kind::SmoothLife => self.smooth_life.kernels(self.size()).to_vec(),
+            };
+            let transforms = kernels.iter().map(|k| fft.forward_real(k)).collect();
+            self.kernels = Some(Kernels {
+                shape,
+                fft,
+                transforms,
+            });
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.refresh_kernels();
+        let Some(kernels) = &self.kernels else {
+            return;
+        };
+        let transform = kernels.fft.forward_real(self.grid.cells());
+        let averages: Vec<Vec<f32>> = kernels
+            .transforms
+            .iter()
+            .map(|kernel| kernels.fft.convolve(&transform, kernel))
+            .collect();
+        match self.kind {
+            Kind::Lenia => {
+                let lenia = &self.lenia;
+                for (cell, &average) in self.grid.cells_mut().iter_mut().zip(&averages[0]) {
+                    *cell = (*cell + lenia.dt * lenia.growth(average)).clamp(0.0, 1.0);
+                }
+            }
+            Kind::SmoothLife => {
+                let smooth = &self.smooth_life;
+                let cells = (self.grid.cells_mut().iter_mut())
+                    .zip(&averages[0])
+                    .zip(&averages[1]);
+                for ((cell, &inner), &outer) in cells {
+                    let next = smooth.transition(outer, inner);
+                    *cell = if smooth.dt >= 1.0 {
+                        next
+                    } else {
+                        (*cell + smooth.dt * (2.0 * next - 1.0)).clamp(0.0, 1.0)
+                    };
+                }
+            }
+        }
+        self.generation += 1;
+    }
+}
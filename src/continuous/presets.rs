@@ -0,0 +1,273 @@
+//! Rules worth knowing about, and the creatures found living in them
+
+use super::{Kind, Lenia, SmoothLife};
+
+/// What a preset fills the grid with when it's loaded
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Start {
+    /// A creature put down in the middle, as rows of states from 0 to 1
+    Creature(&'static [&'static [f32]]),
+    /// A random soup, for rules where things grow out of noise
+    Soup,
+}
+
+/// A rule and what to start it from, with the same parameters as the side panel
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub kind: Kind,
+    /// Kernel radius, peaks, μ, σ and dt for Lenia presets
+    pub lenia: (u32, &'static [f32], f32, f32, f32),
+    pub smooth_life: SmoothLife,
+    pub start: Start,
+}
+
+impl Preset {
+    pub fn lenia(&self) -> Lenia {
+        let (radius, peaks, mu, sigma, dt) = self.lenia;
+        Lenia {
+            radius,
+            peaks: peaks.to_vec(),
+            mu,
+            sigma,
+            dt,
+        }
+    }
+}
+
+/// Orbium unicaudatus, the glider of Lenia, as Bert Chan published it at `R = 13`
+pub const ORBIUM: &[&[f32]] = &[
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.1, 0.14, 0.1, 0.0, 0.0, 0.03, 0.03, 0.0, 0.0, 0.3, 0.0,
+        0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.24, 0.3, 0.3, 0.18, 0.14, 0.15, 0.16, 0.15, 0.09, 0.2,
+        0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.15, 0.34, 0.44, 0.46, 0.38, 0.18, 0.14, 0.11, 0.13, 0.19, 0.18,
+        0.45, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.06, 0.13, 0.39, 0.5, 0.5, 0.37, 0.06, 0.0, 0.0, 0.0, 0.02, 0.16,
+        0.68, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.11, 0.17, 0.17, 0.33, 0.4, 0.38, 0.28, 0.14, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.18, 0.42, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.09, 0.18, 0.13, 0.06, 0.08, 0.26, 0.32, 0.32, 0.27, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.82, 0.0, 0.0,
+    ],
+    &[
+        0.27, 0.0, 0.16, 0.12, 0.0, 0.0, 0.0, 0.25, 0.38, 0.44, 0.45, 0.34, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.22, 0.17, 0.0,
+    ],
+    &[
+        0.0, 0.07, 0.2, 0.02, 0.0, 0.0, 0.0, 0.31, 0.48, 0.57, 0.6, 0.57, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.49, 0.0,
+    ],
+    &[
+        0.0, 0.59, 0.19, 0.0, 0.0, 0.0, 0.0, 0.2, 0.57, 0.69, 0.76, 0.76, 0.49, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.36, 0.0,
+    ],
+    &[
+        0.0, 0.58, 0.19, 0.0, 0.0, 0.0, 0.0, 0.0, 0.67, 0.83, 0.9, 0.92, 0.87, 0.12, 0.0, 0.0, 0.0,
+        0.0, 0.22, 0.07,
+    ],
+    &[
+        0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.7, 0.93, 1.0, 1.0, 1.0, 0.61, 0.0, 0.0, 0.0,
+        0.0, 0.18, 0.11,
+    ],
+    &[
+        0.0, 0.0, 0.82, 0.0, 0.0, 0.0, 0.0, 0.0, 0.47, 1.0, 1.0, 0.98, 1.0, 0.96, 0.27, 0.0, 0.0,
+        0.0, 0.19, 0.1,
+    ],
+    &[
+        0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.25, 1.0, 1.0, 0.84, 0.92, 0.97, 0.54, 0.14,
+        0.04, 0.1, 0.21, 0.05,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.4, 0.0, 0.0, 0.0, 0.0, 0.09, 0.8, 1.0, 0.82, 0.8, 0.85, 0.63, 0.31, 0.18,
+        0.19, 0.2, 0.01,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.36, 0.1, 0.0, 0.0, 0.0, 0.05, 0.54, 0.86, 0.79, 0.74, 0.72, 0.6, 0.39,
+        0.28, 0.24, 0.13, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.01, 0.3, 0.07, 0.0, 0.0, 0.08, 0.36, 0.64, 0.7, 0.64, 0.6, 0.51, 0.39,
+        0.29, 0.19, 0.04, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.1, 0.24, 0.14, 0.1, 0.15, 0.29, 0.45, 0.53, 0.52, 0.46, 0.4, 0.31,
+        0.21, 0.08, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.21, 0.21, 0.22, 0.29, 0.36, 0.39, 0.37, 0.33, 0.26, 0.18,
+        0.09, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.03, 0.13, 0.19, 0.22, 0.24, 0.24, 0.23, 0.18, 0.13, 0.05,
+        0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.02, 0.06, 0.08, 0.09, 0.07, 0.05, 0.01, 0.0, 0.0,
+        0.0, 0.0, 0.0,
+    ],
+];
+
+/// A lone cell of the Hydrogeminium rule below
+///
+/// Grown from a small random patch and rounded to two places. Small seeds that neither die out
+/// nor fill the grid settle into this same shape, which sits almost still, swelling and shrinking
+/// a little
+pub const HYDROGEMINIUM_CELL: &[&[f32]] = &[
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.03, 0.22, 0.4, 0.42, 0.35, 0.15, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.05, 0.92, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.6,
+        0.06, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.69, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+        0.47, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.59, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+        1.0, 0.59, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.26, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+        1.0, 1.0, 0.36, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.04, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.77, 0.6, 0.6, 0.6, 0.88, 1.0, 1.0,
+        1.0, 1.0, 1.0, 1.0, 0.11, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.67, 1.0, 1.0,
+        1.0, 1.0, 1.0, 1.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.02, 0.0, 0.0, 0.0, 0.73, 1.0,
+        1.0, 1.0, 1.0, 1.0, 0.63, 0.0,
+    ],
+    &[
+        0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.72, 1.0, 0.8, 0.11, 0.0, 0.0,
+        0.97, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0,
+    ],
+    &[
+        0.83, 1.0, 1.0, 1.0, 1.0, 1.0, 0.53, 0.0, 0.03, 0.14, 0.0, 0.0, 0.22, 0.34, 0.26, 0.0, 0.0,
+        0.27, 1.0, 1.0, 1.0, 1.0, 1.0, 0.12,
+    ],
+    &[
+        0.45, 0.72, 1.0, 1.0, 1.0, 0.66, 0.0, 0.08, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.46,
+    ],
+    &[
+        0.0, 0.15, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        1.0, 1.0, 1.0, 1.0, 1.0, 0.26,
+    ],
+    &[
+        0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.15, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.27, 0.0,
+        0.95, 1.0, 1.0, 1.0, 1.0, 0.06,
+    ],
+    &[
+        0.0, 0.0, 1.0, 1.0, 0.96, 0.0, 0.65, 1.0, 1.0, 0.01, 0.0, 0.0, 0.0, 0.0, 0.04, 1.0, 0.79,
+        0.0, 0.41, 1.0, 1.0, 1.0, 1.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.31, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.98, 0.0,
+        0.0, 1.0, 1.0, 1.0, 1.0, 0.0,
+    ],
+    &[
+        0.2, 0.08, 1.0, 1.0, 1.0, 0.11, 0.0, 0.89, 1.0, 1.0, 1.0, 1.0, 0.64, 1.0, 1.0, 1.0, 0.3,
+        0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0,
+    ],
+    &[
+        0.48, 0.38, 1.0, 1.0, 1.0, 1.0, 0.0, 0.23, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0,
+        0.0, 1.0, 1.0, 1.0, 0.95, 0.9, 0.0,
+    ],
+    &[
+        0.44, 0.8, 0.48, 0.81, 1.0, 1.0, 0.45, 0.01, 1.0, 1.0, 1.0, 1.0, 1.0, 0.63, 0.1, 0.0, 0.0,
+        0.96, 1.0, 1.0, 0.85, 0.63, 0.7, 0.0,
+    ],
+    &[
+        0.0, 0.75, 0.01, 0.0, 0.73, 1.0, 1.0, 0.89, 1.0, 1.0, 1.0, 1.0, 0.64, 0.0, 0.01, 0.33, 1.0,
+        1.0, 1.0, 0.9, 0.0, 0.4, 0.31, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.7, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.47, 1.0, 1.0, 1.0, 1.0,
+        0.97, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.92, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.84, 0.01,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.59, 1.0, 1.0, 0.73, 0.29, 0.12, 0.16, 0.31, 0.46,
+        0.27, 0.17, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+    &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.18, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.46, 0.47,
+        0.1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ],
+];
+
+/// Rafler's Smooth Life, with the birth and survival ranges the gliders in the paper live in
+pub const SMOOTH_LIFE_GLIDERS: SmoothLife = SmoothLife {
+    inner_radius: 7.0,
+    outer_radius: 21.0,
+    birth: (0.278, 0.365),
+    survival: (0.267, 0.445),
+    outer_smoothness: 0.028,
+    inner_smoothness: 0.147,
+    dt: 1.0,
+};
+
+pub const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "Orbium",
+        about: "the glider of Lenia, a smooth blob that swims slowly across the grid",
+        kind: Kind::Lenia,
+        lenia: (13, &[1.0], 0.15, 0.015, 0.1),
+        smooth_life: SMOOTH_LIFE_GLIDERS,
+        start: Start::Creature(ORBIUM),
+    },
+    Preset {
+        name: "Hydrogeminium",
+        about: "the three ringed kernel Hydrogeminium natans lives in, which grows out of noise \
+                into cells that split and wander",
+        kind: Kind::Lenia,
+        lenia: (18, &[0.5, 1.0, 0.667], 0.26, 0.036, 0.1),
+        smooth_life: SMOOTH_LIFE_GLIDERS,
+        start: Start::Soup,
+    },
+    Preset {
+        name: "Hydrogeminium cell",
+        about: "a single cell in the Hydrogeminium rule, the shape small seeds settle into, holding \
+                together without going anywhere",
+        kind: Kind::Lenia,
+        lenia: (18, &[0.5, 1.0, 0.667], 0.26, 0.036, 0.1),
+        smooth_life: SMOOTH_LIFE_GLIDERS,
+        start: Start::Creature(HYDROGEMINIUM_CELL),
+    },
+    Preset {
+        name: "SmoothLife gliders",
+        about: "Rafler's SmoothLife, where soups settle into gliders heading every which way",
+        kind: Kind::SmoothLife,
+        lenia: (13, &[1.0], 0.15, 0.015, 0.1),
+        smooth_life: SMOOTH_LIFE_GLIDERS,
+        start: Start::Soup,
+    },
+];
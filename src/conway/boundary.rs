@@ -0,0 +1,85 @@
+//! How the edges of the map are glued together (or not)
+
+use crate::Pos;
+
+/// The topology of the map's edges, decides what a cell just past an edge is really looking at
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Boundary {
+    /// Hard walls, everything outside the map is permanently dead
+    Dead,
+    /// Opposite edges are joined, leaving the left edge puts you on the right edge
+    #[default]
+    Torus,
+    /// Left/right edges are joined like a torus, top/bottom are joined with a twist, so
+    /// crossing them flips you horizontally
+    KleinBottle,
+    /// Both pairs of edges are joined with a twist (the real projective plane)
+    CrossSurface,
+    /// The edges act like mirrors, cells past an edge see a reflection of the map
+    Mirror,
+    /// No edges at all, the pattern is free to grow forever
+    Plane,
+}
+
+impl Boundary {
+    pub const ALL: [Self; 6] = [
+        Self::Dead,
+        Self::Torus,
+        Self::KleinBottle,
+        Self::CrossSurface,
+        Self::Mirror,
+        Self::Plane,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dead => "Dead walls",
+            Self::Torus => "Torus",
+            Self::KleinBottle => "Klein bottle",
+            Self::CrossSurface => "Cross-surface",
+            Self::Mirror => "Mirror",
+            Self::Plane => "Unbounded plane",
+        }
+    }
+
+    /// Whether there is a finite map that can be tiled across the screen
+    pub fn is_wrapping(self) -> bool {
+        !matches!(self, Self::Dead | Self::Plane)
+    }
+
+    /// Bring any coordinate, however far out of range, back onto a `width` x `height` map.
+    /// Returns `None` if the coordinate falls off the edge of a dead-walled map.
+    pub fn wrap(self, pos: Pos, width: i32, height: i32) -> Option<Pos> {
+        let Pos(x, y) = pos;
+        // How many times each edge was crossed, and where we ended up on the base map
+        let (crossed_x, crossed_y) = (x.div_euclid(width), y.div_euclid(height));
+        let (base_x, base_y) = (x.rem_euclid(width), y.rem_euclid(height));
+        let flip_x = |odd: bool| if odd { width - 1 - base_x } else { base_x };
+        let flip_y = |odd: bool| if odd { height - 1 - base_y } else { base_y };
+        match self {
+            Self::Dead => (crossed_x == 0 && crossed_y == 0).then_some(pos),
+            Self::Torus => Some(Pos(base_x, base_y)),
+            Self::KleinBottle => Some(Pos(flip_x(crossed_y % 2 != 0), base_y)),
+            Self::CrossSurface => Some(Pos(flip_x(crossed_y % 2 != 0), flip_y(crossed_x % 2 != 0))),
+            Self::Mirror => Some(Pos(flip_x(crossed_x % 2 != 0), flip_y(crossed_y % 2 != 0))),
+            Self::Plane => Some(pos),
+        }
+    }
+
+    /// Where a copy of `pos` shows up when the map is drawn tiled, for the tile at
+    /// (`tile_x`, `tile_y`). The inverse of [`Self::wrap`], used by the toroidal renderer.
+    pub fn tile_image(self, pos: Pos, tile: (i32, i32), width: i32, height: i32) -> Pos {
+        let (tile_x, tile_y) = tile;
+        let (flip_x, flip_y) = match self {
+            Self::KleinBottle => (tile_y % 2 != 0, false),
+            Self::CrossSurface => (tile_y % 2 != 0, tile_x % 2 != 0),
+            Self::Mirror => (tile_x % 2 != 0, tile_y % 2 != 0),
+            Self::Dead | Self::Torus | Self::Plane => (false, false),
+        };
+        let x = if flip_x { width - 1 - pos.0 } else { pos.0 };
+        let y = if flip_y { height - 1 - pos.1 } else { pos.1 };
+        Pos(tile_x * width + x, tile_y * height + y)
+    }
+}
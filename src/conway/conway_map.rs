@@ -11,14 +11,29 @@
 // TODO: Get better understanding of what every funciton does + add documentation for each function
 // TODO: Refactor the code to be more modular, and to be more easily testable
 
-use std::{collections::HashSet, fs};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
 
-use crate::Pos;
-use egui::{vec2, Color32, Rect, CornerRadius, Shape};
+use crate::{Pos, RunStatistics};
+use egui::{Color32, CornerRadius, Rect, Shape, vec2};
 use instant::{Duration, Instant};
-use rand::{thread_rng, Rng};
 
-use super::{DEFAULT_CELL_SIZE, DEFAULT_MAP_SIZE, NEIGHBORS};
+use super::boundary::Boundary;
+use super::engine::sparse::SparseEngine;
+use super::engine::{EngineKind, Engines, Universe};
+use super::rule::{Neighborhood, Rule, RuleParseError};
+use super::rule_file::{Icons, RuleFile};
+use super::{DEFAULT_CELL_SIZE, DEFAULT_MAP_SIZE};
+use crate::pattern::Pattern;
+use crate::soup::Soup;
+
+/// Empty cells left around a pattern loaded from a file, so it has a bit of room to move
+const PATTERN_MARGIN: i32 = 10;
+
+/// Most updates worth of statistics kept around, the oldest quarter is dropped past this
+const HISTORY_LIMIT: usize = 1 << 20;
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -27,7 +42,40 @@ pub enum ConwayCell {
     Dead = 0,
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
+/// Which part of the map stays fixed when it gets resized
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum ResizeAnchor {
+    #[default]
+    Center,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ResizeAnchor {
+    pub const ALL: [Self; 5] = [
+        Self::Center,
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomLeft,
+        Self::BottomRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Center => "Center",
+            Self::TopLeft => "Top left",
+            Self::TopRight => "Top right",
+            Self::BottomLeft => "Bottom left",
+            Self::BottomRight => "Bottom right",
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 /// Eventually will be generalized to be a "Map" struct, but for now it's just a Conway's Game of
 pub struct Map {
@@ -37,14 +85,18 @@ pub struct Map {
     pub y_axis: i32,
     /// Size of each cell in the map, will be clamped between constants CELL_MIN and CELL_MAX
     pub cell_size: f32,
-    /// Size of the map, eventually I want this to be separate from our viewport
-    pub map_size: i32,
+    /// Width of the map in cells, eventually I want this to be separate from our viewport
+    pub width: i32,
+    /// Height of the map in cells
+    pub height: i32,
     /// Speed of the simulation, in what unit? only God knows
     pub speed: u128,
     /// Frames per second
     pub fps: u32,
-    ///Determines the scarcity of cells in the initial state
-    pub rand_scarcity: u32,
+    /// Ignore `fps` and run generations as fast as they can be computed
+    pub max_speed: bool,
+    /// How random soups get made, and the seed they come from
+    pub soup: Soup,
     /// Self explanatory
     pub light_mode: bool,
     /// Whether or not to draw gridlines
@@ -52,12 +104,41 @@ pub struct Map {
     /// Whether to display the map as toroidal/infinite (wrapping)
     pub toroidal_display: bool,
     pub is_initial: bool,
+    /// Birth/survival rule the simulation runs on, persisted as its rulestring
+    pub rule: Rule,
+    /// A Golly rule file that runs instead of `rule` while one is loaded, persisted as its text
+    pub rule_file: Option<RuleFile>,
+    /// What happens to cells that wander off the edge of the map
+    pub boundary: Boundary,
+    /// Algorithm used to compute new generations
+    pub engine: EngineKind,
+    /// Each update moves the simulation forward 2^`step_log2` generations
+    pub step_log2: u32,
 
+    #[serde(skip)]
+    engines: Engines,
+    /// Statistics as of the last update
+    #[serde(skip)]
+    stats: RunStatistics,
+    /// Statistics for every update since the map was last reset
+    #[serde(skip)]
+    history: Vec<RunStatistics>,
     #[serde(skip)]
     last_frame_time: Instant,
     #[serde(skip)]
     cells: HashSet<Pos>,
+    /// Cells on their way out under a Generations rule, and the state they're in (2 and up)
+    #[serde(skip)]
+    dying: HashMap<Pos, u8>,
+    /// Whether `cells` are out of date, the newest generation being only in the Hashlife engine's
+    /// tree. See [`Map::update_deferred`]
+    #[serde(skip)]
+    deferred: bool,
+    /// Bumped every time the cells change, see [`Map::edits`]
+    #[serde(skip)]
+    edits: u64,
     initial_state: HashSet<Pos>,
+    initial_dying: HashMap<Pos, u8>,
 }
 
 impl Default for Map {
@@ -70,19 +151,33 @@ impl Map {
     pub fn new() -> Self {
         Self {
             fps: 10,
+            max_speed: false,
             speed: Map::fps_to_speed(10.0), //why the hell am I storing the "speed" value if I'm just deriving it from fps?
             cells: HashSet::new(),
+            dying: HashMap::new(),
+            deferred: false,
+            edits: 0,
             initial_state: HashSet::new(),
+            initial_dying: HashMap::new(),
             last_frame_time: Instant::now(),
-            map_size: DEFAULT_MAP_SIZE,
+            width: DEFAULT_MAP_SIZE,
+            height: DEFAULT_MAP_SIZE,
             cell_size: DEFAULT_CELL_SIZE,
             x_axis: 0,
             y_axis: 0,
-            rand_scarcity: 3,
+            soup: Soup::default(),
             light_mode: true,
             lines: false,
             toroidal_display: false,
             is_initial: true,
+            rule: Rule::default(),
+            rule_file: None,
+            boundary: Boundary::default(),
+            engine: EngineKind::default(),
+            step_log2: 0,
+            engines: Engines::default(),
+            stats: RunStatistics::new(),
+            history: Vec::new(),
         }
     }
     pub fn update_speed(&mut self) {
@@ -97,56 +192,188 @@ impl Map {
     //     }
     //     neighbors
     // }
-    // NOTE: Below, I have a new toroidal function for neighbor checking, and above, I have a more
-    // naive check, maybe later look into seeing if it is possible to toggle between say "hard"
-    // walls and toroidal walls that just tile
+    // NOTE: Below, I have a new boundary-aware function for neighbor checking, and above, I have
+    // a more naive check. Which cell a neighbor past the edge really is depends on `self.boundary`
     pub fn neighbors(&self, p: &Pos) -> usize {
-        NEIGHBORS.iter().fold(0, |neighbors, &i| {
-            let mut neighbor_pos = Pos(p.0 + i.0, p.1 + i.1);
+        self.universe().neighbors(*p)
+    }
+
+    /// Map any position onto the board according to the current boundary, `None` if it's off
+    /// the edge of a dead-walled map
+    pub fn wrap(&self, pos: Pos) -> Option<Pos> {
+        self.boundary.wrap(pos, self.width, self.height)
+    }
 
-            neighbor_pos.0 = self.apply_periodic_boundary(neighbor_pos.0, self.map_size);
-            neighbor_pos.1 = self.apply_periodic_boundary(neighbor_pos.1, self.map_size);
+    /// Read-only view of the cells and settings, for handing to an engine
+    fn universe(&self) -> Universe<'_> {
+        Universe {
+            cells: &self.cells,
+            dying: &self.dying,
+            rule: &self.rule,
+            boundary: self.boundary,
+            width: self.width,
+            height: self.height,
+        }
+    }
 
-            neighbors + self.cells.contains(&neighbor_pos) as usize
-        })
+    /// Whether the chosen engine can run the current rule and boundary. Rule files, Generations
+    /// and Larger than Life rules only ever run on the sparse set
+    pub fn engine_supported(&mut self) -> bool {
+        if self.rule_file.is_some() {
+            return self.engine == EngineKind::Sparse;
+        }
+        let (rule, boundary) = (&self.rule, self.boundary);
+        self.engines.get(self.engine).supports(rule, boundary)
     }
 
-    ///Generates the random initial state for the map,
-    /// Bases the way the initial state is off of the "rand_scarcity" value
+    /// The engine that will actually run the next update, falling back to the sparse one when the
+    /// chosen engine can't handle the current settings
+    fn active_engine(&mut self) -> EngineKind {
+        if self.engine_supported() {
+            self.engine
+        } else {
+            EngineKind::Sparse
+        }
+    }
+
+    /// A soup made the way `soup` says from its seed, in the middle of the map. Under rules with
+    /// more than two states live cells get any of them
     pub fn gen_random(&mut self) {
         self.clear();
-        for y in 0..=self.map_size - 4 {
-            for x in 0..=self.map_size - 4 {
-                let mut rng = thread_rng();
-                let probability = rng.gen_range(0..=self.rand_scarcity);
-                if probability == 1 {
-                    self.cells.insert(Pos(x, y));
-                }
-            }
+        let max = (self.width.max(0) as u32, self.height.max(0) as u32);
+        let (width, height) = self.soup.size(max);
+        let (dx, dy) = (
+            (self.width - width as i32) / 2,
+            (self.height - height as i32) / 2,
+        );
+        for (p, state) in self.soup.cells(max, 1..=self.states() - 1) {
+            self.set_cell_state(Pos(p.0 + dx, p.1 + dy), state);
         }
-        //self.cache_initial_state();
     }
     pub fn cache_initial_state(&mut self) {
         self.initial_state.clone_from(&self.cells);
+        self.initial_dying.clone_from(&self.dying);
         //basically anytime this has been called, AND update has not been called, we can garuntee we
         //are in the "initial" state of the app
         self.is_initial = true;
     }
     pub fn restore_initial_state(&mut self) {
+        self.edit_cells();
         self.cells.clone_from(&self.initial_state);
+        self.dying.clone_from(&self.initial_dying);
         self.is_initial = true;
+        self.reset_stats();
+    }
+
+    /// Swap in cells computed somewhere else, like on the background simulation thread
+    pub fn set_cells(&mut self, cells: HashSet<Pos>, dying: HashMap<Pos, u8>) {
+        self.edit_cells();
+        self.cells = cells;
+        self.dying = dying;
+        self.is_initial = false;
     }
 
     pub fn clear(&mut self) {
+        self.edit_cells();
         self.cells = HashSet::new();
+        self.dying = HashMap::new();
+        self.reset_stats();
+    }
+
+    /// Statistics as of the last update
+    pub fn stats(&self) -> &RunStatistics {
+        &self.stats
+    }
+
+    /// Statistics for every update since the map was last cleared or restored, oldest first
+    pub fn history(&self) -> &[RunStatistics] {
+        &self.history
+    }
+
+    /// Start counting generations from zero again
+    pub fn reset_stats(&mut self) {
+        self.stats = RunStatistics::new();
+        self.history.clear();
+    }
+
+    fn record(&mut self, stats: RunStatistics) {
+        if self.history.len() >= HISTORY_LIMIT {
+            self.history.drain(..HISTORY_LIMIT / 4);
+        }
+        self.stats = stats;
+        self.history.push(stats);
+    }
+
+    /// Add statistics for updates that ran on a copy of this map, see [`Map::detached_copy`]
+    pub fn extend_history(&mut self, history: Vec<RunStatistics>) {
+        for stats in history {
+            self.record(stats);
+        }
+    }
+
+    /// Hand over the statistics recorded since the last time this was called
+    pub fn take_history(&mut self) -> Vec<RunStatistics> {
+        std::mem::take(&mut self.history)
+    }
+
+    /// A copy of the map to run somewhere else. The statistics history is left behind, it can get
+    /// long and only new entries are of any use to the copy
+    pub fn detached_copy(&mut self) -> Self {
+        self.catch_up();
+        let history = self.take_history();
+        let copy = self.clone();
+        self.history = history;
+        copy
+    }
+
+    /// Toggle a cell at the given position between `state` and dead
+    pub fn toggle_cell(&mut self, pos: Pos, state: u8) {
+        if self.cell_state(pos) == state {
+            self.set_cell_dead(pos);
+        } else {
+            self.set_cell_state(pos, state);
+        }
+    }
+
+    /// Every live cell on the map, as of the last [`Map::catch_up`] if it's been updated with
+    /// [`Map::update_deferred`]
+    pub fn cells(&self) -> &HashSet<Pos> {
+        &self.cells
+    }
+
+    /// Every cell on its way out under a Generations rule, with its state
+    pub fn dying(&self) -> &HashMap<Pos, u8> {
+        &self.dying
+    }
+
+    /// Every cell that isn't dead, with its state
+    pub fn cell_states(&self) -> impl Iterator<Item = (Pos, u8)> + '_ {
+        let alive = self.cells.iter().map(|&p| (p, 1));
+        alive.chain(self.dying.iter().map(|(&p, &state)| (p, state)))
     }
 
-    /// Toggle a cell at the given position (alive -> dead, dead -> alive)
-    pub fn toggle_cell(&mut self, pos: Pos) {
+    /// State of the cell at the given position, 0 for dead and 1 for alive
+    pub fn cell_state(&self, pos: Pos) -> u8 {
         if self.cells.contains(&pos) {
-            self.cells.remove(&pos);
+            1
         } else {
-            self.cells.insert(pos);
+            self.dying.get(&pos).copied().unwrap_or(0)
+        }
+    }
+
+    /// Put a cell in any state. States past what the rule has are clamped to its last one
+    pub fn set_cell_state(&mut self, pos: Pos, state: u8) {
+        self.edit_cells();
+        self.cells.remove(&pos);
+        self.dying.remove(&pos);
+        match state.min(self.states() - 1) {
+            0 => {}
+            1 => {
+                self.cells.insert(pos);
+            }
+            state => {
+                self.dying.insert(pos, state);
+            }
         }
     }
 
@@ -157,52 +384,204 @@ impl Map {
 
     /// Set a cell to be alive
     pub fn set_cell_alive(&mut self, pos: Pos) {
-        self.cells.insert(pos);
+        self.set_cell_state(pos, 1);
     }
 
     /// Set a cell to be dead
     pub fn set_cell_dead(&mut self, pos: Pos) {
-        self.cells.remove(&pos);
+        self.set_cell_state(pos, 0);
+    }
+
+    /// Number of states a cell can be in under whichever rule is running, counting dead
+    pub fn states(&self) -> u8 {
+        self.rule_file
+            .as_ref()
+            .map_or(self.rule.states(), RuleFile::states)
+    }
+
+    /// Neighborhood of whichever rule is running
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.rule_file
+            .as_ref()
+            .map_or(self.rule.neighborhood(), RuleFile::neighborhood)
+    }
+
+    /// Drop any dying cells in states the rule doesn't have, after the rule has changed
+    pub fn clamp_states(&mut self) {
+        let states = self.states();
+        self.dying.retain(|_, state| *state < states);
+        self.initial_dying.retain(|_, state| *state < states);
+    }
+
+    /// Whether cells are drawn as hexagons, for rules on the hexagonal neighborhood
+    fn hexagonal(&self) -> bool {
+        self.neighborhood() == Neighborhood::Hexagonal
+    }
+
+    /// How many cells row `y` is pushed across by. On a hexagonal grid every row sits half a cell
+    /// left of the one above, which puts each cell's neighbors in the rows above and below right
+    /// up against it. Counted from the middle row so the map stays centered
+    fn row_shift(&self, y: i32) -> f32 {
+        if self.hexagonal() {
+            (self.height as f32 / 2.0 - y as f32) / 2.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Horizontal distance between cells on screen. Hexagons are spaced a little wider than they
+    /// are tall so they come out regular, with rows still `cell_size` apart
+    pub fn cell_width(&self) -> f32 {
+        if self.hexagonal() {
+            self.cell_size * 2.0 / 3.0_f32.sqrt()
+        } else {
+            self.cell_size
+        }
+    }
+
+    /// Screen position of the top left corner of cell (0, 0), before any row shift
+    fn grid_origin(&self, rect: Rect) -> egui::Pos2 {
+        // Calculate center offset
+        let center_offset_x = rect.width() / 2.0 - (self.width as f32 * self.cell_width()) / 2.0;
+        let center_offset_y = rect.height() / 2.0 - (self.height as f32 * self.cell_size) / 2.0;
+
+        egui::Pos2::new(
+            rect.min.x + center_offset_x - self.x_axis as f32 * self.cell_width(),
+            rect.min.y + center_offset_y - self.y_axis as f32 * self.cell_size,
+        )
     }
 
     /// Convert screen coordinates to grid position
     pub fn screen_to_grid(&self, screen_pos: egui::Pos2, rect: Rect) -> Option<Pos> {
-        // Calculate center offset
-        let center_offset_x = rect.width() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        let center_offset_y = rect.height() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        
-        // Calculate the grid position accounting for centering
-        let relative_x = screen_pos.x - rect.min.x - center_offset_x;
-        let relative_y = screen_pos.y - rect.min.y - center_offset_y;
-        
-        let grid_x = (relative_x / self.cell_size) as i32 + self.x_axis;
-        let grid_y = (relative_y / self.cell_size) as i32 + self.y_axis;
-        
-        // In toroidal mode, wrap coordinates to map bounds
-        if self.toroidal_display {
-            let wrapped_x = ((grid_x % self.map_size) + self.map_size) % self.map_size;
-            let wrapped_y = ((grid_y % self.map_size) + self.map_size) % self.map_size;
-            Some(Pos(wrapped_x, wrapped_y))
+        // Position in cells from the corner of cell (0, 0)
+        let origin = self.grid_origin(rect);
+        let x = (screen_pos.x - origin.x) / self.cell_width();
+        let y = (screen_pos.y - origin.y) / self.cell_size;
+
+        let row = y.floor() as i32;
+        let in_row = |row: i32| Pos((x - self.row_shift(row)).floor() as i32, row);
+        let grid_pos = if self.hexagonal() {
+            // Hexagons reach into the rows above and below, but every point is inside the
+            // hexagon of the nearest cell center, so it's one of the closest cells in those rows
+            let distance = |pos: &Pos| {
+                let dx = (pos.0 as f32 + 0.5 + self.row_shift(pos.1) - x) * self.cell_width();
+                let dy = (pos.1 as f32 + 0.5 - y) * self.cell_size;
+                dx * dx + dy * dy
+            };
+            (row - 1..=row + 1)
+                .map(in_row)
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                .unwrap_or(in_row(row))
         } else {
-            Some(Pos(grid_x, grid_y))
-        }
+            in_row(row)
+        };
+
+        // Wrap coordinates onto the map the same way the simulation does, this also lines up
+        // clicks on the mirrored copies drawn in toroidal mode. Nothing to hit past a dead wall
+        self.wrap(grid_pos)
     }
 
-    /// Convert grid position to screen coordinates
+    /// Convert grid position to screen coordinates, the rectangle a cell is drawn in (or around,
+    /// for hexagons)
     pub fn grid_to_screen(&self, grid_pos: Pos, rect: Rect) -> egui::Rect {
-        // Calculate center offset
-        let center_offset_x = rect.width() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        let center_offset_y = rect.height() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        
-        let screen_x = rect.min.x + center_offset_x + (grid_pos.0 - self.x_axis) as f32 * self.cell_size;
-        let screen_y = rect.min.y + center_offset_y + (grid_pos.1 - self.y_axis) as f32 * self.cell_size;
-        
+        let origin = self.grid_origin(rect);
+        let screen_x =
+            origin.x + (grid_pos.0 as f32 + self.row_shift(grid_pos.1)) * self.cell_width();
+        let screen_y = origin.y + grid_pos.1 as f32 * self.cell_size;
+
         egui::Rect::from_min_size(
             egui::Pos2::new(screen_x, screen_y),
-            egui::Vec2::splat(self.cell_size)
+            vec2(self.cell_width(), self.cell_size),
         )
     }
 
+    /// Corners of the (pointy topped, regular) hexagon drawn for the cell in `cell_rect`,
+    /// clockwise from the top. The hexagons are exactly the points nearest to each cell center,
+    /// which `screen_to_grid` relies on
+    fn hexagon(&self, cell_rect: Rect) -> [egui::Pos2; 6] {
+        let center = cell_rect.center();
+        let half_width = cell_rect.width() / 2.0;
+        // Rows are three quarters of a hexagon's height apart
+        let radius = self.cell_size * 2.0 / 3.0;
+        [
+            (0.0, -radius),
+            (half_width, -radius / 2.0),
+            (half_width, radius / 2.0),
+            (0.0, radius),
+            (-half_width, radius / 2.0),
+            (-half_width, -radius / 2.0),
+        ]
+        .map(|(dx, dy)| center + vec2(dx, dy))
+    }
+
+    /// A cell filled in with `color`, as a square or a hexagon depending on the grid
+    fn cell_shape(&self, cell_rect: Rect, color: Color32) -> Shape {
+        if self.hexagonal() {
+            Shape::convex_polygon(self.hexagon(cell_rect).to_vec(), color, egui::Stroke::NONE)
+        } else {
+            Shape::rect_filled(cell_rect, CornerRadius::ZERO, color)
+        }
+    }
+
+    /// A cell in `state`, drawn as its rule file's icon if it has one and the cell is big enough
+    /// to make it out
+    fn push_cell(&self, shapes: &mut Vec<Shape>, cell_rect: Rect, state: u8) {
+        let color = self.cell_color(state);
+        let Some(icons) = self.rule_file.as_ref().and_then(RuleFile::icons) else {
+            shapes.push(self.cell_shape(cell_rect, color));
+            return;
+        };
+        let (center, half) = (
+            cell_rect.center(),
+            cell_rect.width().min(cell_rect.height()) / 2.0,
+        );
+        match icons {
+            Icons::Circles => shapes.push(Shape::circle_filled(center, half, color)),
+            Icons::Diamonds => {
+                let corners = [
+                    vec2(0.0, -half),
+                    vec2(half, 0.0),
+                    vec2(0.0, half),
+                    vec2(-half, 0.0),
+                ];
+                let points = corners.iter().map(|&offset| center + offset).collect();
+                shapes.push(Shape::convex_polygon(points, color, egui::Stroke::NONE));
+            }
+            Icons::Hexagons => {
+                shapes.push(Shape::convex_polygon(
+                    self.hexagon(cell_rect).to_vec(),
+                    color,
+                    egui::Stroke::NONE,
+                ));
+            }
+            Icons::Images(_) => {
+                let Some(icon) = icons.fitting(state, self.cell_size) else {
+                    shapes.push(self.cell_shape(cell_rect, color));
+                    return;
+                };
+                let pixel = cell_rect.width() / icon.size as f32;
+                for (i, pixel_color) in icon.pixels.iter().enumerate() {
+                    let Some(mut pixel_color) = *pixel_color else {
+                        continue;
+                    };
+                    if icon.grayscale {
+                        // Gray icons are shaded versions of the state's own color
+                        let shade = f32::from(pixel_color.r()) / 255.0;
+                        pixel_color = Color32::BLACK.lerp_to_gamma(color, shade);
+                    }
+                    let (x, y) = ((i % icon.size) as f32, (i / icon.size) as f32);
+                    let min = cell_rect.min + vec2(x, y) * pixel;
+                    let pixel_rect = Rect::from_min_size(min, vec2(pixel, pixel));
+                    shapes.push(Shape::rect_filled(
+                        pixel_rect,
+                        CornerRadius::ZERO,
+                        pixel_color,
+                    ));
+                }
+            }
+        }
+    }
+
     /// Draw a highlight over a specific cell
     pub fn draw_cell_highlight(&self, grid_pos: Pos, rect: Rect, shapes: &mut Vec<Shape>) {
         let highlight_color = if self.light_mode {
@@ -210,8 +589,8 @@ impl Map {
         } else {
             Color32::from_rgba_unmultiplied(100, 150, 255, 100) // Light blue with transparency
         };
-        
-        if self.toroidal_display {
+
+        if self.tiled() {
             // In toroidal mode, draw highlights for all visible instances of this cell
             self.draw_cell_highlight_toroidal(grid_pos, rect, shapes, highlight_color);
         } else {
@@ -223,34 +602,23 @@ impl Map {
         }
     }
 
-    fn draw_cell_highlight_toroidal(&self, grid_pos: Pos, rect: Rect, shapes: &mut Vec<Shape>, highlight_color: Color32) {
-        // Calculate center offset
-        let center_offset_x = rect.width() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        let center_offset_y = rect.height() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        
-        // Calculate how many times we need to tile the map to fill the viewport
-        let map_pixel_size = self.map_size as f32 * self.cell_size;
-        
-        // Calculate the range of tiles needed to cover the entire viewport
-        let start_tile_x = ((rect.min.x - center_offset_x) / map_pixel_size).floor() as i32 - 1;
-        let end_tile_x = ((rect.max.x - center_offset_x) / map_pixel_size).ceil() as i32 + 1;
-        let start_tile_y = ((rect.min.y - center_offset_y) / map_pixel_size).floor() as i32 - 1;
-        let end_tile_y = ((rect.max.y - center_offset_y) / map_pixel_size).ceil() as i32 + 1;
-        
+    fn draw_cell_highlight_toroidal(
+        &self,
+        grid_pos: Pos,
+        rect: Rect,
+        shapes: &mut Vec<Shape>,
+        highlight_color: Color32,
+    ) {
+        let (tiles_x, tiles_y) = self.visible_tiles(rect);
+
         // Draw highlights for all visible instances of this cell
-        for tile_x in start_tile_x..=end_tile_x {
-            for tile_y in start_tile_y..=end_tile_y {
-                let tile_offset_x = tile_x as f32 * map_pixel_size;
-                let tile_offset_y = tile_y as f32 * map_pixel_size;
-                
-                let screen_x = rect.min.x + center_offset_x + tile_offset_x + (grid_pos.0 - self.x_axis) as f32 * self.cell_size;
-                let screen_y = rect.min.y + center_offset_y + tile_offset_y + (grid_pos.1 - self.y_axis) as f32 * self.cell_size;
-                
-                let cell_rect = Rect {
-                    min: egui::Pos2::new(screen_x, screen_y),
-                    max: egui::Pos2::new(screen_x + self.cell_size, screen_y + self.cell_size),
-                };
-                
+        for tile_x in tiles_x {
+            for tile_y in tiles_y.clone() {
+                let image =
+                    self.boundary
+                        .tile_image(grid_pos, (tile_x, tile_y), self.width, self.height);
+                let cell_rect = self.grid_to_screen(image, rect);
+
                 if rect.intersects(cell_rect) {
                     self.draw_single_highlight(cell_rect, shapes, highlight_color);
                 }
@@ -258,18 +626,66 @@ impl Map {
         }
     }
 
-    fn draw_single_highlight(&self, cell_rect: Rect, shapes: &mut Vec<Shape>, highlight_color: Color32) {
+    /// Whether the map is drawn tiled across the viewport. Only boundaries that actually wrap
+    /// around have anything to tile
+    fn tiled(&self) -> bool {
+        self.toroidal_display && self.boundary.is_wrapping()
+    }
+
+    /// Range of map tiles (in each axis) that are at least partially inside the viewport
+    fn visible_tiles(&self, rect: Rect) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        let map_pixel_width = self.width as f32 * self.cell_width();
+        let map_pixel_height = self.height as f32 * self.cell_size;
+        let center_offset_x = rect.width() / 2.0 - map_pixel_width / 2.0;
+        let center_offset_y = rect.height() / 2.0 - map_pixel_height / 2.0;
+
+        // Pixel offset of the viewport's top left corner from tile (0, 0)
+        let view_x = self.x_axis as f32 * self.cell_width() - center_offset_x;
+        let view_y = self.y_axis as f32 * self.cell_size - center_offset_y;
+
+        let start_tile_x = (view_x / map_pixel_width).floor() as i32 - 1;
+        let end_tile_x = ((view_x + rect.width()) / map_pixel_width).ceil() as i32 + 1;
+        let start_tile_y = (view_y / map_pixel_height).floor() as i32 - 1;
+        let end_tile_y = ((view_y + rect.height()) / map_pixel_height).ceil() as i32 + 1;
+        // Rows of a hexagonal grid lean over, so tiles further up or down are further across
+        let lean = if self.hexagonal() {
+            let rows = start_tile_y.abs().max(end_tile_y.abs()) * self.height;
+            (rows as f32 / 2.0 / self.width as f32).ceil() as i32
+        } else {
+            0
+        };
+        (
+            start_tile_x - lean..=end_tile_x + lean,
+            start_tile_y..=end_tile_y,
+        )
+    }
+
+    fn draw_single_highlight(
+        &self,
+        cell_rect: Rect,
+        shapes: &mut Vec<Shape>,
+        highlight_color: Color32,
+    ) {
+        let border = egui::Stroke::new(1.0, Color32::from_rgb(0, 100, 255));
+        if self.hexagonal() {
+            shapes.push(Shape::convex_polygon(
+                self.hexagon(cell_rect).to_vec(),
+                highlight_color,
+                border,
+            ));
+            return;
+        }
         shapes.push(Shape::rect_filled(
             cell_rect,
             CornerRadius::ZERO,
             highlight_color,
         ));
-        
+
         // Add a border
         shapes.push(Shape::rect_stroke(
             cell_rect,
             CornerRadius::ZERO,
-            egui::Stroke::new(1.0, Color32::from_rgb(0, 100, 255)),
+            border,
             egui::epaint::StrokeKind::Outside,
         ));
     }
@@ -280,36 +696,94 @@ impl Map {
     // NOTE: This could probably be useful for the refactor
     /// How the simulation runs, this is the main function that updates the state of the map, is called once every draw thread
     /// not the fastest way to run a simulation, but could work if it's thrown into some worker thread maybe but idrc
-    pub fn update(&mut self) {
-        let duration_since_last_frame = Instant::now().duration_since(self.last_frame_time);
+    ///
+    /// Returns whether the map actually moved forward, it won't if it's too soon since the last
+    /// update
+    pub fn update(&mut self) -> bool {
+        let updated = self.update_deferred();
+        self.catch_up();
+        updated
+    }
+
+    /// Like [`Map::update`], except Hashlife gets to keep the new generation in its tree instead of
+    /// flattening it out into cells, which is most of the work on a big step. For running lots of
+    /// updates in a row that nobody looks at, with a [`Map::catch_up`] before anything else reads
+    /// the cells
+    pub fn update_deferred(&mut self) -> bool {
+        let now = Instant::now();
+        let duration_since_last_frame = now.duration_since(self.last_frame_time);
         //below line basically forces fps to work. like, it's saying "if last frame happened, but
         //is lower then our set speed, don't do SHIT!"
-        if duration_since_last_frame.as_millis().lt(&self.speed) {
-            return;
+        if !self.max_speed && duration_since_last_frame.as_millis().lt(&self.speed) {
+            return false;
         }
-        let mut n_cells = HashSet::new();
-        let mut checked = HashSet::new();
-        for cell in &self.cells {
-            for step in NEIGHBORS {
-                let mut xy = Pos(cell.0 + step.0, cell.1 + step.1);
-
-                // Does toroidal checks here, basically coordinates on the edge will "wrap around"
-                xy.0 = self.apply_periodic_boundary(xy.0, self.map_size);
-                //Same here
-                xy.1 = self.apply_periodic_boundary(xy.1, self.map_size);
-
-                if !checked.contains(&xy) {
-                    checked.insert(xy);
-                    let n = self.neighbors(&xy);
-                    if n == 2 && self.cells.contains(&xy) || n == 3 {
-                        n_cells.insert(xy);
-                    }
-                }
-            }
+        let engine = self.active_engine();
+        let hashlife = engine == EngineKind::Hashlife;
+        if !hashlife {
+            self.catch_up();
+        }
+        if self.history.is_empty() && self.stats.generations == 0 {
+            // Somewhere for the plots to start from
+            self.record(RunStatistics::of_cells(&self.cells, 0));
+        }
+        let generations = self.stats.generations + (1 << self.step_log2);
+        // Spelled out rather than `self.universe()`, the engine needs borrowing mutably alongside
+        let universe = Universe {
+            cells: &self.cells,
+            dying: &self.dying,
+            rule: &self.rule,
+            boundary: self.boundary,
+            width: self.width,
+            height: self.height,
+        };
+        if hashlife {
+            let stats = self.engines.hashlife.run(&universe, self.step_log2);
+            self.record(RunStatistics {
+                generations,
+                ..stats
+            });
+            self.deferred = true;
+        } else {
+            let (n_cells, n_dying) = if let Some(rule_file) = &self.rule_file {
+                rule_file.step(&universe, self.step_log2)
+            } else if self.rule.is_generations() {
+                SparseEngine::generations(&universe, self.step_log2)
+            } else {
+                let n_cells = self.engines.get(engine).step(&universe, self.step_log2);
+                (n_cells, HashMap::new())
+            };
+            self.record(RunStatistics::of_step(&self.cells, &n_cells, generations));
+            self.edit_cells();
+            self.cells = n_cells;
+            self.dying = n_dying;
         }
-        self.last_frame_time = Instant::now();
-        self.cells = n_cells;
+        // Measured from the start of the step, so slow generations don't drag the rate down
+        self.last_frame_time = now;
         self.is_initial = false;
+        true
+    }
+
+    /// Bring the cells up to date after [`Map::update_deferred`], flattening the newest
+    /// generation out of Hashlife's tree
+    pub fn catch_up(&mut self) {
+        if std::mem::take(&mut self.deferred) {
+            self.cells = self.engines.hashlife.cells();
+            self.dying.clear();
+        }
+    }
+
+    /// Call before changing the cells any way but stepping them with Hashlife, so it doesn't
+    /// carry on from a tree that no longer matches
+    fn edit_cells(&mut self) {
+        self.catch_up();
+        self.engines.hashlife.forget();
+        self.edits += 1;
+    }
+
+    /// How many times the cells have been changed, by editing or stepping. Cells worked out from
+    /// this map somewhere else are out of date once it moves on
+    pub fn edits(&self) -> u64 {
+        self.edits
     }
     // NOTE: If I end up generalizing/standardizing the way a map is implemented in some refactor
     // down the line, I should move alot of these functions to a parent mod.rs file. For now I
@@ -342,30 +816,65 @@ impl Map {
         }
         (max_x, max_y)
     }
+    /// Change the dimensions of the map. The existing pattern (and the saved initial state) stays
+    /// put relative to `anchor`, anything that ends up outside a bounded map is dropped
+    pub fn resize(&mut self, width: i32, height: i32, anchor: ResizeAnchor) {
+        self.edit_cells();
+        let (dx, dy) = match anchor {
+            ResizeAnchor::Center => (width / 2 - self.width / 2, height / 2 - self.height / 2),
+            ResizeAnchor::TopLeft => (0, 0),
+            ResizeAnchor::TopRight => (width - self.width, 0),
+            ResizeAnchor::BottomLeft => (0, height - self.height),
+            ResizeAnchor::BottomRight => (width - self.width, height - self.height),
+        };
+        self.width = width;
+        self.height = height;
+
+        let keep = |p: &Pos| {
+            let p = Pos(p.0 + dx, p.1 + dy);
+            let on_map = p.0 >= 0 && p.0 < width && p.1 >= 0 && p.1 < height;
+            (on_map || self.boundary == Boundary::Plane).then_some(p)
+        };
+        let keep_dying = |(p, state): (&Pos, &u8)| keep(p).map(|p| (p, *state));
+        self.cells = self.cells.iter().filter_map(keep).collect();
+        self.dying = self.dying.iter().filter_map(keep_dying).collect();
+        self.initial_state = self.initial_state.iter().filter_map(keep).collect();
+        self.initial_dying = self.initial_dying.iter().filter_map(keep_dying).collect();
+    }
     //What does this do????
     pub fn center_cells(&mut self, rect: Rect) {
+        self.edit_cells();
         let (min_x, min_y) = self.find_min();
         let (max_x, max_y) = self.find_max();
         let mut elems_c = HashSet::new();
-        if rect.max.x > rect.max.y {
-            self.cell_size = ((rect.max.x - rect.min.x) as i32 / self.map_size) as f32;
-        } else {
-            self.cell_size = ((rect.max.y - rect.min.y) as i32 / self.map_size) as f32;
-        }
+        // Zoom so the whole map fits in the viewport, whichever axis is the tight one
+        let fit_x =
+            ((rect.max.x - rect.min.x) * self.cell_size / self.cell_width()) as i32 / self.width;
+        let fit_y = (rect.max.y - rect.min.y) as i32 / self.height;
+        self.cell_size = (fit_x.min(fit_y) as f32).clamp(crate::CELL_MIN, crate::CELL_MAX);
+        let shift = |cell: &Pos| {
+            Pos(
+                self.width / 2 - (max_x - min_x) / 2 + cell.0 - min_x,
+                self.height / 2 - (max_y - min_y) / 2 + cell.1 - min_y,
+            )
+        };
         for cell in &self.cells {
-            elems_c.insert(Pos(
-                self.map_size / 2 - (max_x - min_x) / 2 + cell.0,
-                self.map_size / 2 - (max_y - min_y) / 2 + cell.1,
-            ));
+            elems_c.insert(shift(cell));
         }
+        let dying = self
+            .dying
+            .iter()
+            .map(|(p, state)| (shift(p), *state))
+            .collect();
 
         self.cells = elems_c;
+        self.dying = dying;
     }
     /// Draw grid lines that properly align with the viewport and cells
     pub fn draw_lines(&mut self, rect: Rect, shapes: &mut Vec<Shape>) {
         // Calculate stroke thickness based on cell size
         let stroke_thickness = self.exponential_easing(crate::CELL_MIN, crate::CELL_MAX, 0.1, 1.5);
-        
+
         // Grid color based on theme
         let grid_color = if self.light_mode {
             Color32::from_gray(200)
@@ -373,6 +882,15 @@ impl Map {
             Color32::from_gray(60)
         };
 
+        if self.hexagonal() {
+            self.draw_hex_lines(
+                rect,
+                shapes,
+                egui::Stroke::new(stroke_thickness, grid_color),
+            );
+            return;
+        }
+
         // Calculate the offset for grid alignment
         let offset_x = (self.x_axis as f32 * self.cell_size) % self.cell_size;
         let offset_y = (self.y_axis as f32 * self.cell_size) % self.cell_size;
@@ -407,81 +925,90 @@ impl Map {
             }
         }
     }
+
+    /// Hexagon outlines across the viewport. Every cell draws its left side and the two edges
+    /// along its top, the other three belong to its neighbors
+    fn draw_hex_lines(&self, rect: Rect, shapes: &mut Vec<Shape>, stroke: egui::Stroke) {
+        let origin = self.grid_origin(rect);
+        let first_row = ((rect.min.y - origin.y) / self.cell_size).floor() as i32 - 1;
+        let last_row = ((rect.max.y - origin.y) / self.cell_size).ceil() as i32 + 1;
+        for y in first_row..=last_row {
+            let shift = self.row_shift(y);
+            let first = ((rect.min.x - origin.x) / self.cell_width() - shift).floor() as i32 - 1;
+            let last = ((rect.max.x - origin.x) / self.cell_width() - shift).ceil() as i32 + 1;
+            for x in first..=last {
+                let [top, top_right, _, _, bottom_left, top_left] =
+                    self.hexagon(self.grid_to_screen(Pos(x, y), rect));
+                for edge in [[bottom_left, top_left], [top_left, top], [top, top_right]] {
+                    shapes.push(Shape::line_segment(edge, stroke));
+                }
+            }
+        }
+    }
+
     pub fn generate_cells(&self, shapes: &mut Vec<Shape>, rect: Rect) {
-        // Calculate center offset to center the map in the viewport
-        let center_offset_x = rect.width() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        let center_offset_y = rect.height() / 2.0 - (self.map_size as f32 * self.cell_size) / 2.0;
-        
-        if self.toroidal_display {
+        if self.tiled() {
             // Toroidal display: show cells wrapping around infinitely
-            self.draw_cells_toroidal(shapes, rect, center_offset_x, center_offset_y);
+            self.draw_cells_toroidal(shapes, rect);
         } else {
             // Standard display: show cells with centering
-            self.draw_cells_standard(shapes, rect, center_offset_x, center_offset_y);
+            self.draw_cells_standard(shapes, rect);
         }
     }
 
-    fn draw_cells_standard(&self, shapes: &mut Vec<Shape>, rect: Rect, center_offset_x: f32, center_offset_y: f32) {
-        for c in &self.cells {
-            let screen_x = rect.min.x + center_offset_x + (c.0 - self.x_axis) as f32 * self.cell_size;
-            let screen_y = rect.min.y + center_offset_y + (c.1 - self.y_axis) as f32 * self.cell_size;
-            
-            let cell_rect = Rect {
-                min: egui::Pos2::new(screen_x, screen_y),
-                max: egui::Pos2::new(screen_x + self.cell_size, screen_y + self.cell_size),
-            };
-            
+    /// Color a cell in `state` is drawn with. Dying cells fade out towards the background the
+    /// closer they get to dead, unless a rule file says otherwise in its `@COLORS`
+    pub fn cell_color(&self, state: u8) -> Color32 {
+        if let Some(color) = self.rule_file.as_ref().and_then(|rule| rule.color(state)) {
+            return color;
+        }
+        let (alive, dying, background) = if self.light_mode {
+            (
+                Color32::BLACK,
+                Color32::from_rgb(0, 90, 220),
+                Color32::WHITE,
+            )
+        } else {
+            (
+                Color32::WHITE,
+                Color32::from_rgb(60, 140, 255),
+                Color32::BLACK,
+            )
+        };
+        if state <= 1 {
+            return alive;
+        }
+        let fade = f32::from(state - 2) / f32::from(self.states().saturating_sub(2).max(1));
+        dying.lerp_to_gamma(background, fade * 0.8)
+    }
+
+    fn draw_cells_standard(&self, shapes: &mut Vec<Shape>, rect: Rect) {
+        for (c, state) in self.cell_states() {
+            let cell_rect = self.grid_to_screen(c, rect);
+
             // Only draw cells that are visible in the viewport
             if rect.intersects(cell_rect) {
-                shapes.push(Shape::rect_filled(
-                    cell_rect,
-                    CornerRadius::ZERO,
-                    if self.light_mode {
-                        Color32::BLACK
-                    } else {
-                        Color32::WHITE
-                    },
-                ));
+                self.push_cell(shapes, cell_rect, state);
             }
         }
     }
 
-    fn draw_cells_toroidal(&self, shapes: &mut Vec<Shape>, rect: Rect, center_offset_x: f32, center_offset_y: f32) {
-        // Calculate how many times we need to tile the map to fill the viewport
-        let map_pixel_size = self.map_size as f32 * self.cell_size;
-        
-        // Calculate the range of tiles needed to cover the entire viewport
-        let start_tile_x = ((rect.min.x - center_offset_x) / map_pixel_size).floor() as i32 - 1;
-        let end_tile_x = ((rect.max.x - center_offset_x) / map_pixel_size).ceil() as i32 + 1;
-        let start_tile_y = ((rect.min.y - center_offset_y) / map_pixel_size).floor() as i32 - 1;
-        let end_tile_y = ((rect.max.y - center_offset_y) / map_pixel_size).ceil() as i32 + 1;
-        
-        // Draw the map tiled across the viewport with no gaps
-        for tile_x in start_tile_x..=end_tile_x {
-            for tile_y in start_tile_y..=end_tile_y {
-                let tile_offset_x = tile_x as f32 * map_pixel_size;
-                let tile_offset_y = tile_y as f32 * map_pixel_size;
-                
-                for c in &self.cells {
-                    let screen_x = rect.min.x + center_offset_x + tile_offset_x + (c.0 - self.x_axis) as f32 * self.cell_size;
-                    let screen_y = rect.min.y + center_offset_y + tile_offset_y + (c.1 - self.y_axis) as f32 * self.cell_size;
-                    
-                    let cell_rect = Rect {
-                        min: egui::Pos2::new(screen_x, screen_y),
-                        max: egui::Pos2::new(screen_x + self.cell_size, screen_y + self.cell_size),
-                    };
-                    
+    fn draw_cells_toroidal(&self, shapes: &mut Vec<Shape>, rect: Rect) {
+        let (tiles_x, tiles_y) = self.visible_tiles(rect);
+
+        // Draw the map tiled across the viewport with no gaps, flipping the tiles that the
+        // boundary glues on with a twist
+        for tile_x in tiles_x {
+            for tile_y in tiles_y.clone() {
+                for (c, state) in self.cell_states() {
+                    let image =
+                        self.boundary
+                            .tile_image(c, (tile_x, tile_y), self.width, self.height);
+                    let cell_rect = self.grid_to_screen(image, rect);
+
                     // Only draw cells that are visible in the viewport
                     if rect.intersects(cell_rect) {
-                        shapes.push(Shape::rect_filled(
-                            cell_rect,
-                            CornerRadius::ZERO,
-                            if self.light_mode {
-                                Color32::BLACK
-                            } else {
-                                Color32::WHITE
-                            },
-                        ));
+                        self.push_cell(shapes, cell_rect, state);
                     }
                 }
             }
@@ -514,29 +1041,44 @@ impl Map {
         min_thickness + t * (max_thickness - min_thickness)
     }
 
-    // TODO: Use this code, and a provided text box to allow users to make "blueprints"
-    pub fn generate_from_file(&mut self, f: &str) {
-        if fs::read_to_string(f).is_err() {
-            println!("Error reading from file");
-            return;
-        };
-        let contents = fs::read_to_string(f).expect("Error reading from file");
-
-        let mut x = HashSet::new();
-        for (ind, l) in contents.split('\n').enumerate() {
-            for (i, c) in l.chars().enumerate() {
-                if c == '#' {
-                    x.insert(Pos(i as i32, ind as i32));
-                }
+    /// Replace everything on the map with `pattern`, centered. If the pattern brings its own rule
+    /// the map switches to it, and a bounded map grows if the pattern wouldn't fit otherwise
+    ///
+    /// A loaded rule file stays loaded for patterns that name it as their rule
+    pub fn load_pattern(&mut self, pattern: &Pattern) -> Result<(), RuleParseError> {
+        if let Some(rule) = &pattern.rule {
+            let rule_file = self.rule_file.as_ref().map(RuleFile::name);
+            if !rule_file.is_some_and(|name| name.eq_ignore_ascii_case(rule)) {
+                self.rule = rule.parse()?;
+                self.rule_file = None;
             }
         }
-        self.cells = x;
-    }
-    fn apply_periodic_boundary(&self, coord: i32, axis_size: i32) -> i32 {
-        match coord {
-            x if x < 0 => axis_size - 1,
-            x if x >= axis_size => 0,
-            x => x,
+        self.clear();
+        if self.boundary != Boundary::Plane {
+            let width = self.width.max(pattern.width + 2 * PATTERN_MARGIN);
+            let height = self.height.max(pattern.height + 2 * PATTERN_MARGIN);
+            self.resize(width, height, ResizeAnchor::Center);
+        }
+        let (dx, dy) = (
+            (self.width - pattern.width) / 2,
+            (self.height - pattern.height) / 2,
+        );
+        // Only a rule with more states has anywhere to put the higher ones, otherwise they're alive
+        let generations = self.states() > 2;
+        for &(p, state) in &pattern.cells {
+            let state = if generations { state } else { 1 };
+            self.set_cell_state(Pos(p.0 + dx, p.1 + dy), state);
         }
+        Ok(())
+    }
+
+    /// Everything alive (or dying) on the map as a pattern, along with the rule it's running under
+    pub fn to_pattern(&self) -> Pattern {
+        let mut pattern = Pattern::from_cells(self.cell_states());
+        pattern.rule = Some(match &self.rule_file {
+            Some(rule_file) => rule_file.name().to_owned(),
+            None => self.rule.to_string(),
+        });
+        pattern
     }
 }
@@ -0,0 +1,491 @@
+//! Bill Gosper's Hashlife
+//!
+//! The universe is stored as a quadtree where identical subtrees are only ever stored once (they
+//! are "hash-consed"), and the result of running any node forward is remembered. Big, repetitive
+//! patterns end up as a handful of nodes, and since the memoized results are reused both across
+//! space and across time, jumping ahead by 2^k generations costs about the same as jumping ahead
+//! by one.
+//!
+//! The pattern is kept as a tree between steps, always centred on (0, 0) so that nodes line up
+//! from one generation to the next. It's only built from the map's cells after they've been
+//! edited, and only flattened back out into cells when something wants to look at them, see
+//! [`HashLife::run`]. Only works on an unbounded plane, and not for B0 rules (empty space would
+//! have to come alive, infinitely far out).
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Engine, Universe};
+use crate::conway::boundary::Boundary;
+use crate::conway::hensel;
+use crate::conway::rule::Rule;
+use crate::{Pos, RunStatistics};
+
+type NodeId = u32;
+
+/// The two level 0 nodes, a single dead or live cell
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Once there are this many nodes, the ones the pattern no longer uses are thrown away at the start
+/// of the next step, so memory doesn't grow without bound on chaotic patterns. If most of them are
+/// still in use the limit is raised instead
+const MIN_NODE_LIMIT: usize = 1 << 20;
+
+/// A square of 2^`level` by 2^`level` cells
+#[derive(Clone, Copy)]
+struct Node {
+    /// North west, north east, south west, south east quarters
+    children: [NodeId; 4],
+    level: u8,
+    population: u64,
+}
+
+pub struct HashLife {
+    nodes: Vec<Node>,
+    /// Lookup from the four children to the node made of them, so every node exists only once
+    interned: HashMap<[NodeId; 4], NodeId>,
+    /// Memoized `advance` results, keyed by node and step size
+    results: HashMap<(NodeId, u32), NodeId>,
+    /// The empty node of each level
+    empty: Vec<NodeId>,
+    /// Smallest and largest corner of the live cells of each node, relative to its top left
+    bounds: HashMap<NodeId, (i64, i64, i64, i64)>,
+    /// Rule the memoized results were computed under
+    rule: Rule,
+    /// The pattern as of the last step, centred on (0, 0). Gone whenever the cells have been
+    /// changed from outside, see [`HashLife::forget`]
+    root: Option<NodeId>,
+    /// How many nodes there can be before garbage is collected
+    node_limit: usize,
+}
+
+impl Default for HashLife {
+    fn default() -> Self {
+        Self::new(Rule::default())
+    }
+}
+
+impl HashLife {
+    pub fn new(rule: Rule) -> Self {
+        let leaf = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        Self {
+            nodes: vec![leaf(0), leaf(1)],
+            interned: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            bounds: HashMap::new(),
+            rule,
+            root: None,
+            node_limit: MIN_NODE_LIMIT,
+        }
+    }
+
+    /// Drop the pattern kept from the last step. Call whenever the cells are changed by anything
+    /// other than this engine, the next step builds the tree from them again
+    pub fn forget(&mut self) {
+        self.root = None;
+    }
+
+    /// Move `universe` forward 2^`step_log2` generations, keeping the result as a tree. The cells
+    /// are only read when there's no pattern kept from the last step, and nothing is flattened,
+    /// use [`HashLife::cells`] for that. Returns the statistics of the step, generation count
+    /// aside
+    pub fn run(&mut self, universe: &Universe<'_>, step_log2: u32) -> RunStatistics {
+        if *universe.rule != self.rule {
+            let cells = self.root.map(|_| self.cells());
+            *self = Self::new(universe.rule.clone());
+            self.root = cells.map(|cells| self.build_centred(&cells));
+        }
+        if self.nodes.len() > self.node_limit {
+            self.collect_garbage();
+        }
+        let before = match self.root {
+            Some(root) => root,
+            None => self.build_centred(universe.cells),
+        };
+
+        // Pad with empty space until the step fits and the pattern is in the middle, then once
+        // more so there's room for it to grow into while it runs. Expanding keeps it centred
+        let mut root = before;
+        while u32::from(self.node(root).level) < step_log2 + 2 || !self.is_padded(root) {
+            root = self.expand(root);
+        }
+        root = self.expand(root);
+        // The middle half is centred on (0, 0) as well
+        let mut after = self.advance(root, step_log2);
+        while self.node(after).level > 2 && self.is_padded(after) {
+            after = self.centre(after);
+        }
+        self.root = Some(after);
+
+        let (births, deaths) = self.changes(before, after);
+        let population = self.node(after).population;
+        let bounding_box = match self.bounds(after) {
+            Some((min_x, min_y, max_x, max_y)) => {
+                ((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32)
+            }
+            None => (0, 0),
+        };
+        RunStatistics {
+            births,
+            deaths,
+            population,
+            bounding_box,
+            ..RunStatistics::new()
+        }
+    }
+
+    /// Every live cell of the pattern kept from the last step
+    pub fn cells(&self) -> HashSet<Pos> {
+        let mut out = HashSet::new();
+        if let Some(root) = self.root {
+            let half = 1i64 << (self.node(root).level - 1);
+            self.collect(root, -half, -half, &mut out);
+        }
+        out
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    /// The node made of these four quarters, creating it if it doesn't exist yet
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let children = [nw, ne, sw, se];
+        if let Some(&id) = self.interned.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.node(nw).level + 1,
+            population: children.iter().map(|&c| self.node(c).population).sum(),
+        });
+        self.interned.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = self.empty[self.empty.len() - 1];
+            let bigger = self.join(e, e, e, e);
+            self.empty.push(bigger);
+        }
+        self.empty[level as usize]
+    }
+
+    /// The middle half of a node, one level down
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children;
+        self.join(
+            self.node(nw).children[3],
+            self.node(ne).children[2],
+            self.node(sw).children[1],
+            self.node(se).children[0],
+        )
+    }
+
+    /// The node straddling the border between two side by side nodes
+    fn centre_horizontal(&mut self, w: NodeId, e: NodeId) -> NodeId {
+        let (w, e) = (self.node(w).children, self.node(e).children);
+        self.join(w[1], e[0], w[3], e[2])
+    }
+
+    /// The node straddling the border between two stacked nodes
+    fn centre_vertical(&mut self, n: NodeId, s: NodeId) -> NodeId {
+        let (n, s) = (self.node(n).children, self.node(s).children);
+        self.join(n[2], n[3], s[0], s[1])
+    }
+
+    /// Wrap a node in empty space, one level up with the original in the middle
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let node = self.node(id);
+        let e = self.empty(node.level - 1);
+        let [nw, ne, sw, se] = node.children;
+        let nw = self.join(e, e, e, nw);
+        let ne = self.join(e, e, ne, e);
+        let sw = self.join(e, sw, e, e);
+        let se = self.join(se, e, e, e);
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Whether everything alive is within the middle half of the node, so it can be run forward
+    /// without anything escaping
+    fn is_padded(&self, id: NodeId) -> bool {
+        let [nw, ne, sw, se] = self.node(id).children;
+        let inner = |quarter: NodeId, corner: usize| {
+            let q = self.node(quarter);
+            q.population == self.node(q.children[corner]).population
+        };
+        inner(nw, 3) && inner(ne, 2) && inner(sw, 1) && inner(se, 0)
+    }
+
+    /// Run a 4x4 node forward a single generation the slow way, giving back its middle 2x2
+    fn base_case(&mut self, id: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        for (q, &quarter) in self.node(id).children.iter().enumerate() {
+            for (l, &leaf) in self.node(quarter).children.iter().enumerate() {
+                grid[(q / 2) * 2 + l / 2][(q % 2) * 2 + l % 2] = leaf == ALIVE;
+            }
+        }
+        let offsets = self.rule.neighborhood().offsets();
+        let next = |x: usize, y: usize| {
+            let alive_at =
+                |&(dx, dy): &(i32, i32)| grid[(y as i32 + dy) as usize][(x as i32 + dx) as usize];
+            let alive = if self.rule.is_totalistic() {
+                let n = offsets.iter().filter(|d| alive_at(d)).count();
+                self.rule.next_state(grid[y][x], n)
+            } else {
+                let ring = (0..8)
+                    .filter(|&i| alive_at(&hensel::RING[i]))
+                    .fold(0, |ring, i| ring | (1 << i));
+                self.rule.next_state_isotropic(grid[y][x], ring)
+            };
+            if alive { ALIVE } else { DEAD }
+        };
+        let cells = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(cells[0], cells[1], cells[2], cells[3])
+    }
+
+    /// The middle half of a node (one level down), 2^`step_log2` generations later. The step
+    /// can be at most a quarter of the node's width, any further and things from outside the
+    /// node could have reached the middle
+    fn advance(&mut self, id: NodeId, step_log2: u32) -> NodeId {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if node.level == 2 {
+            return self.base_case(id);
+        }
+        if let Some(&result) = self.results.get(&(id, step_log2)) {
+            return result;
+        }
+
+        // Nine overlapping sub-squares, each half the size of the node, covering it in a 3x3
+        let [nw, ne, sw, se] = node.children;
+        let parts = [
+            nw,
+            self.centre_horizontal(nw, ne),
+            ne,
+            self.centre_vertical(nw, sw),
+            self.centre(id),
+            self.centre_vertical(ne, se),
+            sw,
+            self.centre_horizontal(sw, se),
+            se,
+        ];
+        // Going full speed, both rounds move half the step forward. Otherwise the first round
+        // just trims down to the middle, and the second round takes the whole step
+        let full_speed = step_log2 == u32::from(node.level) - 2;
+        let s = parts.map(|part| {
+            if full_speed {
+                self.advance(part, step_log2 - 1)
+            } else {
+                self.centre(part)
+            }
+        });
+        let second_step = if full_speed { step_log2 - 1 } else { step_log2 };
+        let quarters = [
+            self.join(s[0], s[1], s[3], s[4]),
+            self.join(s[1], s[2], s[4], s[5]),
+            self.join(s[3], s[4], s[6], s[7]),
+            self.join(s[4], s[5], s[7], s[8]),
+        ];
+        let [nw, ne, sw, se] = quarters.map(|q| self.advance(q, second_step));
+        let result = self.join(nw, ne, sw, se);
+        self.results.insert((id, step_log2), result);
+        result
+    }
+
+    /// Build a node of the given level from cells relative to its top left corner
+    fn build(&mut self, cells: &[(i64, i64)], level: u8) -> NodeId {
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+        let half = 1i64 << (level - 1);
+        let mut quarters: [Vec<(i64, i64)>; 4] = Default::default();
+        for &(x, y) in cells {
+            let q = usize::from(y >= half) * 2 + usize::from(x >= half);
+            quarters[q].push((x % half, y % half));
+        }
+        let [nw, ne, sw, se] = quarters.map(|q| self.build(&q, level - 1));
+        self.join(nw, ne, sw, se)
+    }
+
+    /// A node centred on (0, 0) with the given cells in it
+    fn build_centred(&mut self, cells: &HashSet<Pos>) -> NodeId {
+        let reach = cells
+            .iter()
+            .map(|p| i64::from(p.0).max(-i64::from(p.0) - 1))
+            .chain(
+                cells
+                    .iter()
+                    .map(|p| i64::from(p.1).max(-i64::from(p.1) - 1)),
+            )
+            .max()
+            .unwrap_or(0);
+        let mut level = 2;
+        while (1i64 << (level - 1)) <= reach {
+            level += 1;
+        }
+        let half = 1i64 << (level - 1);
+        let cells: Vec<(i64, i64)> = cells
+            .iter()
+            .map(|p| (i64::from(p.0) + half, i64::from(p.1) + half))
+            .collect();
+        self.build(&cells, level)
+    }
+
+    /// Cells born and cells that died going from one centred node to another
+    fn changes(&mut self, mut before: NodeId, mut after: NodeId) -> (u64, u64) {
+        while self.node(before).level < self.node(after).level {
+            before = self.expand(before);
+        }
+        while self.node(after).level < self.node(before).level {
+            after = self.expand(after);
+        }
+        (
+            self.difference(after, before),
+            self.difference(before, after),
+        )
+    }
+
+    /// Live cells in `a` that aren't alive in `b`, both being the same size and in the same place
+    fn difference(&self, a: NodeId, b: NodeId) -> u64 {
+        let (node_a, node_b) = (self.node(a), self.node(b));
+        if a == b || node_a.population == 0 {
+            0
+        } else if node_b.population == 0 {
+            node_a.population
+        } else {
+            // Two different live level 0 nodes can't happen, so this always has children
+            (node_a.children.iter())
+                .zip(node_b.children)
+                .map(|(&a, b)| self.difference(a, b))
+                .sum()
+        }
+    }
+
+    /// Smallest and largest corner of a node's live cells, relative to its top left
+    fn bounds(&mut self, id: NodeId) -> Option<(i64, i64, i64, i64)> {
+        let node = self.node(id);
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some((0, 0, 0, 0));
+        }
+        if let Some(&bounds) = self.bounds.get(&id) {
+            return Some(bounds);
+        }
+        let half = 1i64 << (node.level - 1);
+        let bounds = (node.children.iter().enumerate())
+            .filter_map(|(q, &child)| {
+                let (dx, dy) = ((q % 2) as i64 * half, (q / 2) as i64 * half);
+                let (x0, y0, x1, y1) = self.bounds(child)?;
+                Some((x0 + dx, y0 + dy, x1 + dx, y1 + dy))
+            })
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))?;
+        self.bounds.insert(id, bounds);
+        Some(bounds)
+    }
+
+    /// Throw away every node the pattern doesn't use, along with the results for them. Results
+    /// for nodes it does use are kept, so running on picks up where it left off
+    fn collect_garbage(&mut self) {
+        let mut live = vec![false; self.nodes.len()];
+        let mark = |hashlife: &Self, live: &mut [bool], id: NodeId| {
+            let mut stack = vec![id];
+            while let Some(id) = stack.pop() {
+                if !std::mem::replace(&mut live[id as usize], true) {
+                    let node = hashlife.node(id);
+                    if node.level > 0 {
+                        stack.extend(node.children);
+                    }
+                }
+            }
+        };
+        for id in [DEAD, ALIVE].into_iter().chain(self.root) {
+            mark(self, &mut live, id);
+        }
+        for &empty in &self.empty {
+            mark(self, &mut live, empty);
+        }
+        // Picked before their results are marked, results can make more nodes live
+        let results: Vec<_> = (self.results.iter())
+            .filter(|((id, _), _)| live[*id as usize])
+            .map(|(&key, &result)| (key, result))
+            .collect();
+        for &(_, result) in &results {
+            mark(self, &mut live, result);
+        }
+
+        // Children always come before their parents, so the new ids can be handed out in order
+        let mut renamed = vec![NodeId::MAX; self.nodes.len()];
+        let mut nodes = Vec::new();
+        for (id, node) in self.nodes.iter().enumerate() {
+            if live[id] {
+                renamed[id] = nodes.len() as NodeId;
+                let children = node.children.map(|c| renamed[c as usize]);
+                nodes.push(Node { children, ..*node });
+            }
+        }
+        let rename = |id: NodeId| renamed[id as usize];
+        self.interned = (nodes.iter().enumerate())
+            .filter(|(_, node)| node.level > 0)
+            .map(|(id, node)| (node.children, id as NodeId))
+            .collect();
+        self.results = (results.into_iter())
+            .map(|((id, step), result)| ((rename(id), step), rename(result)))
+            .collect();
+        self.bounds.clear();
+        self.empty = self.empty.iter().map(|&id| rename(id)).collect();
+        self.root = self.root.map(rename);
+        self.nodes = nodes;
+        // Don't end up collecting again every step when most of it is in use
+        self.node_limit = self.node_limit.max(self.nodes.len() * 2);
+    }
+
+    /// Flatten a node back out into live cells, `(x, y)` being its top left corner
+    fn collect(&self, id: NodeId, x: i64, y: i64, out: &mut HashSet<Pos>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            out.insert(Pos(x as i32, y as i32));
+            return;
+        }
+        let half = 1i64 << (node.level - 1);
+        for (q, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((q % 2) as i64 * half, (q / 2) as i64 * half);
+            self.collect(child, x + dx, y + dy, out);
+        }
+    }
+}
+
+impl Engine for HashLife {
+    fn supports(&self, rule: &Rule, boundary: Boundary) -> bool {
+        // Nodes only ever look one cell past their edges
+        boundary == Boundary::Plane
+            && !rule.births_from_nothing()
+            && !rule.is_generations()
+            && rule.range().is_none()
+    }
+
+    /// The trait hands over whole sets of cells, so this can't count on them being the ones it
+    /// gave back last time and builds the tree from scratch
+    fn step(&mut self, universe: &Universe<'_>, step_log2: u32) -> HashSet<Pos> {
+        self.forget();
+        self.run(universe, step_log2);
+        self.cells()
+    }
+}
@@ -0,0 +1,177 @@
+//! Larger than Life rules, which count neighbors out to a range rather than just next door
+//!
+//! Counting a range `r` neighborhood cell by cell costs O(r²) for every cell. Instead the live
+//! cells get added up into a summed-area table once a generation, after which the population of
+//! any rectangle is four lookups. Every neighborhood shape is a stack of rectangles (just the one
+//! for Moore), so a cell costs O(1) for Moore neighborhoods and at worst O(r) for the others.
+//!
+//! Bounded maps get one table covering the whole map. An unbounded plane is cut into tiles, and
+//! only the tiles in range of a live cell get a table, so a few far flung patterns don't need one
+//! covering all the empty space in between.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Universe, default_threads, in_bands, in_chunks};
+use crate::Pos;
+use crate::conway::boundary::Boundary;
+use crate::conway::rule::Range;
+
+/// Smallest width of the tiles an unbounded plane is cut into. They're never narrower than the
+/// range, so everything in range of a tile is in the tiles right around it
+const TILE_SIZE: i32 = 128;
+
+/// Running totals of live cells: `sums[y][x]` is how many there are above and left of `(x, y)`
+struct SummedArea {
+    sums: Vec<u32>,
+    /// Row length of `sums`, one more than the grid it was built from
+    stride: usize,
+}
+
+impl SummedArea {
+    fn new(alive: &[bool], width: usize) -> Self {
+        let stride = width + 1;
+        let mut sums = vec![0; stride * (alive.len() / width.max(1) + 1)];
+        for (y, row) in alive.chunks(width.max(1)).enumerate() {
+            let mut row_sum = 0;
+            for (x, &alive) in row.iter().enumerate() {
+                row_sum += u32::from(alive);
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+            }
+        }
+        Self { sums, stride }
+    }
+
+    /// Live cells in columns `x0..x1` of rows `y0..y1`
+    fn rectangle(&self, (x0, x1): (usize, usize), (y0, y1): (usize, usize)) -> u32 {
+        let at = |x: usize, y: usize| self.sums[y * self.stride + x];
+        at(x1, y1) + at(x0, y0) - at(x0, y1) - at(x1, y0)
+    }
+}
+
+/// Work out a single generation of a Larger than Life rule
+pub fn generation(universe: &Universe<'_>, range: &Range) -> HashSet<Pos> {
+    // On an unbounded plane anything in range of a live cell can be alive next generation, except
+    // that B0 rules only keep the map area going, same as the sparse engine
+    if universe.boundary == Boundary::Plane && !universe.rule.births_from_nothing() {
+        return on_plane(universe, range);
+    }
+    let area = (Pos(0, 0), universe.width, universe.height);
+    let next = rectangle(universe, range, area, universe.cells, default_threads());
+    next.into_iter().collect()
+}
+
+/// A generation on an unbounded plane, a tile at a time
+fn on_plane(universe: &Universe<'_>, range: &Range) -> HashSet<Pos> {
+    let size = TILE_SIZE.max(range.radius() as i32);
+    let mut tiles: HashMap<(i32, i32), HashSet<Pos>> = HashMap::new();
+    universe.cells.iter().for_each(|&p| {
+        let tile = (p.0.div_euclid(size), p.1.div_euclid(size));
+        tiles.entry(tile).or_default().insert(p);
+    });
+    let around =
+        |(x, y): (i32, i32)| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)));
+    let mut candidates: Vec<(i32, i32)> = tiles.keys().copied().flat_map(around).collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    // Threads take a share of the tiles each, rather than a share of each tile
+    let parts = in_chunks(&candidates, default_threads(), |chunk| {
+        let mut next = Vec::new();
+        for &(x, y) in chunk {
+            let nearby: HashSet<Pos> = around((x, y))
+                .filter_map(|tile| tiles.get(&tile))
+                .flatten()
+                .copied()
+                .collect();
+            let area = (Pos(x * size, y * size), size, size);
+            next.extend(rectangle(universe, range, area, &nearby, 1));
+        }
+        next
+    });
+    parts.into_iter().flatten().collect()
+}
+
+/// The cells alive next generation in the `width`×`height` rectangle from `origin`, out of
+/// `cells`, which has to include every live cell in range of it
+fn rectangle(
+    universe: &Universe<'_>,
+    range: &Range,
+    (origin, width, height): (Pos, i32, i32),
+    cells: &HashSet<Pos>,
+    threads: usize,
+) -> Vec<Pos> {
+    let radius = range.radius() as i32;
+
+    // The area plus a border as wide as the range, with whatever the boundary puts there
+    let padded_width = (width + 2 * radius) as usize;
+    let padded_height = (height + 2 * radius) as usize;
+    let mut alive = vec![false; padded_width * padded_height];
+    let index = |p: Pos| {
+        let (x, y) = (p.0 - origin.0 + radius, p.1 - origin.1 + radius);
+        let inside =
+            (0..padded_width as i32).contains(&x) && (0..padded_height as i32).contains(&y);
+        inside.then(|| y as usize * padded_width + x as usize)
+    };
+    cells
+        .iter()
+        .filter_map(|&cell| index(cell))
+        .for_each(|i| alive[i] = true);
+    if universe.boundary != Boundary::Plane {
+        for y in 0..padded_height as i32 {
+            for x in 0..padded_width as i32 {
+                let p = Pos(origin.0 + x - radius, origin.1 + y - radius);
+                let on_map = (0..width).contains(&(p.0 - origin.0))
+                    && (0..height).contains(&(p.1 - origin.1));
+                if !on_map {
+                    let wrapped = universe.wrap(p).and_then(index);
+                    alive[y as usize * padded_width + x as usize] =
+                        wrapped.is_some_and(|i| alive[i]);
+                }
+            }
+        }
+    }
+    let table = SummedArea::new(&alive, padded_width);
+
+    // The neighborhood as a stack of rectangles, each a run of rows that are equally wide
+    let mut rectangles: Vec<(i32, i32, i32)> = Vec::new();
+    for dy in -radius..=radius {
+        let half_width = range.shape().half_width(radius, dy);
+        match rectangles.last_mut() {
+            Some((_, last, width)) if *width == half_width => *last = dy,
+            _ => rectangles.push((dy, dy, half_width)),
+        }
+    }
+
+    let width = width as usize;
+    let mut next = vec![false; width * height as usize];
+    in_bands(&mut next, width, threads, |first_row, band| {
+        for (row, cells) in band.chunks_mut(width).enumerate() {
+            let y = first_row + row + radius as usize;
+            for (x, next) in cells.iter_mut().enumerate() {
+                let x = x + radius as usize;
+                let here = alive[y * padded_width + x];
+                let mut count: u32 = rectangles
+                    .iter()
+                    .map(|&(top, bottom, half_width)| {
+                        table.rectangle(
+                            (x - half_width as usize, x + half_width as usize + 1),
+                            ((y as i32 + top) as usize, (y as i32 + bottom) as usize + 1),
+                        )
+                    })
+                    .sum();
+                if !range.counts_middle() {
+                    count -= u32::from(here);
+                }
+                *next = universe.rule.next_state(here, count as usize);
+            }
+        }
+    });
+
+    next.iter()
+        .enumerate()
+        .filter(|&(_, &alive)| alive)
+        .map(|(i, _)| Pos(origin.0 + (i % width) as i32, origin.1 + (i / width) as i32))
+        // Cells still on their way out can't be born again yet
+        .filter(|p| !universe.dying.contains_key(p))
+        .collect()
+}
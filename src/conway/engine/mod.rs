@@ -0,0 +1,212 @@
+//! The algorithms that actually compute new generations
+//!
+//! `Map` owns the cells and all the settings, and hands an engine a read-only [`Universe`] view of
+//! them whenever the simulation needs to move forward. Engines are free to keep whatever caches
+//! they like between steps, but the cells on the map are the source of truth, so they can be
+//! swapped out at any point. The one exception is Hashlife, which the map lets run on ahead of its
+//! cells for as long as nothing needs to look at them.
+
+pub mod dense;
+pub mod hashlife;
+pub mod larger_than_life;
+pub mod sparse;
+
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+
+use super::boundary::Boundary;
+use super::hensel;
+use super::rule::Rule;
+use crate::Pos;
+
+/// Everything an engine gets to look at when working out the next generation
+#[derive(Clone, Copy)]
+pub struct Universe<'a> {
+    /// Live cells
+    pub cells: &'a HashSet<Pos>,
+    /// Cells on their way out under a Generations rule, and the state they're in (2 and up).
+    /// Always empty for ordinary rules
+    pub dying: &'a HashMap<Pos, u8>,
+    pub rule: &'a Rule,
+    pub boundary: Boundary,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Universe<'_> {
+    /// Map any position onto the board according to the boundary, `None` if it's off the edge of
+    /// a dead-walled map
+    pub fn wrap(&self, pos: Pos) -> Option<Pos> {
+        self.boundary.wrap(pos, self.width, self.height)
+    }
+
+    /// Number of live cells in the rule's neighborhood of `p`
+    pub fn neighbors(&self, p: Pos) -> usize {
+        self.rule
+            .neighborhood()
+            .offsets()
+            .iter()
+            .filter_map(|&(dx, dy)| self.wrap(Pos(p.0 + dx, p.1 + dy)))
+            .filter(|neighbor| self.cells.contains(neighbor))
+            .count()
+    }
+
+    /// Which of the Moore neighbors of `p` are alive, as bits of [`hensel::RING`]
+    pub fn ring(&self, p: Pos) -> u8 {
+        hensel::RING
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &(dx, dy))| Some((i, self.wrap(Pos(p.0 + dx, p.1 + dy))?)))
+            .filter(|(_, neighbor)| self.cells.contains(neighbor))
+            .fold(0, |ring, (i, _)| ring | (1 << i))
+    }
+
+    /// Whether the cell at `p` will be alive next generation
+    pub fn next_state(&self, p: Pos) -> bool {
+        let alive = self.cells.contains(&p);
+        if self.rule.is_totalistic() {
+            self.rule.next_state(alive, self.neighbors(p))
+        } else {
+            self.rule.next_state_isotropic(alive, self.ring(p))
+        }
+    }
+}
+
+/// Number of threads to spread work over: every core on native builds, and just the one on the
+/// web, where there are no threads to be had
+pub fn default_threads() -> usize {
+    if cfg!(target_arch = "wasm32") {
+        1
+    } else {
+        std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+    }
+}
+
+/// Split `rows` (each `row_len` items long) into one band per thread and run `work` on all of
+/// them at once, passing along the index of the band's first row. Falls back to doing the bands
+/// one after the other on `wasm32`, or when there's only one thread to use.
+fn in_bands<T: Send>(
+    rows: &mut [T],
+    row_len: usize,
+    threads: usize,
+    work: impl Fn(usize, &mut [T]) + Sync,
+) {
+    let row_count = rows.len() / row_len.max(1);
+    let band_rows = row_count.div_ceil(threads.max(1)).max(1);
+    let bands = rows.chunks_mut(band_rows * row_len.max(1)).enumerate();
+    if threads <= 1 || cfg!(target_arch = "wasm32") {
+        for (i, band) in bands {
+            work(i * band_rows, band);
+        }
+        return;
+    }
+    std::thread::scope(|scope| {
+        for (i, band) in bands {
+            let work = &work;
+            scope.spawn(move || work(i * band_rows, band));
+        }
+    });
+}
+
+/// Split `items` into one chunk per thread and run `work` on all of them at once, giving back what
+/// each chunk came to, in order. Same fallbacks as [`in_bands`]
+fn in_chunks<T: Sync, R: Send>(
+    items: &[T],
+    threads: usize,
+    work: impl Fn(&[T]) -> R + Sync,
+) -> Vec<R> {
+    let chunks = items.chunks(items.len().div_ceil(threads.max(1)).max(1));
+    if threads <= 1 || cfg!(target_arch = "wasm32") {
+        return chunks.map(work).collect();
+    }
+    std::thread::scope(|scope| {
+        let work = &work;
+        let handles: Vec<_> = chunks
+            .map(|chunk| scope.spawn(move || work(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// A way of computing generations
+pub trait Engine {
+    /// Whether this engine can run the given rule on the given kind of map at all
+    fn supports(&self, rule: &Rule, boundary: Boundary) -> bool;
+
+    /// Advance the universe by 2^`step_log2` generations, returning the cells alive afterwards
+    fn step(&mut self, universe: &Universe<'_>, step_log2: u32) -> HashSet<Pos>;
+}
+
+/// The engines a user can pick between, this is what gets saved with the map
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum EngineKind {
+    /// Checks every live cell and its neighbors, one generation at a time
+    #[default]
+    Sparse,
+    /// Bit-packed grid covering the whole map, for dense soups
+    Dense,
+    /// Memoized quadtree, can skip ahead huge numbers of generations at once
+    Hashlife,
+}
+
+impl EngineKind {
+    pub const ALL: [Self; 3] = [Self::Sparse, Self::Dense, Self::Hashlife];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sparse => "Sparse set",
+            Self::Dense => "Dense bit grid",
+            Self::Hashlife => "Hashlife",
+        }
+    }
+
+    /// Largest step size (as a power of two) that makes sense for this engine. Engines that go
+    /// one generation at a time would take forever on big steps, and Hashlife can outrun the
+    /// coordinate range if it's pushed too far
+    pub fn max_step_log2(self) -> u32 {
+        match self {
+            Self::Sparse | Self::Dense => 6,
+            Self::Hashlife => 24,
+        }
+    }
+}
+
+/// One instance of every engine, so each one can hang on to its caches between steps
+#[derive(Default)]
+pub struct Engines {
+    pub sparse: sparse::SparseEngine,
+    pub dense: dense::DenseEngine,
+    pub hashlife: hashlife::HashLife,
+}
+
+/// Caches aren't worth copying around, a clone starts out with empty ones
+impl Clone for Engines {
+    fn clone(&self) -> Self {
+        Self {
+            sparse: sparse::SparseEngine,
+            dense: dense::DenseEngine {
+                threads: self.dense.threads,
+            },
+            hashlife: hashlife::HashLife::default(),
+        }
+    }
+}
+
+impl Engines {
+    pub fn get(&mut self, kind: EngineKind) -> &mut dyn Engine {
+        match kind {
+            EngineKind::Sparse => &mut self.sparse,
+            EngineKind::Dense => &mut self.dense,
+            EngineKind::Hashlife => &mut self.hashlife,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! The original stepping algorithm, working straight off the set of live cells
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Engine, Universe, default_threads, in_chunks, larger_than_life};
+use crate::Pos;
+use crate::conway::boundary::Boundary;
+use crate::conway::rule::Rule;
+
+/// Below this many cells per thread, starting the threads takes longer than the work they'd share
+const MIN_CELLS_PER_THREAD: usize = 4096;
+
+/// Only looks at live cells and their neighbors, so the cost scales with the population rather
+/// than the size of the map. Handles every rule and boundary.
+///
+/// On native builds big populations are split between every core, each thread looking at its own
+/// share of the live cells.
+///
+/// The only engine that runs Generations rules, and Larger than Life ones, which it hands over to
+/// [`larger_than_life`] as neighborhoods that size are too slow to count one cell at a time.
+#[derive(Default)]
+pub struct SparseEngine;
+
+impl SparseEngine {
+    /// Work out a single generation
+    pub fn generation(universe: &Universe<'_>) -> HashSet<Pos> {
+        let cells = if universe.rule.births_from_nothing() {
+            (universe.width.max(0) * universe.height.max(0)) as usize
+        } else {
+            universe.cells.len()
+        };
+        let threads = default_threads().min(cells / MIN_CELLS_PER_THREAD);
+        Self::generation_on(universe, threads.max(1))
+    }
+
+    /// Work out a single generation, spreading the work over `threads` threads. Comes out the
+    /// same however many there are
+    pub fn generation_on(universe: &Universe<'_>, threads: usize) -> HashSet<Pos> {
+        if let Some(range) = universe.rule.range() {
+            return larger_than_life::generation(universe, range);
+        }
+        let b0 = universe.rule.births_from_nothing();
+        let candidates: Vec<Pos> = if b0 {
+            // B0 rules can bring any cell to life, not just the ones next to live cells. On an
+            // unbounded plane that would be infinitely many, so only the map area is looked at
+            (0..universe.height)
+                .flat_map(|y| (0..universe.width).map(move |x| Pos(x, y)))
+                .collect()
+        } else {
+            universe.cells.iter().copied().collect()
+        };
+        let parts = in_chunks(&candidates, threads, |chunk| {
+            let mut n_cells = HashSet::new();
+            let mut checked = HashSet::new();
+            let mut check = |xy: Pos| {
+                if checked.insert(xy) {
+                    // Cells still on their way out can't be born again yet
+                    let blocked = universe.dying.contains_key(&xy);
+                    if !blocked && universe.next_state(xy) {
+                        n_cells.insert(xy);
+                    }
+                }
+            };
+            for &cell in chunk {
+                // The cell itself has to be looked at too, rules with S0 keep lone cells alive
+                check(cell);
+                if b0 {
+                    // Every cell that could change is a candidate already
+                    continue;
+                }
+                for step in universe.rule.neighborhood().offsets() {
+                    // Coordinates past the edge get mapped back onto the board (or dropped) here
+                    if let Some(xy) = universe.wrap(Pos(cell.0 + step.0, cell.1 + step.1)) {
+                        check(xy);
+                    }
+                }
+            }
+            n_cells
+        });
+        // Neighbors shared between chunks get looked at by both, but come out the same either way
+        let mut parts = parts.into_iter();
+        let mut n_cells = parts.next().unwrap_or_default();
+        for part in parts {
+            n_cells.extend(part);
+        }
+        n_cells
+    }
+
+    /// Advance a Generations rule by 2^`step_log2` generations
+    ///
+    /// Returns the live cells and the dying ones afterwards
+    pub fn generations(
+        universe: &Universe<'_>,
+        step_log2: u32,
+    ) -> (HashSet<Pos>, HashMap<Pos, u8>) {
+        let mut cells = universe.cells.clone();
+        let mut dying = universe.dying.clone();
+        for _ in 0..1u64 << step_log2 {
+            let next = Self::generation(&Universe {
+                cells: &cells,
+                dying: &dying,
+                ..*universe
+            });
+            // Everything already dying moves one state along, and the cells that just died join
+            // them in the first dying state
+            let rule = universe.rule;
+            dying = dying
+                .into_iter()
+                .map(|(p, state)| (p, rule.next_dying_state(state)))
+                .chain(
+                    cells
+                        .iter()
+                        .filter(|p| !next.contains(p))
+                        .map(|&p| (p, rule.next_dying_state(1))),
+                )
+                .filter(|&(_, state)| state != 0)
+                .collect();
+            cells = next;
+        }
+        (cells, dying)
+    }
+}
+
+impl Engine for SparseEngine {
+    fn supports(&self, _rule: &Rule, _boundary: Boundary) -> bool {
+        true
+    }
+
+    fn step(&mut self, universe: &Universe<'_>, step_log2: u32) -> HashSet<Pos> {
+        let mut cells = Self::generation(universe);
+        for _ in 1..1u64 << step_log2 {
+            cells = Self::generation(&Universe {
+                cells: &cells,
+                ..*universe
+            });
+        }
+        cells
+    }
+}
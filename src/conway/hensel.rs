@@ -0,0 +1,124 @@
+//! Hensel notation for isotropic non-totalistic rules
+//!
+//! Rather than just counting a cell's live neighbors, these rules tell apart the different ways
+//! that many neighbors can be arranged, up to rotation and reflection. Every arrangement of 1 to 7
+//! neighbors gets a letter, so `2a` is two neighbors next to each other and `2i` two on opposite
+//! edges. Between them the letters cover all 256 possible neighborhoods.
+//!
+//! Arrangements of more than four neighbors use the same letter as their complement, the cells
+//! that are dead in `5a` are the ones alive in `3a`.
+
+use std::sync::LazyLock;
+
+/// Offsets of the Moore neighbors going clockwise from the top, bit `i` of a neighborhood is
+/// whether `RING[i]` is alive
+pub const RING: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// The letters that go with each neighbor count, in the order Golly prints them
+const LETTERS: [&str; 9] = [
+    "",
+    "ce",
+    "cekain",
+    "cekainyqjr",
+    "cekainyqjrtwz",
+    "cekainyqjr",
+    "cekain",
+    "ce",
+    "",
+];
+
+/// One neighborhood for every letter of the counts up to four, as bits of `RING`
+const REPRESENTATIVES: [&[u8]; 5] = [
+    &[],
+    // c: NE, e: N
+    &[0b0000_0010, 0b0000_0001],
+    // c: NE SE, e: N E, k: N SE, a: N NE, i: N S, n: NE SW
+    &[
+        0b0000_1010,
+        0b0000_0101,
+        0b0000_1001,
+        0b0000_0011,
+        0b0001_0001,
+        0b0010_0010,
+    ],
+    // c: NE SE SW, e: N E S, k: N E SW, a: N NE E, i: N NE NW, n: N NE SE, y: N SE SW,
+    // q: N NE SW, j: N NE W, r: N NE S
+    &[
+        0b0010_1010,
+        0b0001_0101,
+        0b0010_0101,
+        0b0000_0111,
+        0b1000_0011,
+        0b0000_1011,
+        0b0010_1001,
+        0b0010_0011,
+        0b0100_0011,
+        0b0001_0011,
+    ],
+    // c: corners, e: edges, k: N NE SE W, a: N NE E SE, i: N NE SE S, n: N NE SE NW,
+    // y: N NE SE SW, q: N NE E SW, j: N NE S W, r: N NE E S, t: N NE S NW, w: N NE SW W,
+    // z: N NE S SW
+    &[
+        0b1010_1010,
+        0b0101_0101,
+        0b0100_1011,
+        0b0000_1111,
+        0b0001_1011,
+        0b1000_1011,
+        0b0010_1011,
+        0b0010_0111,
+        0b0101_0011,
+        0b0001_0111,
+        0b1001_0011,
+        0b0110_0011,
+        0b0011_0011,
+    ],
+];
+
+/// Index into `LETTERS[count]` of every neighborhood's letter
+static LETTER_OF: LazyLock<[u8; 256]> = LazyLock::new(|| {
+    let mut table = [0; 256];
+    for (count, representatives) in REPRESENTATIVES.iter().enumerate() {
+        for (letter, &bits) in representatives.iter().enumerate() {
+            for image in symmetries(bits) {
+                table[usize::from(image)] = letter as u8;
+                // The complement has the same letter, with the count on the other side of four.
+                // Fours are complements of other fours, they all have their own representative
+                if count < 4 {
+                    table[usize::from(!image)] = letter as u8;
+                }
+            }
+        }
+    }
+    table
+});
+
+/// Every rotation and reflection of a neighborhood
+fn symmetries(bits: u8) -> impl Iterator<Item = u8> {
+    let mirrored = (0..8)
+        .filter(|i| bits & (1 << i) != 0)
+        .fold(0u8, |out, i| out | (1 << ((8 - i) % 8)));
+    (0..4).flat_map(move |turn| [bits.rotate_left(2 * turn), mirrored.rotate_left(2 * turn)])
+}
+
+/// The letters that can follow `count`, empty for 0 and 8
+pub fn letters(count: usize) -> &'static str {
+    LETTERS.get(count).copied().unwrap_or("")
+}
+
+/// Neighbor count and index of the letter (into [`letters`]) of a neighborhood
+pub fn classify(bits: u8) -> (usize, usize) {
+    (
+        bits.count_ones() as usize,
+        usize::from(LETTER_OF[usize::from(bits)]),
+    )
+}
@@ -1,4 +1,10 @@
+pub mod boundary;
 pub mod conway_map;
+pub mod engine;
+pub mod hensel;
+pub mod rule;
+pub mod rule_file;
+pub mod worker;
 
 pub const DEFAULT_MAP_SIZE: i32 = 75;
 pub const DEFAULT_CELL_SIZE: f32 = 10.0;
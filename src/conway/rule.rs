@@ -0,0 +1,644 @@
+//! Life-like rules written in the usual rulestring notations.
+//!
+//! Six flavours of notation are understood:
+//! * `B36/S23`: birth and survival counts, prefixed with `B` and `S` (in either order)
+//! * `23/36`: the older "S/B" notation, survival counts first, then birth counts
+//! * `B2/S/C3` or `/2/3`: either of the above with a third part giving the number of states, for
+//!   "Generations" rules where cells that die pass through refractory states before they're gone
+//! * `B2-ak3ijr/S1c23-ak`: Hensel notation, where counts can be followed by letters picking out
+//!   particular arrangements of that many neighbors (or by `-` and the letters to leave out), see
+//!   [`super::hensel`]
+//! * `B3/S23V`: any of the above, followed by a neighborhood suffix (`V` for von Neumann, `H` for
+//!   hexagonal)
+//! * `R5,C0,M1,S34..58,B34..45,NM`: Larger than Life, counting neighbors out to a range (`R`),
+//!   with a number of states (`C`, 0 for two), whether the cell counts itself (`M`), survival
+//!   and birth count ranges and the neighborhood shape (`NM` Moore, `NN` von Neumann, `NC`
+//!   circular)
+//!
+//! Life-like rules are always printed back in the canonical `B.../S...` form, and Larger than Life
+//! rules in the `R...` form with every part spelled out.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use super::NEIGHBORS;
+use super::hensel;
+
+/// The set of cells that count as "neighbors" when a rule is applied
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Neighborhood {
+    /// All 8 surrounding cells
+    #[default]
+    Moore,
+    /// Only the 4 orthogonally adjacent cells
+    VonNeumann,
+    /// 6 cells, for a hexagonal grid. Like Golly, hexagons are laid out on the square grid by
+    /// leaving out the north east and south west corners, which puts the rows half a cell apart
+    Hexagonal,
+}
+
+impl Neighborhood {
+    /// Offsets of the cells that make up this neighborhood, a subset of `NEIGHBORS`
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Self::Moore => &NEIGHBORS,
+            Self::VonNeumann => &[(0, 1), (-1, 0), (1, 0), (0, -1)],
+            Self::Hexagonal => &[(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)],
+        }
+    }
+
+    /// Highest neighbor count that can occur in this neighborhood
+    pub fn max_count(self) -> usize {
+        self.offsets().len()
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Moore => "",
+            Self::VonNeumann => "V",
+            Self::Hexagonal => "H",
+        }
+    }
+}
+
+/// Furthest a Larger than Life neighborhood can reach, same as Golly
+pub const MAX_RANGE: u32 = 500;
+
+/// Shape of a Larger than Life neighborhood
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum RangeShape {
+    /// The whole square around the cell
+    #[default]
+    Moore,
+    /// Cells no more than the range away going along rows and columns, a diamond
+    VonNeumann,
+    /// Cells whose centers are closer than the range and a half
+    Circular,
+}
+
+impl RangeShape {
+    fn letter(self) -> char {
+        match self {
+            Self::Moore => 'M',
+            Self::VonNeumann => 'N',
+            Self::Circular => 'C',
+        }
+    }
+
+    /// How far either side of the cell the neighborhood reaches in the row `dy` above or below
+    pub fn half_width(self, radius: i32, dy: i32) -> i32 {
+        match self {
+            Self::Moore => radius,
+            Self::VonNeumann => radius - dy.abs(),
+            Self::Circular => (0..=radius)
+                .rev()
+                .find(|dx| dx * dx + dy * dy <= radius * radius + radius)
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// The part of a Larger than Life rule that ordinary rules don't have, see [`Rule::range`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Range {
+    radius: u32,
+    shape: RangeShape,
+    /// Whether a cell counts itself as one of its neighbors
+    middle: bool,
+    birth: RangeInclusive<u32>,
+    survival: RangeInclusive<u32>,
+}
+
+impl Range {
+    /// How many cells either side of a cell its neighborhood reaches
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    pub fn shape(&self) -> RangeShape {
+        self.shape
+    }
+
+    /// Whether a cell counts itself as one of its neighbors
+    pub fn counts_middle(&self) -> bool {
+        self.middle
+    }
+
+    /// Most live cells a neighborhood can hold
+    pub fn max_count(&self) -> u32 {
+        let radius = self.radius as i32;
+        let cells: i32 = (-radius..=radius)
+            .map(|dy| 2 * self.shape.half_width(radius, dy) + 1)
+            .sum();
+        cells as u32 - u32::from(!self.middle)
+    }
+}
+
+/// Why a rulestring could not be parsed, shown to the user as-is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleParseError {
+    /// Nothing was typed in
+    Empty,
+    /// The string doesn't have the `birth/survival` shape
+    MissingSlash,
+    /// A character that has no meaning at that point in the rulestring
+    UnexpectedChar { ch: char, index: usize },
+    /// The same `B` or `S` section was given twice
+    DuplicateSection(char),
+    /// A neighbor count that can't happen in the chosen neighborhood
+    CountOutOfRange { count: usize, max: usize },
+    /// A Generations state count that isn't a number from 2 to 255
+    InvalidStates(String),
+    /// A part of a Larger than Life rule that doesn't make sense
+    InvalidRangePart(String),
+    /// A Larger than Life rule without its range, birth or survival part
+    MissingRangePart(char),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "rulestring is empty"),
+            Self::MissingSlash => {
+                write!(
+                    f,
+                    "expected a '/' between the birth and survival conditions"
+                )
+            }
+            Self::UnexpectedChar { ch, index } => {
+                write!(f, "unexpected character '{ch}' at position {}", index + 1)
+            }
+            Self::DuplicateSection(section) => {
+                write!(f, "the '{section}' section appears more than once")
+            }
+            Self::CountOutOfRange { count, max } => write!(
+                f,
+                "a cell can't have {count} neighbors in this neighborhood (max is {max})"
+            ),
+            Self::InvalidStates(states) => {
+                write!(f, "'{states}' isn't a number of states from 2 to 255")
+            }
+            Self::InvalidRangePart(part) => {
+                write!(f, "'{part}' isn't a valid Larger than Life setting")
+            }
+            Self::MissingRangePart(key) => {
+                write!(f, "Larger than Life rules need a '{key}' part")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+/// An outer-totalistic rule, e.g. Conway's Life (`B3/S23`)
+///
+/// Stored as bitmasks for every neighbor count: bit `i` of `birth[n]` is set when a dead cell with
+/// `n` live neighbors arranged like the `i`th Hensel letter for `n` is born, and likewise for
+/// `survival`. Counts without letters only use bit 0.
+///
+/// Rules that don't care about the arrangement of neighbors, only how many there are, are
+/// totalistic, which is all the dense engine can run.
+///
+/// With more than two `states` it's a Generations rule: a live cell that doesn't survive goes to
+/// state 2, then 3 and so on every generation until it wraps around to dead. Only live cells
+/// (state 1) count as neighbors, and cells on their way out can't be born again until they're
+/// fully dead.
+///
+/// Larger than Life rules count neighbors over a wider `range` instead, and only ever look at how
+/// many there are.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rule {
+    birth: [u16; 9],
+    survival: [u16; 9],
+    neighborhood: Neighborhood,
+    /// Number of states a cell can be in, counting dead and alive, 2 for ordinary rules
+    states: u8,
+    /// Worked out once up front, it gets asked for every cell
+    totalistic: bool,
+    /// Set for Larger than Life rules, which ignore `birth`, `survival` and `neighborhood`
+    range: Option<Range>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::conway()
+    }
+}
+
+impl Rule {
+    /// Conway's Game of Life, `B3/S23`
+    pub fn conway() -> Self {
+        Self::new(&[3], &[2, 3], Neighborhood::Moore)
+    }
+
+    /// Build a rule from lists of birth and survival counts
+    pub fn new(birth: &[usize], survival: &[usize], neighborhood: Neighborhood) -> Self {
+        let mask = |counts: &[usize]| {
+            let mut masks = [0; 9];
+            for &n in counts {
+                masks[n] = Self::full(neighborhood, n);
+            }
+            masks
+        };
+        Self::build(mask(birth), mask(survival), neighborhood, 2)
+    }
+
+    fn build(birth: [u16; 9], survival: [u16; 9], neighborhood: Neighborhood, states: u8) -> Self {
+        let totalistic = (0..9).all(|n| {
+            let full = Self::full(neighborhood, n);
+            [birth[n], survival[n]]
+                .iter()
+                .all(|&mask| mask == 0 || mask == full)
+        });
+        Self {
+            birth,
+            survival,
+            neighborhood,
+            states,
+            totalistic,
+            range: None,
+        }
+    }
+
+    /// The same rule as a Generations rule with `states` states, 2 makes it an ordinary one
+    pub fn with_states(self, states: u8) -> Self {
+        Self {
+            states: states.max(2),
+            ..self
+        }
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// The Larger than Life neighborhood, for rules that have one
+    pub fn range(&self) -> Option<&Range> {
+        self.range.as_ref()
+    }
+
+    /// Whether a dead cell with `n` live neighbors is born, in at least one arrangement of them
+    pub fn births_on(&self, n: usize) -> bool {
+        match &self.range {
+            Some(range) => range.birth.contains(&(n as u32)),
+            None => self.birth[n] != 0,
+        }
+    }
+
+    /// Whether a live cell with `n` live neighbors survives, in at least one arrangement of them
+    pub fn survives_on(&self, n: usize) -> bool {
+        match &self.range {
+            Some(range) => range.survival.contains(&(n as u32)),
+            None => self.survival[n] != 0,
+        }
+    }
+
+    /// Whether only the number of neighbors matters, not how they're arranged
+    pub fn is_totalistic(&self) -> bool {
+        self.totalistic
+    }
+
+    /// The state a cell will be in next generation, given its current state and neighbor count.
+    /// Only makes sense for totalistic rules
+    pub fn next_state(&self, alive: bool, n: usize) -> bool {
+        if alive {
+            self.survives_on(n)
+        } else {
+            self.births_on(n)
+        }
+    }
+
+    /// The state a cell will be in next generation, given its current state and which of its
+    /// Moore neighbors are alive, as bits of [`hensel::RING`]. Works for any Moore rule
+    pub fn next_state_isotropic(&self, alive: bool, ring: u8) -> bool {
+        let (n, letter) = hensel::classify(ring);
+        let masks = if alive { &self.survival } else { &self.birth };
+        masks[n] & (1 << letter) != 0
+    }
+
+    /// Number of states a cell can be in, counting dead and alive
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Whether cells that die pass through refractory states first
+    pub fn is_generations(&self) -> bool {
+        self.states > 2
+    }
+
+    /// The state a cell dying under this rule moves on to after `state`, 0 once it's gone
+    pub fn next_dying_state(&self, state: u8) -> u8 {
+        let next = state.saturating_add(1);
+        if next >= self.states { 0 } else { next }
+    }
+
+    /// Rules with `B0` turn empty space alive, so every cell of the map has to be looked at
+    pub fn births_from_nothing(&self) -> bool {
+        self.births_on(0)
+    }
+
+    /// Letters that can follow count `n` in this neighborhood
+    fn letters(neighborhood: Neighborhood, n: usize) -> &'static str {
+        match neighborhood {
+            Neighborhood::Moore => hensel::letters(n),
+            Neighborhood::VonNeumann | Neighborhood::Hexagonal => "",
+        }
+    }
+
+    /// The mask for count `n` with every arrangement of neighbors included
+    fn full(neighborhood: Neighborhood, n: usize) -> u16 {
+        (1 << Self::letters(neighborhood, n).len().max(1)) - 1
+    }
+
+    /// One side of the slash written out, using whichever of the letters or the letters left out
+    /// is shorter
+    fn counts(&self, masks: &[u16; 9]) -> String {
+        let mut out = String::new();
+        for (n, &mask) in masks.iter().enumerate().filter(|(_, mask)| **mask != 0) {
+            out.push(char::from(b'0' + n as u8));
+            if mask == Self::full(self.neighborhood, n) {
+                continue;
+            }
+            let letters = Self::letters(self.neighborhood, n);
+            let minus = mask.count_ones() as usize * 2 > letters.len();
+            if minus {
+                out.push('-');
+            }
+            out.extend(
+                letters
+                    .chars()
+                    .enumerate()
+                    .filter(|&(i, _)| (mask & (1 << i) != 0) != minus)
+                    .map(|(_, letter)| letter),
+            );
+        }
+        out
+    }
+
+    /// Parse one side of the slash (counts, each optionally followed by Hensel letters) into a
+    /// mask for every count
+    fn parse_counts(
+        digits: &str,
+        offset: usize,
+        neighborhood: Neighborhood,
+    ) -> Result<[u16; 9], RuleParseError> {
+        let mut masks = [0; 9];
+        let max = neighborhood.max_count();
+        // Count being written out, its letters so far and whether they're the ones to leave out
+        let mut current: Option<(usize, u16, bool)> = None;
+        let finish = |masks: &mut [u16; 9], current: Option<(usize, u16, bool)>| {
+            if let Some((n, letters, minus)) = current {
+                let full = Self::full(neighborhood, n);
+                masks[n] |= match (letters, minus) {
+                    (0, _) => full,
+                    (letters, true) => full & !letters,
+                    (letters, false) => letters,
+                };
+            }
+        };
+        for (i, ch) in digits.char_indices() {
+            let unexpected = RuleParseError::UnexpectedChar {
+                ch,
+                index: offset + i,
+            };
+            if let Some(n) = ch.to_digit(10) {
+                let n = n as usize;
+                if n > max {
+                    return Err(RuleParseError::CountOutOfRange { count: n, max });
+                }
+                finish(&mut masks, current.replace((n, 0, false)));
+                continue;
+            }
+            let Some((n, letters, minus)) = &mut current else {
+                return Err(unexpected);
+            };
+            if ch == '-' && *letters == 0 && !*minus {
+                *minus = true;
+                continue;
+            }
+            let index = Self::letters(neighborhood, *n)
+                .find(ch.to_ascii_lowercase())
+                .ok_or(unexpected)?;
+            *letters |= 1 << index;
+        }
+        finish(&mut masks, current);
+        Ok(masks)
+    }
+
+    /// Parse a Larger than Life rule, a comma separated list of settings each starting with a
+    /// letter: `R5,C0,M1,S34..58,B34..45,NM`
+    fn parse_range(s: &str) -> Result<Self, RuleParseError> {
+        let mut radius = None;
+        let mut states = 2;
+        let mut middle = false;
+        let mut birth = None;
+        let mut survival = None;
+        let mut shape = RangeShape::Moore;
+        for part in s.split(',').map(str::trim) {
+            let invalid = || RuleParseError::InvalidRangePart(part.to_owned());
+            let mut chars = part.chars();
+            let key = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+            let value = chars.as_str();
+            let counts = || -> Result<RangeInclusive<u32>, RuleParseError> {
+                let (min, max) = value.split_once("..").ok_or_else(invalid)?;
+                match (min.parse(), max.parse()) {
+                    (Ok(min), Ok(max)) if min <= max => Ok(min..=max),
+                    _ => Err(invalid()),
+                }
+            };
+            match key {
+                'R' => match value.parse() {
+                    Ok(r) if (1..=MAX_RANGE).contains(&r) => radius = Some(r),
+                    _ => return Err(invalid()),
+                },
+                // Golly writes two state rules as C0, C1 and C2 mean the same
+                'C' => match value.parse::<u8>() {
+                    Ok(n) => states = n.max(2),
+                    Err(_) => return Err(RuleParseError::InvalidStates(value.to_owned())),
+                },
+                'M' => match value {
+                    "0" => middle = false,
+                    "1" => middle = true,
+                    _ => return Err(invalid()),
+                },
+                'S' => survival = Some(counts()?),
+                'B' => birth = Some(counts()?),
+                'N' => {
+                    shape = match value.to_ascii_uppercase().as_str() {
+                        "M" => RangeShape::Moore,
+                        "N" => RangeShape::VonNeumann,
+                        "C" => RangeShape::Circular,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        let range = Range {
+            radius: radius.ok_or(RuleParseError::MissingRangePart('R'))?,
+            shape,
+            middle,
+            birth: birth.ok_or(RuleParseError::MissingRangePart('B'))?,
+            survival: survival.ok_or(RuleParseError::MissingRangePart('S'))?,
+        };
+        let max = range.max_count();
+        let highest = (*range.birth.end()).max(*range.survival.end());
+        if highest > max {
+            return Err(RuleParseError::CountOutOfRange {
+                count: highest as usize,
+                max: max as usize,
+            });
+        }
+        Ok(Self {
+            range: Some(range),
+            ..Self::build([0; 9], [0; 9], Neighborhood::Moore, states)
+        })
+    }
+}
+
+impl std::str::FromStr for Rule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+        // Offset of `trimmed` inside `s`, so error positions line up with what the user typed
+        let lead = s.len() - s.trim_start().len();
+
+        if trimmed.starts_with(['R', 'r']) && trimmed.contains(',') {
+            return Self::parse_range(trimmed);
+        }
+
+        let (body, neighborhood) = match trimmed.chars().last() {
+            Some('V' | 'v') => (&trimmed[..trimmed.len() - 1], Neighborhood::VonNeumann),
+            Some('H' | 'h') => (&trimmed[..trimmed.len() - 1], Neighborhood::Hexagonal),
+            _ => (trimmed, Neighborhood::Moore),
+        };
+
+        let (left, right) = body.split_once('/').ok_or(RuleParseError::MissingSlash)?;
+        let right_offset = lead + left.len() + 1;
+        // Generations rules have the number of states as a third part, with or without a `C`
+        let (right, states) = match right.split_once('/') {
+            Some((right, states)) => {
+                let digits = states.strip_prefix(['C', 'c', 'G', 'g']).unwrap_or(states);
+                match digits.parse::<u8>() {
+                    Ok(n) if n >= 2 => (right, n),
+                    _ => return Err(RuleParseError::InvalidStates(states.to_owned())),
+                }
+            }
+            None => (right, 2),
+        };
+
+        let is_bs = |part: &str| part.starts_with(['B', 'b', 'S', 's']);
+        let (birth, survival) = if is_bs(left) || is_bs(right) {
+            let mut birth = None;
+            let mut survival = None;
+            for (part, offset) in [(left, lead), (right, right_offset)] {
+                let mut chars = part.chars();
+                let (slot, section) = match chars.next() {
+                    Some('B' | 'b') => (&mut birth, 'B'),
+                    Some('S' | 's') => (&mut survival, 'S'),
+                    Some(ch) => return Err(RuleParseError::UnexpectedChar { ch, index: offset }),
+                    None => return Err(RuleParseError::MissingSlash),
+                };
+                if slot.is_some() {
+                    return Err(RuleParseError::DuplicateSection(section));
+                }
+                *slot = Some(Self::parse_counts(
+                    chars.as_str(),
+                    offset + 1,
+                    neighborhood,
+                )?);
+            }
+            (birth.unwrap_or_default(), survival.unwrap_or_default())
+        } else {
+            let survival = Self::parse_counts(left, lead, neighborhood)?;
+            let birth = Self::parse_counts(right, right_offset, neighborhood)?;
+            (birth, survival)
+        };
+
+        Ok(Self::build(birth, survival, neighborhood, states))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(range) = &self.range {
+            return write!(
+                f,
+                "R{},C{},M{},S{}..{},B{}..{},N{}",
+                range.radius,
+                if self.is_generations() {
+                    self.states
+                } else {
+                    0
+                },
+                u8::from(range.middle),
+                range.survival.start(),
+                range.survival.end(),
+                range.birth.start(),
+                range.birth.end(),
+                range.shape.letter()
+            );
+        }
+        write!(
+            f,
+            "B{}/S{}",
+            self.counts(&self.birth),
+            self.counts(&self.survival)
+        )?;
+        if self.is_generations() {
+            write!(f, "/C{}", self.states)?;
+        }
+        write!(f, "{}", self.neighborhood.suffix())
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = RuleParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
+
+/// Well known Life-like rules, offered in the rule preset dropdown
+pub const PRESETS: &[(&str, &str)] = &[
+    ("Conway's Life", "B3/S23"),
+    ("HighLife", "B36/S23"),
+    ("Day & Night", "B3678/S34678"),
+    ("Seeds", "B2/S"),
+    ("Replicator", "B1357/S1357"),
+    ("Maze", "B3/S12345"),
+    ("Mazectric", "B3/S1234"),
+    ("Life without Death", "B3/S012345678"),
+    ("2x2", "B36/S125"),
+    ("34 Life", "B34/S34"),
+    ("Move", "B368/S245"),
+    ("Diamoeba", "B35678/S5678"),
+    ("Coral", "B3/S45678"),
+    ("Anneal", "B4678/S35678"),
+    ("Long Life", "B345/S5"),
+    ("Gnarl", "B1/S1"),
+    ("Brian's Brain", "B2/S/C3"),
+    ("Star Wars", "B2/S345/C4"),
+    ("Frogs", "B34/S12/C3"),
+    ("Bloomerang", "B34678/S234/C24"),
+    ("tlife", "B3/S2-i34q"),
+    ("Just Friends", "B2-a/S12"),
+    ("Hex Life", "B2/S34H"),
+    ("Bosco's Rule", "R5,C0,M1,S34..58,B34..45,NM"),
+    ("Majority", "R4,C0,M1,S41..81,B41..81,NM"),
+    ("Waffle", "R7,C0,M1,S100..200,B75..170,NM"),
+    ("Globe", "R8,C0,M0,S163..223,B74..252,NM"),
+];
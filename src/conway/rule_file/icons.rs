@@ -0,0 +1,159 @@
+//! The `@ICONS` section: pictures drawn in place of plain squares once cells are big enough
+//!
+//! Icons are XPM images, one per size, each as wide as an icon and tall enough for one icon per
+//! live state stacked top to bottom:
+//!
+//! ```text
+//! XPM
+//! /* width height num_colors chars_per_pixel */
+//! "7 14 2 1"
+//! ". c #000000"
+//! "A c #FFFFFF"
+//! "..AAA.."
+//! ...
+//! ```
+//!
+//! Black and `None` pixels are left out so the background shows through. Like Golly, icons made
+//! only of grays take their color from the state's `@COLORS` entry, scaled by how bright each
+//! pixel is. Instead of images the section can also just name a shape: `circles`, `diamonds` or
+//! `hexagons`.
+
+use egui::Color32;
+
+use super::RuleFileError;
+
+/// One icon, every pixel row by row, `None` for ones that aren't drawn
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Icon {
+    pub size: usize,
+    pub pixels: Vec<Option<Color32>>,
+    /// Whether the pixels are shades of gray to be tinted with the state's color
+    pub grayscale: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Icons {
+    Circles,
+    Diamonds,
+    Hexagons,
+    /// Icons for every live state, at each size they were given in, smallest first
+    Images(Vec<Vec<Icon>>),
+}
+
+impl Icons {
+    /// Read an `@ICONS` section, `None` if there's nothing in it
+    pub fn parse(lines: &[(usize, &str)]) -> Result<Option<Self>, RuleFileError> {
+        if let Some(&(_, name)) = lines.first() {
+            match name {
+                "circles" => return Ok(Some(Self::Circles)),
+                "diamonds" => return Ok(Some(Self::Diamonds)),
+                "hexagons" => return Ok(Some(Self::Hexagons)),
+                _ => {}
+            }
+        }
+        // Everything but the quoted strings is comments and the XPM markers
+        let mut strings = lines.iter().filter_map(|&(line, text)| {
+            let text = text.trim().trim_end_matches(',');
+            let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+            Some((line, inner))
+        });
+        let mut images = Vec::new();
+        while let Some((line, header)) = strings.next() {
+            images.push(xpm(line, header, &mut strings)?);
+        }
+        if images.is_empty() {
+            return Ok(None);
+        }
+        images.sort_by_key(|sizes: &Vec<Icon>| sizes.first().map_or(0, |icon| icon.size));
+        Ok(Some(Self::Images(images)))
+    }
+
+    /// The biggest icon for `state` that fits in `cell_size` pixels
+    pub fn fitting(&self, state: u8, cell_size: f32) -> Option<&Icon> {
+        let Self::Images(images) = self else {
+            return None;
+        };
+        images
+            .iter()
+            .rev()
+            .filter_map(|icons| icons.get(usize::from(state).checked_sub(1)?))
+            .find(|icon| icon.size as f32 <= cell_size)
+    }
+}
+
+/// One XPM image after its `header` string, cut into an icon per state
+fn xpm<'a>(
+    line: usize,
+    header: &str,
+    strings: &mut impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Vec<Icon>, RuleFileError> {
+    let bad = |line: usize| RuleFileError::BadIcon { line };
+    let numbers: Vec<usize> = header
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_err| bad(line))?;
+    let &[width, height, colors, chars] = numbers.as_slice() else {
+        return Err(bad(line));
+    };
+    if width == 0 || chars == 0 || height % width != 0 {
+        return Err(bad(line));
+    }
+
+    let mut palette = Vec::with_capacity(colors);
+    for _ in 0..colors {
+        let (line, entry) = strings.next().ok_or(bad(line))?;
+        let key = entry.get(..chars).ok_or(bad(line))?.to_owned();
+        let color = entry[chars..]
+            .split_whitespace()
+            .skip_while(|&word| word != "c")
+            .nth(1)
+            .ok_or(bad(line))?;
+        palette.push((key, parse_color(color).ok_or(bad(line))?));
+    }
+    let grayscale = palette
+        .iter()
+        .map(|&(_, color)| color)
+        .filter(|&color| color != Color32::TRANSPARENT)
+        .all(|c| c.r() == c.g() && c.g() == c.b());
+
+    let mut rows = Vec::with_capacity(height);
+    for _ in 0..height {
+        let (line, row) = strings.next().ok_or(bad(line))?;
+        let pixels = (0..width)
+            .map(|x| {
+                let key = row.get(x * chars..(x + 1) * chars)?;
+                let &(_, color) = palette.iter().find(|(k, _)| k == key)?;
+                Some((color != Color32::TRANSPARENT).then_some(color))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(bad(line))?;
+        rows.push(pixels);
+    }
+    Ok(rows
+        .chunks(width)
+        .map(|icon| Icon {
+            size: width,
+            pixels: icon.concat(),
+            grayscale,
+        })
+        .collect())
+}
+
+/// `#RRGGBB`, with black and `None` being see-through
+fn parse_color(text: &str) -> Option<Color32> {
+    if text.eq_ignore_ascii_case("none") {
+        return Some(Color32::TRANSPARENT);
+    }
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let color = Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?);
+    Some(if color == Color32::BLACK {
+        Color32::TRANSPARENT
+    } else {
+        color
+    })
+}
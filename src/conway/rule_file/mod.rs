@@ -0,0 +1,399 @@
+//! Golly `.rule` files, for custom rules with any number of states
+//!
+//! A rule file is a handful of sections, each starting with a line beginning with `@`:
+//! * `@RULE name`, then anything up to the next section describes the rule
+//! * `@TABLE`, the rule written as a table of transitions, see [`table`]
+//! * `@TREE`, the rule as a decision tree, see [`tree`]
+//! * `@COLORS`, a color for each state, either `state r g b` or a gradient `r g b r g b` running
+//!   from state 1 to the last one
+//! * `@ICONS`, pictures for each state, see [`icons`]
+//!
+//! Everything else, like `@NAMES`, is skipped over. A rule needs a table or a tree, and if it
+//! has both the table gets used. Once loaded, a rule file takes over from the rulestring and runs
+//! on the map's own cells, with live cells in state 1 and every higher state where a Generations
+//! rule would keep its dying cells.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use egui::Color32;
+
+use super::boundary::Boundary;
+use super::engine::Universe;
+use super::rule::Neighborhood;
+use crate::Pos;
+
+pub mod icons;
+pub mod table;
+pub mod tree;
+
+pub use icons::{Icon, Icons};
+pub use table::Table;
+pub use tree::Tree;
+
+/// Why a rule file couldn't be read, shown to the user as-is. Lines are counted from 1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleFileError {
+    /// There's no `@RULE` line naming the rule
+    MissingName,
+    /// There's neither a `@TABLE` nor a `@TREE` to run
+    NoTransitions,
+    /// A setting the table or tree can't do without
+    MissingSetting(&'static str),
+    /// A line in the settings that isn't one
+    Setting {
+        line: usize,
+        text: String,
+    },
+    /// A number of states that isn't from 2 to 256
+    InvalidStates(String),
+    UnknownNeighborhood(String),
+    UnknownSymmetry(String),
+    /// A name in a transition that isn't a state or a variable
+    UnknownVariable {
+        line: usize,
+        name: String,
+    },
+    /// A state past the last one the rule has
+    StateOutOfRange {
+        line: usize,
+        state: u16,
+    },
+    /// A transition with the wrong number of entries for the neighborhood
+    WrongLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A transition whose new state could be more than one thing
+    UnboundOutput {
+        line: usize,
+    },
+    /// A tree node that doesn't fit with the ones before it
+    BadNode {
+        line: usize,
+    },
+    /// A tree with no nodes, or a root at the wrong level
+    EmptyTree,
+    BadColor {
+        line: usize,
+    },
+    BadIcon {
+        line: usize,
+    },
+}
+
+impl fmt::Display for RuleFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingName => write!(f, "no '@RULE' line giving the rule a name"),
+            Self::NoTransitions => write!(f, "no '@TABLE' or '@TREE' section to run"),
+            Self::MissingSetting(setting) => write!(f, "'{setting}' needs setting first"),
+            Self::Setting { line, text } => write!(f, "line {line}: '{text}' isn't a setting"),
+            Self::InvalidStates(states) => {
+                write!(f, "'{states}' isn't a number of states from 2 to 256")
+            }
+            Self::UnknownNeighborhood(name) => write!(f, "unknown neighborhood '{name}'"),
+            Self::UnknownSymmetry(name) => {
+                write!(f, "unknown symmetry '{name}' for this neighborhood")
+            }
+            Self::UnknownVariable { line, name } => {
+                write!(f, "line {line}: '{name}' isn't a state or a variable")
+            }
+            Self::StateOutOfRange { line, state } => {
+                write!(f, "line {line}: the rule doesn't have a state {state}")
+            }
+            Self::WrongLength {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} entries, found {found}"),
+            Self::UnboundOutput { line } => write!(
+                f,
+                "line {line}: the new state has to be a state or a variable used before it"
+            ),
+            Self::BadNode { line } => write!(f, "line {line}: not a valid tree node"),
+            Self::EmptyTree => write!(f, "the tree has no root at the right level"),
+            Self::BadColor { line } => write!(f, "line {line}: not a state and a color"),
+            Self::BadIcon { line } => write!(f, "line {line}: can't read this icon"),
+        }
+    }
+}
+
+impl std::error::Error for RuleFileError {}
+
+/// The neighbors transitions look at for `neighborhood`, clockwise from north with y going down,
+/// which is the order tables list them in
+pub fn ring(neighborhood: Neighborhood) -> &'static [(i32, i32)] {
+    match neighborhood {
+        Neighborhood::Moore => &[
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+        ],
+        Neighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+        Neighborhood::Hexagonal => &[(0, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)],
+    }
+}
+
+/// How a rule file works out new states
+#[derive(Debug)]
+pub enum Transitions {
+    Table(Table),
+    Tree(Tree),
+}
+
+impl Transitions {
+    pub fn states(&self) -> usize {
+        match self {
+            Self::Table(table) => table.states(),
+            Self::Tree(tree) => tree.states(),
+        }
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        match self {
+            Self::Table(table) => table.neighborhood(),
+            Self::Tree(tree) => tree.neighborhood(),
+        }
+    }
+
+    /// The state a cell in `cell` goes to with `neighbors` round it, in [`ring`] order
+    pub fn next(&self, cell: u8, neighbors: &[u8]) -> u8 {
+        match self {
+            Self::Table(table) => table.next(cell, neighbors),
+            Self::Tree(tree) => tree.next(cell, neighbors),
+        }
+    }
+}
+
+/// A loaded `.rule` file, persisted as the text it was read from
+///
+/// The compiled transitions and icons are shared between copies, the map gets cloned onto the
+/// worker thread every time it starts.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RuleFile {
+    name: String,
+    description: String,
+    source: String,
+    transitions: Arc<Transitions>,
+    /// Colors from `@COLORS` for each state, where it gave one
+    colors: Vec<Option<Color32>>,
+    icons: Option<Arc<Icons>>,
+}
+
+/// Two rule files are the same rule if they were read from the same text
+impl PartialEq for RuleFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for RuleFile {}
+
+impl RuleFile {
+    pub fn parse(source: &str) -> Result<Self, RuleFileError> {
+        let mut name = None;
+        let mut sections: HashMap<&str, Vec<(usize, &str)>> = HashMap::new();
+        let mut section = "";
+        for (i, raw) in source.lines().enumerate() {
+            let text = raw.trim();
+            if let Some(header) = text.strip_prefix('@') {
+                let (tag, rest) = header
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((header, ""));
+                section = tag;
+                if tag == "RULE" {
+                    name = Some(rest.trim().to_owned());
+                }
+                sections.entry(tag).or_default();
+                continue;
+            }
+            // Icons have `#` in their colors, everywhere else it starts a comment
+            let text = if section == "ICONS" {
+                text
+            } else {
+                text.split('#').next().unwrap_or("").trim()
+            };
+            if !text.is_empty() {
+                sections.entry(section).or_default().push((i + 1, text));
+            }
+        }
+
+        let name = name
+            .filter(|name| !name.is_empty())
+            .ok_or(RuleFileError::MissingName)?;
+        let lines = |tag: &str| sections.get(tag).map_or(&[][..], Vec::as_slice);
+        let transitions = if sections.contains_key("TABLE") {
+            Transitions::Table(Table::parse(lines("TABLE"))?)
+        } else if sections.contains_key("TREE") {
+            Transitions::Tree(Tree::parse(lines("TREE"))?)
+        } else {
+            return Err(RuleFileError::NoTransitions);
+        };
+        let colors = parse_colors(lines("COLORS"), transitions.states())?;
+        let icons = Icons::parse(lines("ICONS"))?.map(Arc::new);
+        let description = lines("RULE")
+            .iter()
+            .map(|&(_, text)| text)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Self {
+            name,
+            description,
+            source: source.to_owned(),
+            transitions: Arc::new(transitions),
+            colors,
+            icons,
+        })
+    }
+
+    /// What the file calls the rule, which is what patterns for it give as their rule
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The text between the `@RULE` line and the next section
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn transitions(&self) -> &Transitions {
+        &self.transitions
+    }
+
+    pub fn states(&self) -> u8 {
+        (self.transitions.states() - 1).min(254) as u8 + 1
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.transitions.neighborhood()
+    }
+
+    /// The color `@COLORS` gave `state`, if it gave one
+    pub fn color(&self, state: u8) -> Option<Color32> {
+        self.colors.get(usize::from(state)).copied().flatten()
+    }
+
+    pub fn icons(&self) -> Option<&Icons> {
+        self.icons.as_deref()
+    }
+
+    /// Advance the universe by 2^`step_log2` generations
+    ///
+    /// Returns the cells in state 1 and the ones in higher states afterwards. If the rule brings
+    /// empty space to life, every cell of a bounded map is looked at, but on an unbounded plane
+    /// only cells next to something are.
+    pub fn step(
+        &self,
+        universe: &Universe<'_>,
+        step_log2: u32,
+    ) -> (HashSet<Pos>, HashMap<Pos, u8>) {
+        let mut states: HashMap<Pos, u8> = universe
+            .cells
+            .iter()
+            .map(|&p| (p, 1))
+            .chain(universe.dying.iter().map(|(&p, &state)| (p, state)))
+            .collect();
+        for _ in 0..1u64 << step_log2 {
+            states = self.generation(&states, universe);
+        }
+        let cells = states
+            .iter()
+            .filter(|&(_, &state)| state == 1)
+            .map(|(&p, _)| p)
+            .collect();
+        states.retain(|_, state| *state > 1);
+        (cells, states)
+    }
+
+    fn generation(&self, states: &HashMap<Pos, u8>, universe: &Universe<'_>) -> HashMap<Pos, u8> {
+        let ring = ring(self.neighborhood());
+        let state = |p: Pos| {
+            universe
+                .wrap(p)
+                .and_then(|p| states.get(&p).copied())
+                .unwrap_or(0)
+        };
+        let from_nothing = self.transitions.next(0, &vec![0; ring.len()]) != 0;
+        let candidates: HashSet<Pos> = if from_nothing && universe.boundary != Boundary::Plane {
+            (0..universe.height)
+                .flat_map(|y| (0..universe.width).map(move |x| Pos(x, y)))
+                .collect()
+        } else {
+            states
+                .keys()
+                .flat_map(|&p| {
+                    std::iter::once(p).chain(
+                        ring.iter()
+                            .filter_map(move |&(dx, dy)| universe.wrap(Pos(p.0 + dx, p.1 + dy))),
+                    )
+                })
+                .collect()
+        };
+        let mut neighbors = vec![0; ring.len()];
+        candidates
+            .into_iter()
+            .filter_map(|p| {
+                for (n, &(dx, dy)) in neighbors.iter_mut().zip(ring) {
+                    *n = state(Pos(p.0 + dx, p.1 + dy));
+                }
+                let new = self.transitions.next(state(p), &neighbors);
+                (new != 0).then_some((p, new))
+            })
+            .collect()
+    }
+}
+
+/// `@COLORS`: `state r g b` lines, or `r g b r g b` for a gradient over the live states
+fn parse_colors(
+    lines: &[(usize, &str)],
+    states: usize,
+) -> Result<Vec<Option<Color32>>, RuleFileError> {
+    let mut colors = vec![None; states];
+    for &(line, text) in lines {
+        let numbers: Vec<u8> = text
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|n| !n.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_err| RuleFileError::BadColor { line })?;
+        match *numbers.as_slice() {
+            [state, r, g, b] => {
+                if let Some(color) = colors.get_mut(usize::from(state)) {
+                    *color = Some(Color32::from_rgb(r, g, b));
+                }
+            }
+            [r1, g1, b1, r2, g2, b2] => {
+                let (from, to) = (Color32::from_rgb(r1, g1, b1), Color32::from_rgb(r2, g2, b2));
+                let live = states.saturating_sub(2).max(1) as f32;
+                for (i, color) in colors.iter_mut().enumerate().skip(1) {
+                    *color = Some(from.lerp_to_gamma(to, (i - 1) as f32 / live));
+                }
+            }
+            _ => return Err(RuleFileError::BadColor { line }),
+        }
+    }
+    Ok(colors)
+}
+
+impl TryFrom<String> for RuleFile {
+    type Error = RuleFileError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+impl From<RuleFile> for String {
+    fn from(rule: RuleFile) -> Self {
+        rule.source
+    }
+}
@@ -0,0 +1,414 @@
+//! The `@TABLE` section: transitions written out one per line, compiled into bitsets
+//!
+//! A table starts with its settings and variables:
+//!
+//! ```text
+//! n_states:3
+//! neighborhood:Moore
+//! symmetries:rotate4reflect
+//! var a={0,1,2}
+//! ```
+//!
+//! and every line after that is a transition: the cell's state, each neighbor's state going
+//! clockwise from north, and the state the cell becomes. A variable stands for any of its states,
+//! and one used more than once in the same transition has to be the same state every time. The
+//! first transition that matches wins, and a cell no transition matches stays as it is.
+//!
+//! Like Golly, the table gets compiled by expanding every variable that's used more than once and
+//! every symmetry into plain transitions, and then working out for each position and state which
+//! transitions allow that state there. Finding the transition for a cell is then just a bitwise AND
+//! of one bitset per position, taking the lowest bit left.
+
+use std::collections::{HashMap, HashSet};
+
+use super::RuleFileError;
+use crate::conway::rule::Neighborhood;
+
+/// A compiled `@TABLE`
+#[derive(Clone, Debug)]
+pub struct Table {
+    states: u8,
+    neighborhood: Neighborhood,
+    /// How many 64 bit words one bitset over every transition takes up
+    words: usize,
+    /// For each position (the cell, then its neighbors) and state, the transitions that allow it
+    masks: Vec<u64>,
+    /// What each transition turns the cell into
+    outputs: Vec<u8>,
+}
+
+/// One entry of a transition before variables that are used more than once get expanded
+#[derive(Clone, Debug)]
+enum Token {
+    /// Any of these states
+    States(Vec<u8>),
+    /// A variable, which is bound if it comes up more than once
+    Var(String),
+}
+
+impl Table {
+    /// Compile the lines of a `@TABLE` section, each with its line number in the file
+    pub fn parse(lines: &[(usize, &str)]) -> Result<Self, RuleFileError> {
+        let mut states = None;
+        let mut neighborhood = Neighborhood::Moore;
+        let mut symmetries = "none".to_owned();
+        let mut vars: HashMap<String, Vec<u8>> = HashMap::new();
+        // Expanded transitions as a set of states for every position, and the output
+        let mut transitions: Vec<(Vec<Vec<u8>>, u8)> = Vec::new();
+
+        for &(line, text) in lines {
+            if let Some((key, value)) = text.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" | "num_states" => {
+                        let n = value
+                            .parse::<u16>()
+                            .ok()
+                            .filter(|n| (2..=256).contains(n))
+                            .ok_or_else(|| RuleFileError::InvalidStates(value.to_owned()))?;
+                        states = Some((n - 1) as u8);
+                    }
+                    "neighborhood" => neighborhood = parse_neighborhood(value)?,
+                    "symmetries" => symmetries = value.to_owned(),
+                    _ => {
+                        return Err(RuleFileError::Setting {
+                            line,
+                            text: text.to_owned(),
+                        });
+                    }
+                }
+                continue;
+            }
+            // States are kept as the highest one until the table's read, so 256 of them fits
+            let top = states.ok_or(RuleFileError::MissingSetting("n_states"))?;
+            if let Some(var) = text.strip_prefix("var ") {
+                let (name, set) = var.split_once('=').ok_or_else(|| RuleFileError::Setting {
+                    line,
+                    text: text.to_owned(),
+                })?;
+                let set = parse_set(set.trim(), &vars, top, line)?;
+                vars.insert(name.trim().to_owned(), set);
+                continue;
+            }
+
+            let tokens = tokenize(text)
+                .into_iter()
+                .map(|token| parse_token(token, &vars, top, line))
+                .collect::<Result<Vec<_>, _>>()?;
+            let expected = neighborhood.max_count() + 2;
+            if tokens.len() != expected {
+                return Err(RuleFileError::WrongLength {
+                    line,
+                    expected,
+                    found: tokens.len(),
+                });
+            }
+            for (inputs, output) in bind(&tokens, &vars, line)? {
+                for inputs in symmetric(&inputs, &symmetries)? {
+                    transitions.push((inputs, output));
+                }
+            }
+        }
+
+        let states = states.ok_or(RuleFileError::MissingSetting("n_states"))?;
+        Ok(Self::compile(
+            usize::from(states) + 1,
+            neighborhood,
+            transitions,
+        ))
+    }
+
+    fn compile(
+        states: usize,
+        neighborhood: Neighborhood,
+        transitions: Vec<(Vec<Vec<u8>>, u8)>,
+    ) -> Self {
+        // The same transition can come out of more than one symmetry, only the first one counts
+        let mut seen = HashSet::new();
+        let transitions: Vec<_> = transitions
+            .into_iter()
+            .filter(|transition| seen.insert(transition.clone()))
+            .collect();
+        let positions = neighborhood.max_count() + 1;
+        let words = transitions.len().div_ceil(64).max(1);
+        let mut masks = vec![0; positions * states * words];
+        for (i, (inputs, _)) in transitions.iter().enumerate() {
+            for (position, set) in inputs.iter().enumerate() {
+                for &state in set {
+                    masks[(position * states + usize::from(state)) * words + i / 64] |=
+                        1 << (i % 64);
+                }
+            }
+        }
+        Self {
+            states: (states - 1) as u8,
+            neighborhood,
+            words,
+            masks,
+            outputs: transitions.into_iter().map(|(_, output)| output).collect(),
+        }
+    }
+
+    /// Number of states, the highest being one less
+    pub fn states(&self) -> usize {
+        usize::from(self.states) + 1
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// How many plain transitions the table came out as once everything was expanded
+    pub fn transitions(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// The state a cell in `cell` goes to with `neighbors` round it, clockwise from north
+    pub fn next(&self, cell: u8, neighbors: &[u8]) -> u8 {
+        let states = self.states();
+        let mask = |position: usize, state: u8, word: usize| {
+            self.masks
+                .get((position * states + usize::from(state)) * self.words + word)
+                .copied()
+                .unwrap_or(0)
+        };
+        for word in 0..self.words {
+            let matching = neighbors
+                .iter()
+                .enumerate()
+                .fold(mask(0, cell, word), |bits, (i, &state)| {
+                    bits & mask(i + 1, state, word)
+                });
+            if matching != 0 {
+                let transition = word * 64 + matching.trailing_zeros() as usize;
+                return self.outputs.get(transition).copied().unwrap_or(cell);
+            }
+        }
+        cell
+    }
+}
+
+fn parse_neighborhood(name: &str) -> Result<Neighborhood, RuleFileError> {
+    match name {
+        "Moore" => Ok(Neighborhood::Moore),
+        "vonNeumann" => Ok(Neighborhood::VonNeumann),
+        "hexagonal" => Ok(Neighborhood::Hexagonal),
+        _ => Err(RuleFileError::UnknownNeighborhood(name.to_owned())),
+    }
+}
+
+/// Split a transition into its entries. Golly lets tables with fewer than 11 states leave out
+/// the commas, so a line of nothing but digits is one entry per digit
+fn tokenize(text: &str) -> Vec<&str> {
+    if text.chars().all(|c| c.is_ascii_digit()) {
+        return (0..text.len()).map(|i| &text[i..=i]).collect();
+    }
+    let mut tokens = Vec::new();
+    let (mut start, mut depth) = (None, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => {
+                depth += 1;
+                start.get_or_insert(i);
+            }
+            '}' => depth -= 1,
+            ',' | ' ' | '\t' if depth == 0 => {
+                if let Some(s) = start.take() {
+                    tokens.push(&text[s..i]);
+                }
+            }
+            _ => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&text[s..]);
+    }
+    tokens
+}
+
+fn parse_state(text: &str, top: u8, line: usize) -> Result<u8, RuleFileError> {
+    match text.parse::<u16>() {
+        Ok(state) if state <= u16::from(top) => Ok(state as u8),
+        Ok(state) => Err(RuleFileError::StateOutOfRange { line, state }),
+        Err(_) => Err(RuleFileError::UnknownVariable {
+            line,
+            name: text.to_owned(),
+        }),
+    }
+}
+
+/// `{0,1,a}`, where variables in the list stand for all of their states
+fn parse_set(
+    text: &str,
+    vars: &HashMap<String, Vec<u8>>,
+    top: u8,
+    line: usize,
+) -> Result<Vec<u8>, RuleFileError> {
+    let inner = text.trim_start_matches('{').trim_end_matches('}');
+    let mut set = Vec::new();
+    for item in inner.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match vars.get(item) {
+            Some(states) => set.extend(states),
+            None => set.push(parse_state(item, top, line)?),
+        }
+    }
+    Ok(set)
+}
+
+fn parse_token(
+    text: &str,
+    vars: &HashMap<String, Vec<u8>>,
+    top: u8,
+    line: usize,
+) -> Result<Token, RuleFileError> {
+    if text.starts_with('{') {
+        parse_set(text, vars, top, line).map(Token::States)
+    } else if vars.contains_key(text) {
+        Ok(Token::Var(text.to_owned()))
+    } else {
+        parse_state(text, top, line).map(|state| Token::States(vec![state]))
+    }
+}
+
+/// The states each input of a transition allows, and its output
+type Bound = (Vec<Vec<u8>>, u8);
+
+/// Every way of giving the variables that come up more than once one state each, as the sets of
+/// states each input allows and the output
+fn bind(
+    tokens: &[Token],
+    vars: &HashMap<String, Vec<u8>>,
+    line: usize,
+) -> Result<Vec<Bound>, RuleFileError> {
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for token in tokens {
+        if let Token::Var(name) = token {
+            *uses.entry(name).or_default() += 1;
+        }
+    }
+    let mut bound: Vec<&str> = uses
+        .iter()
+        .filter(|&(_, &n)| n > 1)
+        .map(|(&name, _)| name)
+        .collect();
+    bound.sort_unstable();
+
+    let mut expanded = Vec::new();
+    let mut choice = vec![0; bound.len()];
+    loop {
+        let value = |name: &str| {
+            bound
+                .iter()
+                .position(|&b| b == name)
+                .map(|i| vars[name][choice[i]])
+        };
+        let (output, inputs) = tokens.split_last().expect("transitions aren't empty");
+        let inputs = inputs
+            .iter()
+            .map(|token| match token {
+                Token::States(states) => states.clone(),
+                Token::Var(name) => value(name).map_or_else(|| vars[name].clone(), |s| vec![s]),
+            })
+            .collect();
+        let output = match output {
+            Token::States(states) if states.len() == 1 => states[0],
+            Token::Var(name) => value(name).ok_or(RuleFileError::UnboundOutput { line })?,
+            Token::States(_) => return Err(RuleFileError::UnboundOutput { line }),
+        };
+        expanded.push((inputs, output));
+
+        // Count through every combination, the last variable fastest
+        let mut i = bound.len();
+        loop {
+            if i == 0 {
+                return Ok(expanded);
+            }
+            i -= 1;
+            choice[i] += 1;
+            if choice[i] < vars[bound[i]].len() {
+                break;
+            }
+            choice[i] = 0;
+        }
+    }
+}
+
+/// The transition along with every copy of it the symmetries ask for, the cell itself staying put
+fn symmetric(inputs: &[Vec<u8>], symmetries: &str) -> Result<Vec<Vec<Vec<u8>>>, RuleFileError> {
+    let (cell, ring) = inputs.split_first().expect("transitions aren't empty");
+    let n = ring.len();
+    let unknown = || RuleFileError::UnknownSymmetry(symmetries.to_owned());
+    let rearranged = |order: &[usize]| {
+        std::iter::once(cell.clone())
+            .chain(order.iter().map(|&i| ring[i].clone()))
+            .collect::<Vec<_>>()
+    };
+    // Reading the neighbors the other way round mirrors them through the north neighbor
+    let mirror: Vec<usize> = (0..n).map(|i| (n - i) % n).collect();
+    let orders: Vec<Vec<usize>> = match symmetries {
+        "none" => vec![(0..n).collect()],
+        "reflect_horizontal" | "reflect" => vec![(0..n).collect(), mirror],
+        "permute" => return Ok(permutations(ring).iter().map(|o| rearranged(o)).collect()),
+        _ => {
+            let rest = symmetries.strip_prefix("rotate").ok_or_else(unknown)?;
+            let (turns, reflect) = match rest.strip_suffix("reflect") {
+                Some(turns) => (turns, true),
+                None => (rest, false),
+            };
+            let turns: usize = turns.parse().map_err(|_err| unknown())?;
+            if turns == 0 || n % turns != 0 {
+                return Err(unknown());
+            }
+            let step = n / turns;
+            let rotations = (0..turns).map(|t| (0..n).map(|i| (i + t * step) % n).collect());
+            let mut orders: Vec<Vec<usize>> = rotations.collect();
+            if reflect {
+                let reflected: Vec<Vec<usize>> = orders
+                    .iter()
+                    .map(|order| mirror.iter().map(|&i| order[i]).collect())
+                    .collect();
+                orders.extend(reflected);
+            }
+            orders
+        }
+    };
+    Ok(orders.iter().map(|order| rearranged(order)).collect())
+}
+
+/// Every distinct order the neighbors' sets of states can go in, as indices into `ring`
+fn permutations(ring: &[Vec<u8>]) -> Vec<Vec<usize>> {
+    let mut sorted: Vec<usize> = (0..ring.len()).collect();
+    sorted.sort_by(|&a, &b| ring[a].cmp(&ring[b]));
+    let mut out = Vec::new();
+    let mut used = vec![false; ring.len()];
+    let mut current = Vec::with_capacity(ring.len());
+    permute(ring, &sorted, &mut used, &mut current, &mut out);
+    out
+}
+
+fn permute(
+    ring: &[Vec<u8>],
+    sorted: &[usize],
+    used: &mut [bool],
+    current: &mut Vec<usize>,
+    out: &mut Vec<Vec<usize>>,
+) {
+    if current.len() == sorted.len() {
+        out.push(current.clone());
+        return;
+    }
+    for i in 0..sorted.len() {
+        // Equal sets are interchangeable, so only ever take the first unused one of them
+        let repeat = i > 0 && ring[sorted[i]] == ring[sorted[i - 1]] && !used[i - 1];
+        if used[i] || repeat {
+            continue;
+        }
+        used[i] = true;
+        current.push(sorted[i]);
+        permute(ring, sorted, used, current, out);
+        current.pop();
+        used[i] = false;
+    }
+}
@@ -0,0 +1,150 @@
+//! The `@TREE` section: a decision tree over the neighbors' states
+//!
+//! ```text
+//! num_states=2
+//! num_neighbors=8
+//! num_nodes=32
+//! 1 0 0
+//! 2 0 0
+//! ...
+//! ```
+//!
+//! Each node is its level followed by one entry per state. Level 1 nodes hold the new state of the
+//! cell, and nodes above that hold the index of a node one level down, counting the nodes from 0
+//! in the order they're written. The last node is the root. A cell's next state is found by
+//! starting at the root and following the entry for each neighbor in turn, in Golly's order:
+//! north west, north east, south west, south east, north, west, east, south and finally the cell
+//! itself, or just north, west, east, south and the cell with four neighbors.
+//!
+//! The nodes get flattened into one array, with each entry pointing straight at where the next
+//! node starts, so stepping down the tree is one lookup per level.
+
+use super::RuleFileError;
+use crate::conway::rule::Neighborhood;
+
+/// Where Golly's tree order takes each neighbor from, as indices clockwise from north
+const MOORE_ORDER: [usize; 8] = [7, 1, 5, 3, 0, 6, 2, 4];
+const VON_NEUMANN_ORDER: [usize; 4] = [0, 3, 1, 2];
+
+/// A compiled `@TREE`
+#[derive(Clone, Debug)]
+pub struct Tree {
+    states: usize,
+    neighborhood: Neighborhood,
+    /// Every node's entries one after the other. Leaves hold states, the rest where the node
+    /// they point to starts
+    entries: Vec<u32>,
+    /// Where the root starts
+    root: u32,
+}
+
+impl Tree {
+    /// Compile the lines of a `@TREE` section, each with its line number in the file
+    pub fn parse(lines: &[(usize, &str)]) -> Result<Self, RuleFileError> {
+        let (mut states, mut neighbors) = (None, None);
+        // Where each node starts and its level
+        let mut nodes: Vec<(u32, usize)> = Vec::new();
+        let mut entries = Vec::new();
+        for &(line, text) in lines {
+            if let Some((key, value)) = text.split_once('=') {
+                let value = value.trim();
+                match key.trim() {
+                    "num_states" => {
+                        states = Some(
+                            value
+                                .parse::<usize>()
+                                .ok()
+                                .filter(|n| (2..=256).contains(n))
+                                .ok_or_else(|| RuleFileError::InvalidStates(value.to_owned()))?,
+                        );
+                    }
+                    "num_neighbors" => {
+                        neighbors = match value {
+                            "4" => Some(Neighborhood::VonNeumann),
+                            "8" => Some(Neighborhood::Moore),
+                            _ => return Err(RuleFileError::UnknownNeighborhood(value.to_owned())),
+                        };
+                    }
+                    // Only there to help readers allocate up front
+                    "num_nodes" => {}
+                    _ => {
+                        return Err(RuleFileError::Setting {
+                            line,
+                            text: text.to_owned(),
+                        });
+                    }
+                }
+                continue;
+            }
+            let states = states.ok_or(RuleFileError::MissingSetting("num_states"))?;
+            let bad = || RuleFileError::BadNode { line };
+            let numbers = text
+                .split_whitespace()
+                .map(|n| n.parse::<usize>().map_err(|_err| bad()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let (&level, children) = numbers.split_first().ok_or_else(bad)?;
+            if level == 0 || children.len() != states {
+                return Err(bad());
+            }
+            let start = entries.len() as u32;
+            for &child in children {
+                entries.push(if level == 1 {
+                    // A leaf, holding the new state itself
+                    if child >= states {
+                        return Err(bad());
+                    }
+                    child as u32
+                } else {
+                    // A node one level down that's already been written
+                    match nodes.get(child) {
+                        Some(&(at, child_level)) if child_level == level - 1 => at,
+                        _ => return Err(bad()),
+                    }
+                });
+            }
+            nodes.push((start, level));
+        }
+
+        let states = states.ok_or(RuleFileError::MissingSetting("num_states"))?;
+        let neighborhood = neighbors.ok_or(RuleFileError::MissingSetting("num_neighbors"))?;
+        let &(root, level) = nodes.last().ok_or(RuleFileError::EmptyTree)?;
+        if level != neighborhood.max_count() + 1 {
+            return Err(RuleFileError::EmptyTree);
+        }
+        Ok(Self {
+            states,
+            neighborhood,
+            entries,
+            root,
+        })
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// The state a cell in `cell` goes to with `neighbors` round it, clockwise from north
+    pub fn next(&self, cell: u8, neighbors: &[u8]) -> u8 {
+        let order: &[usize] = match self.neighborhood {
+            Neighborhood::VonNeumann => &VON_NEUMANN_ORDER,
+            _ => &MOORE_ORDER,
+        };
+        let inputs = order
+            .iter()
+            .map(|&i| neighbors.get(i).copied().unwrap_or(0))
+            .chain(std::iter::once(cell));
+        let mut at = self.root;
+        for state in inputs {
+            at = self
+                .entries
+                .get(at as usize + usize::from(state))
+                .copied()
+                .unwrap_or(0);
+        }
+        at as u8
+    }
+}
@@ -0,0 +1,375 @@
+//! Running the simulation off the UI thread
+//!
+//! While the simulation plays, a copy of the map lives on a background thread that steps it as
+//! often as the speed setting allows, or flat out in max speed mode. Every time the UI draws a
+//! frame it picks up the newest generation the thread has published, so slow generations don't
+//! hold up drawing and fast ones aren't capped by the frame rate.
+//!
+//! The UI's map stays the one that gets edited. Whenever one of the settings the thread depends
+//! on changes (rule, size, engine and so on) the thread is stopped, and a new one is started from
+//! the UI's map once the settings have stayed put for a moment, so dragging a slider doesn't start
+//! a thread every frame. Speed changes are just passed on to the running thread.
+//!
+//! There are no threads on `wasm32`, so there the map is stepped right on the UI thread, fitting
+//! as many generations into a frame as it can in max speed mode.
+
+use std::collections::VecDeque;
+
+use instant::{Duration, Instant};
+
+use super::boundary::Boundary;
+use super::conway_map::Map;
+use super::engine::EngineKind;
+use super::rule::Rule;
+use super::rule_file::RuleFile;
+
+/// How far back generation counts are kept around to work out the speed from
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// How long the settings have to stay the same before a new thread is started for them
+const RESTART_DELAY: Duration = Duration::from_millis(250);
+
+/// Everything about a map that changes how it steps, the thread is restarted when any of it does
+#[derive(Clone, PartialEq, Eq)]
+struct Settings {
+    rule: Rule,
+    rule_file: Option<RuleFile>,
+    boundary: Boundary,
+    width: i32,
+    height: i32,
+    engine: EngineKind,
+    step_log2: u32,
+}
+
+impl Settings {
+    fn of(map: &Map) -> Self {
+        Self {
+            rule: map.rule.clone(),
+            rule_file: map.rule_file.clone(),
+            boundary: map.boundary,
+            width: map.width,
+            height: map.height,
+            engine: map.engine,
+            step_log2: map.step_log2,
+        }
+    }
+}
+
+/// Steps a map in the background while the simulation is running
+#[derive(Default)]
+pub struct Worker {
+    #[cfg(not(target_arch = "wasm32"))]
+    thread: Option<native::Thread>,
+    /// What the current thread was started with, or is about to be
+    settings: Option<Settings>,
+    /// When to start a thread for the current settings, if there isn't one running for them yet
+    restart_at: Option<Instant>,
+    /// Generations run by threads that have since been stopped
+    finished_generations: u64,
+    /// Total generations run at recent points in time, oldest first
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl Worker {
+    /// Call once a frame. Starts, restarts or stops the background thread as needed and brings
+    /// `map` up to date with the newest generation it has computed
+    pub fn sync(&mut self, map: &mut Map, running: bool) {
+        if self.outdated(map) {
+            // The UI changed the cells since the thread last handed any over, so whatever it has
+            // got to since is from cells that are gone. Start again from the new ones
+            self.stop(None);
+            self.settings = None;
+        }
+        if !running {
+            // Paused, so the map carries on from wherever the thread got to
+            self.stop(Some(map));
+            self.settings = None;
+            self.restart_at = None;
+            self.samples.clear();
+            return;
+        }
+        let now = Instant::now();
+        let settings = Settings::of(map);
+        match &self.settings {
+            None => self.restart_at = Some(now),
+            Some(old) if *old != settings => {
+                // Anything the UI hasn't picked up yet was computed under the old settings, so
+                // it's dropped, along with the generations that went into it
+                self.stop(None);
+                self.samples.clear();
+                self.restart_at = Some(now + RESTART_DELAY);
+            }
+            Some(_) => {}
+        }
+        self.settings = Some(settings);
+        if self.restart_at.is_some_and(|at| now >= at) {
+            self.restart_at = None;
+            self.start(map);
+        }
+        self.pull(map);
+
+        self.samples.push_back((now, self.generations()));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Generations per second over the last second or so, 0 when not running
+    pub fn gens_per_sec(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((start, first)), Some((end, last))) if end > start => {
+                (last - first) as f64 / end.duration_since(*start).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::thread::{self, JoinHandle};
+
+    use instant::{Duration, Instant};
+
+    use super::Worker;
+    use crate::conway::conway_map::Map;
+    use crate::{Pos, RunStatistics};
+
+    /// A generation the thread has got to, for the UI to pick up
+    struct Published {
+        cells: HashSet<Pos>,
+        dying: HashMap<Pos, u8>,
+        /// Statistics of every update since the last lot
+        history: Vec<RunStatistics>,
+        /// Generations the thread had run to get here
+        generations: u64,
+    }
+
+    /// What the background thread and the UI share
+    #[derive(Default)]
+    struct Shared {
+        /// Set by the UI to tell the thread to finish up
+        stop: AtomicBool,
+        /// Generations the thread has run so far
+        generations: AtomicU64,
+        /// Newest cells, waiting for the UI to pick them up
+        latest: Mutex<Option<Published>>,
+        /// The map's `speed` and `max_speed`, which can change without restarting the thread
+        speed: AtomicU64,
+        max_speed: AtomicBool,
+    }
+
+    impl Shared {
+        fn publish(&self, map: &mut Map) {
+            // Flattened and copied before locking, so the UI isn't kept waiting
+            map.catch_up();
+            let cells = map.cells().clone();
+            let dying = map.dying().clone();
+            let generations = self.generations.load(Ordering::Relaxed);
+            let mut latest = self.latest.lock().unwrap_or_else(PoisonError::into_inner);
+            // Statistics the UI hasn't seen yet carry over, the cells don't
+            let mut history = latest.take().map(|p| p.history).unwrap_or_default();
+            history.extend(map.take_history());
+            *latest = Some(Published {
+                cells,
+                dying,
+                history,
+                generations,
+            });
+        }
+    }
+
+    pub(super) struct Thread {
+        /// Taken once the thread has been joined
+        handle: Option<JoinHandle<()>>,
+        shared: Arc<Shared>,
+        /// Generations the thread had run as of the last cells the UI picked up
+        pulled: u64,
+        /// The UI map's [`Map::edits`] as of the thread starting or the UI last picking up cells
+        edits: u64,
+    }
+
+    impl Thread {
+        fn spawn(mut map: Map) -> Self {
+            let shared = Arc::new(Shared {
+                speed: AtomicU64::new(map.speed as u64),
+                max_speed: AtomicBool::new(map.max_speed),
+                ..Shared::default()
+            });
+            let thread_shared = Arc::clone(&shared);
+            let handle = thread::spawn(move || {
+                let shared = thread_shared;
+                let mut last_step = Instant::now();
+                let mut published = 0;
+                while !shared.stop.load(Ordering::Relaxed) {
+                    map.speed = u128::from(shared.speed.load(Ordering::Relaxed));
+                    map.max_speed = shared.max_speed.load(Ordering::Relaxed);
+                    let started = Instant::now();
+                    if map.update_deferred() {
+                        last_step = started;
+                        shared
+                            .generations
+                            .fetch_add(1 << map.step_log2, Ordering::Relaxed);
+                        // Only flatten and copy the cells out if the UI took the last lot, at max
+                        // speed there are far more generations than frames
+                        let taken = shared
+                            .latest
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .is_none();
+                        if taken {
+                            shared.publish(&mut map);
+                            published = shared.generations.load(Ordering::Relaxed);
+                        }
+                    }
+                    if !map.max_speed {
+                        let interval = Duration::from_millis(map.speed as u64);
+                        thread::park_timeout(interval.saturating_sub(last_step.elapsed()));
+                    }
+                }
+                // The last few generations too, in case the UI carries on from here
+                if shared.generations.load(Ordering::Relaxed) != published {
+                    shared.publish(&mut map);
+                }
+            });
+            Self {
+                handle: Some(handle),
+                shared,
+                pulled: 0,
+                edits: 0,
+            }
+        }
+
+        /// Tell the thread to finish up, and wait for the generation it's on
+        fn finish(&mut self) {
+            self.shared.stop.store(true, Ordering::Relaxed);
+            if let Some(handle) = self.handle.take() {
+                handle.thread().unpark();
+                // A panic on the thread has already been reported, all that's lost is its cells
+                let _panicked = handle.join();
+            }
+        }
+
+        /// Bring `map` up to the newest generation published, if the UI hasn't had it yet
+        fn take_latest(&mut self, map: &mut Map) {
+            let latest = self
+                .shared
+                .latest
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let Some(latest) = latest {
+                map.set_cells(latest.cells, latest.dying);
+                map.extend_history(latest.history);
+                self.pulled = latest.generations;
+                self.edits = map.edits();
+            }
+        }
+    }
+
+    impl Drop for Thread {
+        fn drop(&mut self) {
+            self.finish();
+        }
+    }
+
+    impl Worker {
+        pub(super) fn start(&mut self, map: &mut Map) {
+            let mut thread = Thread::spawn(map.detached_copy());
+            thread.edits = map.edits();
+            self.thread = Some(thread);
+        }
+
+        /// Whether `map` has been changed since the thread was given its cells
+        pub(super) fn outdated(&self, map: &Map) -> bool {
+            self.thread
+                .as_ref()
+                .is_some_and(|thread| thread.edits != map.edits())
+        }
+
+        /// Stop the thread, bringing `map` up to the last generation it got to if there is one.
+        /// Only the generations the map has been given are counted
+        pub(super) fn stop(&mut self, map: Option<&mut Map>) {
+            let Some(mut thread) = self.thread.take() else {
+                return;
+            };
+            thread.finish();
+            if let Some(map) = map {
+                thread.take_latest(map);
+            }
+            self.finished_generations += thread.pulled;
+        }
+
+        pub(super) fn pull(&mut self, map: &mut Map) {
+            let Some(thread) = &mut self.thread else {
+                return;
+            };
+            thread
+                .shared
+                .speed
+                .store(map.speed as u64, Ordering::Relaxed);
+            thread
+                .shared
+                .max_speed
+                .store(map.max_speed, Ordering::Relaxed);
+            thread.take_latest(map);
+        }
+
+        /// Generations run since the app started
+        pub fn generations(&self) -> u64 {
+            let running = self.thread.as_ref().map_or(0, |thread| {
+                thread.shared.generations.load(Ordering::Relaxed)
+            });
+            self.finished_generations + running
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use instant::{Duration, Instant};
+
+    use super::Worker;
+    use crate::conway::conway_map::Map;
+
+    /// How long max speed mode gets to spend stepping each frame, leaving the rest of the frame
+    /// for drawing
+    const FRAME_BUDGET: Duration = Duration::from_millis(12);
+
+    impl Worker {
+        pub(super) fn start(&mut self, _map: &mut Map) {}
+
+        pub(super) fn stop(&mut self, _map: Option<&mut Map>) {}
+
+        /// The map is stepped in place, so it's never behind
+        pub(super) fn outdated(&self, _map: &Map) -> bool {
+            false
+        }
+
+        /// No thread to pull from, so this is where the stepping happens
+        pub(super) fn pull(&mut self, map: &mut Map) {
+            let start = Instant::now();
+            loop {
+                if map.update_deferred() {
+                    self.finished_generations += 1 << map.step_log2;
+                }
+                if !map.max_speed || start.elapsed() > FRAME_BUDGET {
+                    break;
+                }
+            }
+            map.catch_up();
+        }
+
+        /// Generations run since the app started
+        pub fn generations(&self) -> u64 {
+            self.finished_generations
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Cyclic cellular automata, and Rock-Paper-Scissors
+//!
+//! Every cell is in one of n states arranged in a circle, each one eaten by the state after it
+//! and the last eaten by the first. A cell moves on to the next state round once at least
+//! `threshold` of its neighbors within `range` are already in it. From a random soup this makes
+//! patches that grow until they meet, then waves and spirals chasing each other round.
+//!
+//! Rock-Paper-Scissors is the same game with some luck thrown in: each step a cell needs up to
+//! `randomness` more neighbors than the threshold before it gets eaten, picked at random, which
+//! roughens the waves into the ragged spirals the game is known for. Its random numbers come from
+//! the same generator as the soup, so the same seed runs the same way every time.
+//!
+//! Like the other grid modes the edges wrap round.
+
+use rand::{Rng as _, SeedableRng as _};
+use rand_chacha::ChaCha8Rng;
+
+use egui::Color32;
+use egui::ecolor::Hsva;
+
+use crate::grid::{Grid, Neighborhood};
+use crate::soup::{Soup, soup};
+use crate::timing::Pace;
+
+pub const DEFAULT_SIZE: usize = 200;
+/// Most states a cell can cycle through
+pub const MAX_STATES: u8 = 32;
+/// Furthest away a cell's neighbors can be
+pub const MAX_RANGE: u32 = 10;
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Kind {
+    #[default]
+    Cyclic,
+    RockPaperScissors,
+}
+
+impl Kind {
+    pub const ALL: [Self; 2] = [Self::Cyclic, Self::RockPaperScissors];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cyclic => "Cyclic",
+            Self::RockPaperScissors => "Rock-Paper-Scissors",
+        }
+    }
+}
+
+/// A rule worth knowing about, mostly from Mirek's Cellebration
+pub struct Preset {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub kind: Kind,
+    pub states: u8,
+    pub range: u32,
+    pub threshold: u32,
+    pub neighborhood: Neighborhood,
+    pub randomness: u32,
+}
+
+pub const PRESETS: [Preset; 7] = [
+    Preset {
+        name: "313",
+        about: "three states that settle into big turning spirals",
+        kind: Kind::Cyclic,
+        states: 3,
+        range: 1,
+        threshold: 3,
+        neighborhood: Neighborhood::Moore,
+        randomness: 0,
+    },
+    Preset {
+        name: "Perfect spirals",
+        about: "four states, spirals that fill the whole grid",
+        kind: Kind::Cyclic,
+        states: 4,
+        range: 1,
+        threshold: 3,
+        neighborhood: Neighborhood::Moore,
+        randomness: 0,
+    },
+    Preset {
+        name: "Griffeath's CCA",
+        about: "the original fourteen state cyclic CA, patches that crystallise into spirals",
+        kind: Kind::Cyclic,
+        states: 14,
+        range: 1,
+        threshold: 1,
+        neighborhood: Neighborhood::VonNeumann,
+        randomness: 0,
+    },
+    Preset {
+        name: "Squarish spirals",
+        about: "six states, spirals with corners",
+        kind: Kind::Cyclic,
+        states: 6,
+        range: 2,
+        threshold: 2,
+        neighborhood: Neighborhood::VonNeumann,
+        randomness: 0,
+    },
+    Preset {
+        name: "Cyclic spirals",
+        about: "eight states over a wide neighborhood, slow fat spirals",
+        kind: Kind::Cyclic,
+        states: 8,
+        range: 3,
+        threshold: 5,
+        neighborhood: Neighborhood::Moore,
+        randomness: 0,
+    },
+    Preset {
+        name: "Stripes",
+        about: "five states that line up into marching stripes",
+        kind: Kind::Cyclic,
+        states: 5,
+        range: 3,
+        threshold: 4,
+        neighborhood: Neighborhood::VonNeumann,
+        randomness: 0,
+    },
+    Preset {
+        name: "Rock-Paper-Scissors",
+        about: "three states each eating the next, with a little luck in who wins",
+        kind: Kind::RockPaperScissors,
+        states: 3,
+        range: 1,
+        threshold: 3,
+        neighborhood: Neighborhood::Moore,
+        randomness: 2,
+    },
+];
+
+/// The color for `state` out of `states`, evenly spaced round the color wheel so the palette
+/// wraps round the same way the states do
+pub fn color(state: u8, states: u8) -> Color32 {
+    let hue = f32::from(state) / f32::from(states.max(1));
+    Hsva::new(hue, 0.75, 0.95, 1.0).into()
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Cyclic {
+    pub kind: Kind,
+    /// How many states go round the circle, from 2 to [`MAX_STATES`]
+    pub states: u8,
+    /// How far away neighbors can be, from 1 to [`MAX_RANGE`]
+    pub range: u32,
+    /// How many neighbors have to be in the next state for a cell to move on to it
+    pub threshold: u32,
+    pub neighborhood: Neighborhood,
+    /// Rock-Paper-Scissors only, the most extra neighbors a cell can need on top of the threshold
+    pub randomness: u32,
+    /// Every cell's state
+    grid: Grid<u8>,
+    /// Generations per second
+    pub pace: Pace,
+    /// Seed and density of the soups [`Cyclic::randomize`] fills the grid with
+    pub soup: Soup,
+    #[serde(skip)]
+    generation: u64,
+    /// Where Rock-Paper-Scissors gets its luck from, carrying on from the soup
+    #[serde(skip, default = "unseeded")]
+    rng: ChaCha8Rng,
+}
+
+/// Where the luck comes from until the first soup is made
+fn unseeded() -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(0)
+}
+
+impl Default for Cyclic {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE, DEFAULT_SIZE)
+    }
+}
+
+impl Cyclic {
+    /// A `width`×`height` grid running the first preset, with every cell in state 0
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut cyclic = Self {
+            kind: Kind::Cyclic,
+            states: 3,
+            range: 1,
+            threshold: 3,
+            neighborhood: Neighborhood::Moore,
+            randomness: 0,
+            grid: Grid::default(),
+            pace: Pace::new(20),
+            soup: Soup {
+                density: 100.0,
+                ..Soup::default()
+            },
+            generation: 0,
+            rng: unseeded(),
+        };
+        cyclic.load_preset(&PRESETS[0]);
+        cyclic.resize(width, height);
+        cyclic
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Every cell's state
+    pub fn grid(&self) -> &Grid<u8> {
+        &self.grid
+    }
+
+    /// Set the cell at column `x` of row `y`, wrapping states past the last one back round
+    pub fn set_cell(&mut self, x: usize, y: usize, state: u8) {
+        self.grid.set(x, y, state % self.states);
+    }
+
+    /// Most neighbors a cell can have, which is as high as the threshold is worth going
+    pub fn max_threshold(&self) -> u32 {
+        self.neighborhood.offsets(self.range).len() as u32
+    }
+
+    /// Take on the settings of `preset`, keeping the grid but wrapping states it no longer has
+    pub fn load_preset(&mut self, preset: &Preset) {
+        self.kind = preset.kind;
+        self.range = preset.range;
+        self.threshold = preset.threshold;
+        self.neighborhood = preset.neighborhood;
+        self.randomness = preset.randomness;
+        self.set_states(preset.states);
+    }
+
+    /// Change how many states there are, wrapping any cells past the last one back round
+    pub fn set_states(&mut self, states: u8) {
+        self.states = states.clamp(2, MAX_STATES);
+        for cell in self.grid.cells_mut() {
+            *cell %= self.states;
+        }
+    }
+
+    /// Change the size of the grid, wiping it
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid = Grid::new(width.max(1), height.max(1));
+        self.generation = 0;
+    }
+
+    /// Put every cell back in state 0
+    pub fn clear(&mut self) {
+        self.grid.clear();
+        self.generation = 0;
+    }
+
+    /// Fill the grid from `soup`, every state equally likely in the cells it fills in and the rest
+    /// left in state 0. Rock-Paper-Scissors carries on drawing from the soup's generator, so the
+    /// same seed gives the same run
+    pub fn randomize(&mut self) {
+        let (width, height) = (self.grid.width(), self.grid.height());
+        let mut rng = self.soup.rng();
+        let density = self.soup.density / 100.0;
+        self.grid
+            .replace(soup(width, height, density, 0..=self.states - 1, &mut rng));
+        self.rng = ChaCha8Rng::seed_from_u64(rng.r#gen());
+        self.generation = 0;
+    }
+
+    pub fn step(&mut self) {
+        let (width, height) = (self.grid.width() as i32, self.grid.height() as i32);
+        let offsets = self.neighborhood.offsets(self.range);
+        let cells = self.grid.cells();
+        let mut next = cells.to_vec();
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                let eater = (cells[i] + 1) % self.states;
+                let count = offsets
+                    .iter()
+                    .filter(|&&(dx, dy)| {
+                        let (nx, ny) = ((x + dx).rem_euclid(width), (y + dy).rem_euclid(height));
+                        cells[(ny * width + nx) as usize] == eater
+                    })
+                    .count() as u32;
+                let needed = match self.kind {
+                    Kind::Cyclic => self.threshold,
+                    Kind::RockPaperScissors => {
+                        self.threshold + self.rng.gen_range(0..=self.randomness)
+                    }
+                };
+                if count >= needed.max(1) {
+                    next[i] = eater;
+                }
+            }
+        }
+        self.grid.replace(next);
+        self.generation += 1;
+    }
+}
@@ -0,0 +1,205 @@
+//! Wolfram's elementary cellular automata, and the 1D rules they generalize to
+//!
+//! A single row of cells, each on or off, where the next state of a cell depends on nothing but
+//! itself and its two neighbors. There are 8 possible neighborhoods, so a rule just says which of
+//! them turn the cell on: an 8 bit number from 0 to 255. Bit `n` of the rule number is the new
+//! state for the neighborhood that reads as `n` in binary, left to right, so rule 30 (`00011110`)
+//! turns the cell on for 100, 011, 010 and 001.
+//!
+//! The same idea works with more than two states and wider neighborhoods, see [`rule`].
+//!
+//! Every generation is kept as a new row underneath the last, which makes the space-time diagram
+//! these are usually drawn as.
+
+use std::collections::VecDeque;
+
+use rand::{Rng as _, thread_rng};
+
+use rule::{CodeError, Rule};
+
+use crate::timing::Pace;
+
+pub mod rule;
+
+/// Oldest rows get dropped past this many, only so many fit on screen anyway
+const MAX_ROWS: usize = 2000;
+
+pub const DEFAULT_WIDTH: usize = 151;
+
+/// What's past either end of the row
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Edges {
+    /// The two ends are neighbors, like the row is a ring
+    #[default]
+    Wrap,
+    /// Always-blank cells
+    Fixed,
+}
+
+impl Edges {
+    pub const ALL: [Self; 2] = [Self::Wrap, Self::Fixed];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Wrap => "Wrap around",
+            Self::Fixed => "Fixed at 0",
+        }
+    }
+}
+
+/// How the first row gets filled in
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum InitialRow {
+    /// One cell in the middle
+    #[default]
+    SingleCell,
+    /// Every cell in a random state
+    Random,
+    /// Whatever was drawn by hand
+    Painted,
+}
+
+impl InitialRow {
+    pub const ALL: [Self; 3] = [Self::SingleCell, Self::Random, Self::Painted];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::SingleCell => "Single cell",
+            Self::Random => "Random",
+            Self::Painted => "Painted",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Elementary {
+    pub rule: Rule,
+    /// Number of cells in a row
+    pub width: usize,
+    pub edges: Edges,
+    pub initial: InitialRow,
+    /// The row drawn by hand, for `InitialRow::Painted`
+    pub painted: Vec<u8>,
+    /// Rows added per second
+    pub pace: Pace,
+    /// The code number as typed into the side panel, which may not be a valid one
+    #[serde(skip)]
+    pub code_text: String,
+    /// What's wrong with `code_text`, or with the last change to the rule's shape
+    #[serde(skip)]
+    pub code_error: Option<CodeError>,
+
+    /// The generations still around, oldest first
+    #[serde(skip)]
+    rows: VecDeque<Vec<u8>>,
+    /// Generation of the newest row
+    #[serde(skip)]
+    generation: u64,
+}
+
+impl Default for Elementary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Elementary {
+    pub fn new() -> Self {
+        let mut elementary = Self {
+            rule: Rule::default(),
+            width: DEFAULT_WIDTH,
+            edges: Edges::default(),
+            initial: InitialRow::default(),
+            painted: vec![0; DEFAULT_WIDTH],
+            pace: Pace::new(20),
+            code_text: Rule::default().code(),
+            code_error: None,
+            rows: VecDeque::new(),
+            generation: 0,
+        };
+        elementary.restart();
+        elementary
+    }
+
+    /// Throw away every generation and start over from a fresh first row
+    pub fn restart(&mut self) {
+        let states = self.rule.states();
+        self.painted.resize(self.width, 0);
+        for cell in &mut self.painted {
+            *cell = (*cell).min(states - 1);
+        }
+        let first = match self.initial {
+            InitialRow::SingleCell => {
+                let mut row = vec![0; self.width];
+                row[self.width / 2] = 1;
+                row
+            }
+            InitialRow::Random => {
+                let mut rng = thread_rng();
+                (0..self.width).map(|_| rng.gen_range(0..states)).collect()
+            }
+            InitialRow::Painted => self.painted.clone(),
+        };
+        self.rows = VecDeque::from([first]);
+        self.generation = 0;
+    }
+
+    /// Add the next generation, dropping the oldest if there are too many
+    pub fn step(&mut self) {
+        let next = match self.rows.back() {
+            Some(row) => self.rule.next_row(row, self.edges),
+            None => return,
+        };
+        if self.rows.len() >= MAX_ROWS {
+            self.rows.pop_front();
+        }
+        self.rows.push_back(next);
+        self.generation += 1;
+    }
+
+    /// Every generation still kept, oldest first
+    pub fn rows(&self) -> &VecDeque<Vec<u8>> {
+        &self.rows
+    }
+
+    /// Generation of the newest row
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Generation of the oldest row still kept
+    pub fn first_generation(&self) -> u64 {
+        self.generation + 1 - self.rows.len() as u64
+    }
+
+    /// Switch to a different rule. Rows already there are kept unless the number of states changed
+    pub fn set_rule(&mut self, rule: Rule) {
+        let restart = rule.states() != self.rule.states();
+        self.code_text = rule.code();
+        self.code_error = None;
+        self.rule = rule;
+        if restart {
+            self.restart();
+        }
+    }
+
+    /// Move a cell of the hand-drawn row on to the next state, and start over from it
+    pub fn cycle_painted(&mut self, index: usize) {
+        if self.initial != InitialRow::Painted {
+            // Start from whatever is showing, rather than from nothing
+            if let Some(row) = self.rows.front() {
+                self.painted.clone_from(row);
+            }
+            self.initial = InitialRow::Painted;
+        }
+        if let Some(cell) = self.painted.get_mut(index) {
+            *cell = (*cell + 1) % self.rule.states();
+        }
+        self.restart();
+    }
+}
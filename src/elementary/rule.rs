@@ -0,0 +1,361 @@
+//! 1D rules with any number of colors and any neighborhood radius, given as code numbers
+//!
+//! A cell's neighborhood is itself and the `r` cells either side of it, and every cell is in one
+//! of `k` states (colors), 0 being blank. Rules are numbered the way Wolfram does it:
+//! * General rules list the new state for every one of the `k^(2r + 1)` neighborhoods. Reading a
+//!   neighborhood left to right as a base `k` number gives its index, and digit `n` (in base `k`)
+//!   of the code number is the new state for neighborhood `n`. The elementary rules are the
+//!   `k = 2`, `r = 1` case.
+//! * Totalistic rules only look at the sum of the states in the neighborhood, so digit `n` of the
+//!   code is the new state when the states add up to `n`.
+//!
+//! Code numbers get enormous quickly (a general 3 color, radius 2 rule has 116 digits), so they're
+//! worked with as arbitrary precision numbers.
+
+use std::fmt;
+
+use rand::Rng;
+
+use super::Edges;
+
+/// Fewest and most colors a rule can have
+pub const STATES: std::ops::RangeInclusive<u8> = 2..=8;
+/// Widest neighborhood on either side of a cell
+pub const MAX_RADIUS: u8 = 5;
+/// Rules with more neighborhoods than this aren't worth listing out, totalistic codes still work
+pub const MAX_TABLE: usize = 4096;
+
+/// How the code number is read
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum CodeKind {
+    /// One digit for every neighborhood
+    #[default]
+    General,
+    /// One digit for every neighborhood sum
+    Totalistic,
+}
+
+impl CodeKind {
+    pub const ALL: [Self; 2] = [Self::General, Self::Totalistic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::General => "Rule number",
+            Self::Totalistic => "Totalistic code",
+        }
+    }
+}
+
+/// Why a code number could not be used, shown to the user as-is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodeError {
+    /// Nothing was typed in
+    Empty,
+    /// Something other than a decimal digit
+    UnexpectedChar { ch: char, index: usize },
+    /// The code has more digits in base `states` than there are entries in the table
+    TooBig { states: u8, entries: usize },
+    /// A general rule with so many neighborhoods they can't all be listed
+    TableTooBig { entries: usize },
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "code number is empty"),
+            Self::UnexpectedChar { ch, index } => {
+                write!(f, "unexpected character '{ch}' at position {}", index + 1)
+            }
+            Self::TooBig { states, entries } => {
+                write!(f, "code number has to be less than {states}^{entries}")
+            }
+            Self::TableTooBig { entries } => write!(
+                f,
+                "{entries} neighborhoods is too many for a general rule (max is {MAX_TABLE}), try a \
+                 totalistic code"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodeError {}
+
+/// A rule for a row of `k` state cells, looking `r` cells either side
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "Spec", into = "Spec")]
+pub struct Rule {
+    states: u8,
+    radius: u8,
+    kind: CodeKind,
+    /// New state for each neighborhood index (or sum), the digits of the code number
+    table: Vec<u8>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::elementary(30)
+    }
+}
+
+impl Rule {
+    /// One of the 256 two color, radius 1 rules
+    pub fn elementary(number: u8) -> Self {
+        Self {
+            states: 2,
+            radius: 1,
+            kind: CodeKind::General,
+            table: (0..8).map(|n| (number >> n) & 1).collect(),
+        }
+    }
+
+    /// Rule from a code number written in decimal
+    pub fn from_code(
+        states: u8,
+        radius: u8,
+        kind: CodeKind,
+        code: &str,
+    ) -> Result<Self, CodeError> {
+        let entries = Self::entries(states, radius, kind)?;
+        let code = code.trim();
+        if code.is_empty() {
+            return Err(CodeError::Empty);
+        }
+        let mut number = BigUint::parse(code)?;
+        let table = (0..entries)
+            .map(|_| number.div_rem(u32::from(states)) as u8)
+            .collect();
+        if !number.is_zero() {
+            return Err(CodeError::TooBig { states, entries });
+        }
+        Ok(Self {
+            states,
+            radius,
+            kind,
+            table,
+        })
+    }
+
+    /// A rule picked at random, except that blank neighborhoods stay blank
+    pub fn random(
+        states: u8,
+        radius: u8,
+        kind: CodeKind,
+        rng: &mut impl Rng,
+    ) -> Result<Self, CodeError> {
+        let entries = Self::entries(states, radius, kind)?;
+        let table = (0..entries)
+            .map(|n| if n == 0 { 0 } else { rng.gen_range(0..states) })
+            .collect();
+        Ok(Self {
+            states,
+            radius,
+            kind,
+            table,
+        })
+    }
+
+    /// Size of the table for rules of this shape
+    fn entries(states: u8, radius: u8, kind: CodeKind) -> Result<usize, CodeError> {
+        let width = 2 * u32::from(radius) + 1;
+        match kind {
+            CodeKind::General => {
+                let entries = usize::from(states).checked_pow(width);
+                match entries {
+                    Some(entries) if entries <= MAX_TABLE => Ok(entries),
+                    _ => Err(CodeError::TableTooBig {
+                        entries: entries.unwrap_or(usize::MAX),
+                    }),
+                }
+            }
+            CodeKind::Totalistic => Ok(width as usize * usize::from(states - 1) + 1),
+        }
+    }
+
+    /// The code number, in decimal
+    pub fn code(&self) -> String {
+        let mut number = BigUint::default();
+        for &digit in self.table.iter().rev() {
+            number.mul_add(u32::from(self.states), u32::from(digit));
+        }
+        number.to_string()
+    }
+
+    /// Number of colors
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// How many cells either side make up the neighborhood
+    pub fn radius(&self) -> u8 {
+        self.radius
+    }
+
+    pub fn kind(&self) -> CodeKind {
+        self.kind
+    }
+
+    /// New state for every neighborhood index (or sum), lowest first
+    pub fn table(&self) -> &[u8] {
+        &self.table
+    }
+
+    /// Change the new state for one neighborhood index (or sum)
+    pub fn set_output(&mut self, entry: usize, state: u8) {
+        if let Some(output) = self.table.get_mut(entry) {
+            *output = state % self.states;
+        }
+    }
+
+    /// The cells of the neighborhood with index `entry`, left to right
+    pub fn neighborhood(&self, entry: usize) -> Vec<u8> {
+        let width = 2 * usize::from(self.radius) + 1;
+        let k = usize::from(self.states);
+        (0..width)
+            .rev()
+            .map(|i| ((entry / k.pow(i as u32)) % k) as u8)
+            .collect()
+    }
+
+    /// The new state of a cell, given the cells of its neighborhood left to right
+    pub fn output(&self, neighborhood: impl Iterator<Item = u8>) -> u8 {
+        let k = usize::from(self.states);
+        let entry = match self.kind {
+            CodeKind::General => neighborhood.fold(0, |n, state| n * k + usize::from(state)),
+            CodeKind::Totalistic => neighborhood.map(usize::from).sum(),
+        };
+        self.table[entry]
+    }
+
+    /// The generation after `row`
+    pub fn next_row(&self, row: &[u8], edges: Edges) -> Vec<u8> {
+        let len = row.len() as isize;
+        let radius = isize::from(self.radius);
+        let cell = |i: isize| match edges {
+            Edges::Wrap => row[i.rem_euclid(len) as usize],
+            Edges::Fixed => usize::try_from(i)
+                .ok()
+                .and_then(|i| row.get(i))
+                .copied()
+                .unwrap_or(0),
+        };
+        (0..len)
+            .map(|i| self.output((i - radius..=i + radius).map(cell)))
+            .collect()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.code();
+        match (self.kind, self.states, self.radius) {
+            (CodeKind::General, 2, 1) => write!(f, "Rule {code}"),
+            (kind, k, r) => write!(f, "{} {code}, k = {k}, r = {r}", kind.name()),
+        }
+    }
+}
+
+/// How a rule is saved, the table is too long to store as is
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Spec {
+    states: u8,
+    radius: u8,
+    kind: CodeKind,
+    code: String,
+}
+
+impl TryFrom<Spec> for Rule {
+    type Error = CodeError;
+
+    fn try_from(spec: Spec) -> Result<Self, Self::Error> {
+        Self::from_code(spec.states, spec.radius, spec.kind, &spec.code)
+    }
+}
+
+impl From<Rule> for Spec {
+    fn from(rule: Rule) -> Self {
+        Self {
+            code: rule.code(),
+            states: rule.states,
+            radius: rule.radius,
+            kind: rule.kind,
+        }
+    }
+}
+
+/// Just enough of an unsigned big integer to convert code numbers between bases
+#[derive(Default)]
+struct BigUint {
+    /// Base 10^9 limbs, least significant first, with no zero limbs on the end
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    const BASE: u64 = 1_000_000_000;
+    const LIMB_DIGITS: usize = 9;
+
+    fn parse(decimal: &str) -> Result<Self, CodeError> {
+        if let Some((index, ch)) = decimal.char_indices().find(|(_, ch)| !ch.is_ascii_digit()) {
+            return Err(CodeError::UnexpectedChar { ch, index });
+        }
+        let mut number = Self::default();
+        for chunk in decimal.as_bytes().chunks(Self::LIMB_DIGITS) {
+            let value = chunk
+                .iter()
+                .fold(0, |n, digit| n * 10 + u32::from(digit - b'0'));
+            number.mul_add(10u32.pow(chunk.len() as u32), value);
+        }
+        Ok(number)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// `self = self * factor + add`
+    fn mul_add(&mut self, factor: u32, add: u32) {
+        let mut carry = u64::from(add);
+        for limb in &mut self.limbs {
+            let n = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = (n % Self::BASE) as u32;
+            carry = n / Self::BASE;
+        }
+        while carry > 0 {
+            self.limbs.push((carry % Self::BASE) as u32);
+            carry /= Self::BASE;
+        }
+        self.trim();
+    }
+
+    /// Divide by `divisor` in place, returning the remainder
+    fn div_rem(&mut self, divisor: u32) -> u32 {
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let n = rem * Self::BASE + u64::from(*limb);
+            *limb = (n / u64::from(divisor)) as u32;
+            rem = n % u64::from(divisor);
+        }
+        self.trim();
+        rem as u32
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((top, rest)) = self.limbs.split_last() else {
+            return write!(f, "0");
+        };
+        write!(f, "{top}")?;
+        for limb in rest.iter().rev() {
+            write!(f, "{limb:09}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+//! The fixed size grid of cells that every mode besides the 2D map and the elementary rows runs on
+//!
+//! Cells are stored row by row. Reading past the edges gives an empty cell and writing past them
+//! does nothing, so painting that strays off the grid doesn't need checking first.
+//!
+//! Only the size is saved along with the app, since most modes start from a fresh soup anyway. A
+//! mode that wants its cells kept too can save its grid [`with_cells`].
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    /// Every cell, row by row
+    cells: Vec<T>,
+}
+
+impl<T: Copy + Default> Default for Grid<T> {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl<T: Copy + Default> Grid<T> {
+    /// A `width`×`height` grid of empty cells
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![T::default(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Width and height together, the way [`crate::ui::grid_view::GridView`] takes them
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Every cell, row by row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Swap in a whole new set of cells, which has to be the same size as the grid
+    pub fn replace(&mut self, cells: Vec<T>) {
+        assert_eq!(
+            cells.len(),
+            self.cells.len(),
+            "a {}×{} grid needs that many cells",
+            self.width,
+            self.height
+        );
+        self.cells = cells;
+    }
+
+    /// Where the cell at column `x` of row `y` is in [`Self::cells`], if it's on the grid
+    pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y * self.width + x)
+    }
+
+    /// The cell at column `x` of row `y`, empty past the edges
+    pub fn get(&self, x: usize, y: usize) -> T {
+        self.index(x, y).map_or_else(T::default, |i| self.cells[i])
+    }
+
+    /// Put `cell` at column `x` of row `y`, anything past the edges is ignored
+    pub fn set(&mut self, x: usize, y: usize, cell: T) {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = cell;
+        }
+    }
+
+    /// Change the size of the grid, keeping whatever still fits from the top left corner
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut resized = Self::new(width, height);
+        for y in 0..height.min(self.height) {
+            let row = y * self.width;
+            resized.cells[y * width..][..width.min(self.width)]
+                .copy_from_slice(&self.cells[row..][..width.min(self.width)]);
+        }
+        *self = resized;
+    }
+
+    /// Empty every cell
+    pub fn clear(&mut self) {
+        self.cells.fill(T::default());
+    }
+}
+
+/// Which cells within range of a grid cell count as its neighbors
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Neighborhood {
+    /// The whole square round the cell
+    #[default]
+    Moore,
+    /// Cells no more than the range away going along rows and columns, a diamond
+    VonNeumann,
+}
+
+impl Neighborhood {
+    pub const ALL: [Self; 2] = [Self::Moore, Self::VonNeumann];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Moore => "Moore",
+            Self::VonNeumann => "von Neumann",
+        }
+    }
+
+    /// Offsets of every neighbor within `range`, leaving out the cell itself
+    pub fn offsets(self, range: u32) -> Vec<(i32, i32)> {
+        let range = range as i32;
+        (-range..=range)
+            .flat_map(|dy| (-range..=range).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| (dx, dy) != (0, 0))
+            .filter(|&(dx, dy)| self == Self::Moore || dx.abs() + dy.abs() <= range)
+            .collect()
+    }
+}
+
+/// What gets saved of a grid
+#[derive(Deserialize, Serialize)]
+struct Size {
+    width: usize,
+    height: usize,
+}
+
+impl<T> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let size = Size {
+            width: self.width,
+            height: self.height,
+        };
+        size.serialize(serializer)
+    }
+}
+
+impl<'de, T: Copy + Default> Deserialize<'de> for Grid<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let size = Size::deserialize(deserializer)?;
+        Ok(Self::new(size.width, size.height))
+    }
+}
+
+/// Save a grid cells and all, for `#[serde(with = "crate::grid::with_cells")]`
+pub mod with_cells {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Grid;
+
+    #[derive(Deserialize, Serialize)]
+    struct Saved<C> {
+        width: usize,
+        height: usize,
+        cells: C,
+    }
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        grid: &Grid<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Saved {
+            width: grid.width,
+            height: grid.height,
+            cells: grid.cells.as_slice(),
+        }
+        .serialize(serializer)
+    }
+
+    /// A grid saved with its cells, or an empty one if they don't add up to its size
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Grid<T>, D::Error>
+    where
+        T: Copy + Default + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let saved = Saved::<Vec<T>>::deserialize(deserializer)?;
+        if saved.cells.len() != saved.width * saved.height {
+            return Ok(Grid::new(saved.width, saved.height));
+        }
+        Ok(Grid {
+            width: saved.width,
+            height: saved.height,
+            cells: saved.cells,
+        })
+    }
+}
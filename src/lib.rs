@@ -1,26 +1,103 @@
 #![warn(clippy::all, rust_2018_idioms)]
+use std::collections::HashSet;
+
 pub mod app;
+pub mod continuous;
 pub mod conway;
+pub mod cyclic;
+pub mod elementary;
+pub mod grid;
+pub mod margolus;
+pub mod pattern;
+pub mod plot;
+pub mod sandpile;
+pub mod soup;
+pub mod stochastic;
+pub mod timing;
+pub mod turmite;
+// The directory is capitalised, the module shouldn't be
+#[path = "UI/mod.rs"]
+pub mod ui;
+pub mod wireworld;
 pub use app::ConwaySim;
 pub use consts::*;
 pub use conway::conway_map::Map;
 
-#[derive(Default)]
+/// How the map changed in a single update, and what it looked like afterwards
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct RunStatistics {
-    pub births: u32,
-    pub deaths: u32,
-    pub generations: u32,
-    pub population: u32,
+    /// Cells that came alive during the update
+    pub births: u64,
+    /// Cells that died during the update
+    pub deaths: u64,
+    /// Generations run since the map was last reset
+    pub generations: u64,
+    /// Live cells after the update
+    pub population: u64,
+    /// Width and height of the smallest box around every live cell, 0×0 when there are none
+    pub bounding_box: (u32, u32),
 }
 impl RunStatistics {
+    /// Column names for [`RunStatistics::to_csv`]
+    pub const CSV_HEADER: &'static str =
+        "generation,population,births,deaths,bounding_width,bounding_height";
+
     pub fn new() -> Self {
         RunStatistics {
             births: 0,
             deaths: 0,
             generations: 0,
             population: 0,
+            bounding_box: (0, 0),
+        }
+    }
+
+    /// Statistics for a map that is just sitting there, with nothing born or died yet
+    pub fn of_cells(cells: &HashSet<Pos>, generations: u64) -> Self {
+        let bounding_box = match (
+            cells.iter().map(|p| p.0).min(),
+            cells.iter().map(|p| p.0).max(),
+            cells.iter().map(|p| p.1).min(),
+            cells.iter().map(|p| p.1).max(),
+        ) {
+            (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) => {
+                ((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32)
+            }
+            _ => (0, 0),
+        };
+        Self {
+            generations,
+            population: cells.len() as u64,
+            bounding_box,
+            ..Self::new()
         }
     }
+
+    /// Statistics for an update that turned `before` into `after`, which is `generations` in
+    pub fn of_step(before: &HashSet<Pos>, after: &HashSet<Pos>, generations: u64) -> Self {
+        Self {
+            births: after.difference(before).count() as u64,
+            deaths: before.difference(after).count() as u64,
+            ..Self::of_cells(after, generations)
+        }
+    }
+
+    /// A whole run of statistics as CSV, one row per update
+    pub fn to_csv(history: &[Self]) -> String {
+        let mut csv = format!("{}\n", Self::CSV_HEADER);
+        for stats in history {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                stats.generations,
+                stats.population,
+                stats.births,
+                stats.deaths,
+                stats.bounding_box.0,
+                stats.bounding_box.1
+            ));
+        }
+        csv
+    }
 }
 /// DECLARATION OF CONSTS
 
@@ -44,6 +121,41 @@ pub enum RunModes {
     #[default]
     TwoDimensional,
     Elementary,
+    WireWorld,
+    Turmites,
+    Margolus,
+    Continuous,
+    Cyclic,
+    Sandpile,
+    Stochastic,
+}
+
+impl RunModes {
+    pub const ALL: [Self; 9] = [
+        Self::TwoDimensional,
+        Self::Elementary,
+        Self::WireWorld,
+        Self::Turmites,
+        Self::Margolus,
+        Self::Continuous,
+        Self::Cyclic,
+        Self::Sandpile,
+        Self::Stochastic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TwoDimensional => "2D Life",
+            Self::Elementary => "1D Elementary",
+            Self::WireWorld => "WireWorld",
+            Self::Turmites => "Ants & Turmites",
+            Self::Margolus => "Margolus blocks",
+            Self::Continuous => "Lenia & SmoothLife",
+            Self::Cyclic => "Cyclic & Rock-Paper-Scissors",
+            Self::Sandpile => "Sandpile",
+            Self::Stochastic => "Forest fire & SIR",
+        }
+    }
 }
 
 pub trait UserInterface {
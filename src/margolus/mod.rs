@@ -0,0 +1,308 @@
+//! Block cellular automata on the Margolus neighborhood
+//!
+//! Instead of every cell looking at its neighbors, the grid is cut into 2×2 blocks and each block
+//! is replaced as a whole. The cut moves one cell down and right every other generation, so what
+//! happens in one block spreads to its neighbors the generation after.
+//!
+//! A block is four cells, numbered as bits the way Golly and Mirek's Cellebration do it: 1 is the
+//! top left cell, 2 the top right, 4 the bottom left and 8 the bottom right. A rule is just the
+//! new block for each of the 16 there can be. Whenever no two blocks turn into the same one the
+//! rule can be run backwards exactly, by looking blocks up in the table the other way round.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::grid::Grid;
+use crate::timing::Pace;
+
+pub const DEFAULT_SIZE: usize = 200;
+
+/// Rules worth knowing about, as (name, rule, what it's known for)
+pub const PRESETS: [(&str, &str, &str); 4] = [
+    (
+        "Critters",
+        "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0",
+        "gliders that bounce off each other, and soups that cool down into them",
+    ),
+    (
+        "Tron",
+        "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0",
+        "full and empty blocks flip, making squares that grow and shrink",
+    ),
+    (
+        "BBM",
+        "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15",
+        "Fredkin's billiard ball machine, balls that bounce off walls and each other",
+    ),
+    (
+        "Single rotation",
+        "MS,D0;2;8;3;1;5;6;7;4;9;10;11;12;13;14;15",
+        "lone cells turn clockwise round their block, everything else stays put",
+    ),
+];
+
+/// Why a block rule couldn't be read, shown to the user as-is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleParseError {
+    /// There should be exactly one new block for each of the 16
+    WrongLength(usize),
+    /// An entry that isn't a block number from 0 to 15
+    BadEntry(String),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongLength(n) => write!(f, "expected 16 entries, got {n}"),
+            Self::BadEntry(entry) => write!(f, "`{entry}` isn't a block from 0 to 15"),
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+/// The new block for every block, written `MS,D` and the 16 of them separated by `;`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rule {
+    table: [u8; 16],
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        PRESETS[0].1.parse().expect("presets are valid rules")
+    }
+}
+
+impl Rule {
+    pub fn new(table: [u8; 16]) -> Self {
+        Self {
+            table: table.map(|block| block & 15),
+        }
+    }
+
+    /// The new block for every block
+    pub fn table(&self) -> [u8; 16] {
+        self.table
+    }
+
+    /// Replace `block` with `with`
+    pub fn set(&mut self, block: u8, with: u8) {
+        self.table[usize::from(block & 15)] = with & 15;
+    }
+
+    pub fn apply(&self, block: u8) -> u8 {
+        self.table[usize::from(block & 15)]
+    }
+
+    /// The rule that undoes this one, if no two blocks turn into the same thing
+    pub fn inverse(&self) -> Option<Self> {
+        let mut inverse = [None; 16];
+        for (block, &to) in self.table.iter().enumerate() {
+            inverse[usize::from(to)] = Some(block as u8);
+        }
+        let mut table = [0; 16];
+        for (entry, from) in table.iter_mut().zip(inverse) {
+            *entry = from?;
+        }
+        Some(Self { table })
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.inverse().is_some()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    /// `MS,D` and 16 block numbers split by `;`, or just the numbers split by anything
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let list = s
+            .strip_prefix("MS,D")
+            .or_else(|| s.strip_prefix("ms,d"))
+            .unwrap_or(s);
+        let entries: Vec<&str> = list
+            .split(|c: char| c == ';' || c == ',' || c.is_whitespace())
+            .filter(|e| !e.is_empty())
+            .collect();
+        if entries.len() != 16 {
+            return Err(RuleParseError::WrongLength(entries.len()));
+        }
+        let mut table = [0; 16];
+        for (entry, text) in table.iter_mut().zip(entries) {
+            *entry = text
+                .parse()
+                .ok()
+                .filter(|&block: &u8| block < 16)
+                .ok_or_else(|| RuleParseError::BadEntry(text.to_owned()))?;
+        }
+        Ok(Self { table })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MS,D")?;
+        for (i, block) in self.table.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{block}")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = RuleParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Margolus {
+    pub rule: Rule,
+    /// Every cell, 1 for alive
+    grid: Grid<u8>,
+    /// Generations per second
+    pub pace: Pace,
+    /// The rule as typed into the side panel, which may not be a valid one
+    #[serde(skip)]
+    pub rule_text: String,
+    #[serde(skip)]
+    pub rule_error: Option<RuleParseError>,
+    /// Which generation is showing, going below 0 when stepping back past the start
+    #[serde(skip)]
+    generation: i64,
+}
+
+impl Default for Margolus {
+    fn default() -> Self {
+        Self::new(Rule::default(), DEFAULT_SIZE, DEFAULT_SIZE)
+    }
+}
+
+impl Margolus {
+    /// An empty `width`×`height` grid, both rounded up to even so the blocks tile it
+    pub fn new(rule: Rule, width: usize, height: usize) -> Self {
+        let mut margolus = Self {
+            rule_text: rule.to_string(),
+            rule,
+            grid: Grid::default(),
+            pace: Pace::new(20),
+            rule_error: None,
+            generation: 0,
+        };
+        margolus.resize(width, height);
+        margolus
+    }
+
+    pub fn generation(&self) -> i64 {
+        self.generation
+    }
+
+    /// Every cell, 1 for alive
+    pub fn grid(&self) -> &Grid<u8> {
+        &self.grid
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> bool {
+        self.grid.get(x, y) != 0
+    }
+
+    /// Set the cell at column `x` of row `y`, anything past the edges is ignored
+    pub fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.grid.set(x, y, u8::from(alive));
+    }
+
+    /// Change the size of the grid, wiping it. Sizes get rounded up to even
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid = Grid::new((width.max(2) + 1) & !1, (height.max(2) + 1) & !1);
+        self.generation = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.grid.clear();
+        self.generation = 0;
+    }
+
+    /// Fill the middle `fraction` of the grid at random, each cell alive with chance `density`
+    pub fn randomize(&mut self, fraction: f64, density: f64, rng: &mut impl Rng) {
+        self.clear();
+        let (width, height) = self.grid.size();
+        let (w, h) = (
+            (width as f64 * fraction) as usize,
+            (height as f64 * fraction) as usize,
+        );
+        let (x0, y0) = ((width - w) / 2, (height - h) / 2);
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                self.grid.set(x, y, u8::from(rng.gen_bool(density)));
+            }
+        }
+    }
+
+    /// Replace every block with what `rule` says, with the blocks cut `offset` cells in from
+    /// the top left corner
+    fn apply(&mut self, rule: &Rule, offset: usize) {
+        let (width, height) = self.grid.size();
+        let grid = self.grid.cells_mut();
+        for by in (0..height).step_by(2) {
+            for bx in (0..width).step_by(2) {
+                // The four cells of the block, wrapping round the far edges
+                let (x0, y0) = (bx + offset, by + offset);
+                let (x1, y1) = ((x0 + 1) % width, (y0 + 1) % height);
+                let (x0, y0) = (x0 % width, y0 % height);
+                let cells = [
+                    y0 * width + x0,
+                    y0 * width + x1,
+                    y1 * width + x0,
+                    y1 * width + x1,
+                ];
+                let block = cells
+                    .iter()
+                    .enumerate()
+                    .fold(0, |block, (bit, &i)| block | (grid[i] << bit));
+                let next = rule.apply(block);
+                for (bit, &i) in cells.iter().enumerate() {
+                    grid[i] = (next >> bit) & 1;
+                }
+            }
+        }
+    }
+
+    /// Which way the blocks are cut for `generation`: 0 on even generations, 1 on odd ones
+    fn offset(generation: i64) -> usize {
+        generation.rem_euclid(2) as usize
+    }
+
+    pub fn step(&mut self) {
+        let rule = self.rule;
+        self.apply(&rule, Self::offset(self.generation));
+        self.generation += 1;
+    }
+
+    /// Go back a generation, undoing exactly what the last step did. Returns false without
+    /// changing anything if the rule can't be undone
+    pub fn step_back(&mut self) -> bool {
+        let Some(inverse) = self.rule.inverse() else {
+            return false;
+        };
+        self.generation -= 1;
+        self.apply(&inverse, Self::offset(self.generation));
+        true
+    }
+}
@@ -0,0 +1,367 @@
+//! Reading and writing patterns in all the supported file formats
+//!
+//! Besides RLE (see [`super::rle`]) this handles:
+//! * Plaintext `.cells` files: one row per line, `O` for live cells and `.` for dead ones, with
+//!   `!` comment lines on top
+//! * Life 1.05: blocks of `*`/`.` rows, each placed by a `#P x y` line
+//! * Life 1.06: just a list of `x y` coordinates of live cells
+//! * Mirek's Cellebration `.mcl` files (see [`super::mcell`])
+//!
+//! Reading figures out the format from the contents of the file, so the extension doesn't matter.
+
+use std::{fmt, fs, io, path::Path};
+
+use super::{MAX_CELLS, MAX_SIZE, Pattern, PatternError, PatternErrorKind, mcell, rle};
+use crate::Pos;
+
+/// Rule that Life 1.05 files mean when they say `#N` ("normal" rules)
+const CONWAY_RULE: &str = "B3/S23";
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Format {
+    #[default]
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    MCell,
+}
+
+impl Format {
+    pub const ALL: [Self; 5] = [
+        Self::Rle,
+        Self::Plaintext,
+        Self::Life105,
+        Self::Life106,
+        Self::MCell,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rle => "RLE (.rle)",
+            Self::Plaintext => "Plaintext (.cells)",
+            Self::Life105 => "Life 1.05 (.lif)",
+            Self::Life106 => "Life 1.06 (.lif)",
+            Self::MCell => "MCell (.mcl)",
+        }
+    }
+
+    /// Guess the format of a pattern file from what's in it
+    pub fn detect(src: &str) -> Self {
+        let mut lines = src.lines().map(str::trim).filter(|l| !l.is_empty());
+        match lines.next() {
+            Some(l) if l.starts_with("#Life 1.06") => Self::Life106,
+            Some(l) if l.starts_with("#Life 1.05") => Self::Life105,
+            Some(l) if l.starts_with("#MCell") => Self::MCell,
+            Some(l) if l.starts_with('!') => Self::Plaintext,
+            // Headerless plaintext, nothing but dots and Os
+            Some(l) if l.chars().all(|c| matches!(c, '.' | 'O' | '*')) => {
+                if lines.all(|l| l.chars().all(|c| matches!(c, '.' | 'O' | '*'))) {
+                    Self::Plaintext
+                } else {
+                    Self::Rle
+                }
+            }
+            _ => Self::Rle,
+        }
+    }
+}
+
+/// Why a pattern file couldn't be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file couldn't be read at all
+    Io(io::Error),
+    /// The file was read, but doesn't contain a valid pattern
+    Parse(PatternError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read file: {e}"),
+            Self::Parse(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<PatternError> for LoadError {
+    fn from(e: PatternError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Read a pattern file from disk, in whatever format it turns out to be
+pub fn load(path: impl AsRef<Path>) -> Result<Pattern, LoadError> {
+    let src = fs::read_to_string(path)?;
+    Ok(read(&src)?)
+}
+
+/// Write a pattern to disk in the given format
+pub fn save(path: impl AsRef<Path>, pattern: &Pattern, format: Format) -> io::Result<()> {
+    fs::write(path, write(pattern, format))
+}
+
+/// Parse a pattern, detecting the format from the contents
+pub fn read(src: &str) -> Result<Pattern, PatternError> {
+    read_as(src, Format::detect(src))
+}
+
+/// Parse a pattern that is known to be in `format`
+pub fn read_as(src: &str, format: Format) -> Result<Pattern, PatternError> {
+    match format {
+        Format::Rle => rle::parse(src),
+        Format::Plaintext => parse_plaintext(src),
+        Format::Life105 => parse_life105(src),
+        Format::Life106 => parse_life106(src),
+        Format::MCell => mcell::parse(src),
+    }
+}
+
+/// Write a pattern out in `format`. Formats without multi-state support write every non-empty
+/// cell as alive
+pub fn write(pattern: &Pattern, format: Format) -> String {
+    match format {
+        Format::Rle => rle::write(pattern),
+        Format::Plaintext => write_plaintext(pattern),
+        Format::Life105 => write_life105(pattern),
+        Format::Life106 => write_life106(pattern),
+        Format::MCell => mcell::write(pattern),
+    }
+}
+
+/// Live cells read so far at whatever coordinates the file gave them, and the box around them
+#[derive(Default)]
+struct Cells {
+    cells: Vec<(Pos, u8)>,
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl Cells {
+    /// Add a live cell at (`x`, `y`), which came from `line` and `column` of the file. Errors
+    /// if that makes the pattern more than [`MAX_SIZE`] across, or gives it too many cells
+    fn push(&mut self, (x, y): (i64, i64), line: usize, column: usize) -> Result<(), PatternError> {
+        let (min, max) = if self.cells.is_empty() {
+            ((x, y), (x, y))
+        } else {
+            (
+                (self.min.0.min(x), self.min.1.min(y)),
+                (self.max.0.max(x), self.max.1.max(y)),
+            )
+        };
+        let size = i64::from(MAX_SIZE);
+        let fits = max.0 - min.0 < size && max.1 - min.1 < size && self.cells.len() < MAX_CELLS;
+        match (i32::try_from(x), i32::try_from(y)) {
+            (Ok(x), Ok(y)) if fits => {
+                self.cells.push((Pos(x, y), 1));
+                (self.min, self.max) = (min, max);
+                Ok(())
+            }
+            _ => Err(PatternError::new(line, column, PatternErrorKind::TooBig)),
+        }
+    }
+
+    /// The final pattern, keeping the metadata in `meta`
+    fn finish(self, meta: Pattern) -> Pattern {
+        Pattern {
+            name: meta.name,
+            author: meta.author,
+            comments: meta.comments,
+            rule: meta.rule,
+            ..Pattern::from_cells(self.cells)
+        }
+    }
+}
+
+/// Parse one row of a plaintext or Life 1.05 file. Plaintext is supposed to use `O` and Life
+/// 1.05 `*` for live cells, but files in the wild mix them up so both are taken either way
+fn parse_row(
+    line: &str,
+    line_no: usize,
+    origin: (i64, i64),
+    cells: &mut Cells,
+) -> Result<(), PatternError> {
+    for (col_idx, ch) in line.trim_end().chars().enumerate() {
+        match ch {
+            '.' => {}
+            'O' | '*' => {
+                cells.push((origin.0 + col_idx as i64, origin.1), line_no, col_idx + 1)?;
+            }
+            _ => {
+                return Err(PatternError::new(
+                    line_no,
+                    col_idx + 1,
+                    PatternErrorKind::UnexpectedChar(ch),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_plaintext(src: &str) -> Result<Pattern, PatternError> {
+    let mut meta = Pattern::default();
+    let mut cells = Cells::default();
+    let mut y = 0;
+    for (line_idx, line) in src.lines().enumerate() {
+        if let Some(comment) = line.strip_prefix('!') {
+            let comment = comment.trim();
+            if let Some(name) = comment.strip_prefix("Name:") {
+                meta.name = Some(name.trim().to_owned());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                meta.author = Some(author.trim().to_owned());
+            } else {
+                meta.comments.push(comment.to_owned());
+            }
+            continue;
+        }
+        parse_row(line, line_idx + 1, (0, y), &mut cells)?;
+        y += 1;
+    }
+    Ok(cells.finish(meta))
+}
+
+fn write_plaintext(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("!Name: {name}\n"));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("!Author: {author}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("!{comment}\n"));
+    }
+    out.push_str(&rows(pattern, 'O'));
+    out
+}
+
+/// The pattern drawn out as rows of `.` and `alive`, trailing dead cells left off
+fn rows(pattern: &Pattern, alive: char) -> String {
+    let width = pattern.width.max(0) as usize;
+    let height = pattern.height.max(0) as usize;
+    let mut grid = vec![vec!['.'; width]; height];
+    for (p, _) in pattern.cells.iter().filter(|(_, s)| *s != 0) {
+        grid[p.1 as usize][p.0 as usize] = alive;
+    }
+    let mut out = String::new();
+    for row in grid {
+        let row: String = row.into_iter().collect();
+        out.push_str(row.trim_end_matches('.'));
+        out.push('\n');
+    }
+    out
+}
+
+/// Parse the whitespace separated numbers on a line, reporting the column of anything that isn't
+fn parse_numbers<const N: usize>(
+    line: &str,
+    line_no: usize,
+    column_offset: usize,
+) -> Result<[i32; N], PatternError> {
+    let mut numbers = [0; N];
+    // Every word on the line along with the byte offset it starts at
+    let mut start = 0;
+    let mut words = line.split(char::is_whitespace).filter_map(|word| {
+        let word_start = start;
+        start += word.len() + 1;
+        (!word.is_empty()).then_some((word_start, word))
+    });
+    for number in &mut numbers {
+        let (word_start, word) = words.next().ok_or(PatternError::new(
+            line_no,
+            column_offset + line.len() + 1,
+            PatternErrorKind::ExpectedNumber,
+        ))?;
+        let column = column_offset + word_start + 1;
+        *number = word
+            .parse()
+            .map_err(|_err| PatternError::new(line_no, column, PatternErrorKind::ExpectedNumber))?;
+    }
+    Ok(numbers)
+}
+
+fn parse_life105(src: &str) -> Result<Pattern, PatternError> {
+    let mut meta = Pattern::default();
+    let mut cells = Cells::default();
+    // Where the next row of the current block goes, Life 1.05 coordinates are relative to the
+    // center of the pattern, `finish` takes care of shifting them back to the corner
+    let mut row = (0, 0);
+    for (line_idx, line) in src.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let Some(directive) = line.strip_prefix('#') else {
+            parse_row(line, line_no, row, &mut cells)?;
+            row.1 += 1;
+            continue;
+        };
+        let mut chars = directive.chars();
+        let kind = chars.next();
+        let text = chars.as_str();
+        match kind {
+            Some('D' | 'C') => meta.comments.push(text.trim().to_owned()),
+            Some('N') => meta.rule = Some(CONWAY_RULE.to_owned()),
+            Some('R') => meta.rule = Some(text.trim().to_owned()),
+            Some('P') => {
+                let [x, y] = parse_numbers(text, line_no, 2)?;
+                row = (i64::from(x), i64::from(y));
+            }
+            // The `#Life 1.05` header itself, and anything we don't know about
+            _ => {}
+        }
+    }
+    Ok(cells.finish(meta))
+}
+
+fn write_life105(pattern: &Pattern) -> String {
+    let mut out = "#Life 1.05\n".to_owned();
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#D {name}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#D {comment}\n"));
+    }
+    match pattern.rule.as_deref() {
+        None | Some(CONWAY_RULE) => out.push_str("#N\n"),
+        Some(rule) => out.push_str(&format!("#R {rule}\n")),
+    }
+    out.push_str(&format!(
+        "#P {} {}\n",
+        -(pattern.width / 2),
+        -(pattern.height / 2)
+    ));
+    out.push_str(&rows(pattern, '*'));
+    out
+}
+
+fn parse_life106(src: &str) -> Result<Pattern, PatternError> {
+    let mut cells = Cells::default();
+    for (line_idx, line) in src.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let [x, y] = parse_numbers(line, line_idx + 1, 0)?;
+        cells.push((i64::from(x), i64::from(y)), line_idx + 1, 1)?;
+    }
+    Ok(cells.finish(Pattern::default()))
+}
+
+fn write_life106(pattern: &Pattern) -> String {
+    let mut out = "#Life 1.06\n".to_owned();
+    // Centered on the origin, like most 1.06 files out there
+    let (dx, dy) = (pattern.width / 2, pattern.height / 2);
+    for (p, _) in pattern.cells.iter().filter(|(_, s)| *s != 0) {
+        out.push_str(&format!("{} {}\n", p.0 - dx, p.1 - dy));
+    }
+    out
+}
@@ -0,0 +1,182 @@
+//! Reader and writer for Mirek's Cellebration `.mcl` patterns, how most Wireworld circuits get
+//! passed around
+//!
+//! A file looks something like this:
+//! ```text
+//! #MCell 4.20
+//! #GAME WireWorld
+//! #BOARD 40x20
+//! #D A clock feeding a wire
+//! #L .3C$A.CB$.3C
+//! ```
+//! The `#L` lines are run length encoded much like RLE: `.` is state 0, `A`..`X` states 1 to 24
+//! (with an `a`..`j` prefix for the states above that) and `$` ends a row. There's no `!` at the
+//! end, the cells just stop with the last `#L` line.
+//!
+//! `#GAME` says which family of rules the pattern is for, and `#RULE` which one of them. Only the
+//! family is needed for games like Wireworld that have just the one rule. Life rules are written
+//! survival first (`23/3`), Generations rules add the number of states (`345/2/4`) and Larger
+//! than Life ones use the same `R...` form we do. Rules it has no way of writing, like Hensel or
+//! hexagonal ones, are left out of the file.
+
+use super::runs::{Dialect, Runs};
+use super::{Pattern, PatternError};
+use crate::conway::rule::{Neighborhood, Rule};
+
+/// Games that are a rule of their own, as (what Mirek's Cellebration calls it, what Golly calls the rule)
+const NAMED_GAMES: [(&str, &str); 1] = [("WireWorld", crate::wireworld::RULE_NAME)];
+
+/// Letters `.mcl` cell data is written with
+const DIALECT: Dialect = Dialect {
+    dead: &['.'],
+    alive: None,
+    prefixes: 'a'..='j',
+    end: None,
+};
+
+/// Cell data is split over `#L` lines of about this length when writing
+const LINE_LENGTH: usize = 70;
+
+/// Parse the contents of an `.mcl` file
+pub fn parse(src: &str) -> Result<Pattern, PatternError> {
+    let mut comments = Vec::new();
+    let mut game = None;
+    let mut rule = None;
+    let mut runs = Runs::new(DIALECT);
+    let mut last = (1, 1);
+
+    for (line_idx, line) in src.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let line = line.trim_end();
+        let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match key {
+            "#GAME" => game = Some(value.trim().to_owned()),
+            "#RULE" => rule = Some(value.trim().to_owned()),
+            "#D" => comments.push(value.trim().to_owned()),
+            "#L" => {
+                // Column where `value` starts, for pointing at mistakes in it
+                let offset = line.len() - value.len();
+                for (col_idx, ch) in value.chars().enumerate() {
+                    last = (line_no, offset + col_idx + 1);
+                    runs.read(ch)
+                        .map_err(|kind| PatternError::new(last.0, last.1, kind))?;
+                }
+            }
+            // `#MCell`, `#BOARD`, `#SPEED`, `#WRAP`, colors and anything else we don't know about
+            _ => {}
+        }
+    }
+    let cells = runs
+        .finish()
+        .map_err(|kind| PatternError::new(last.0, last.1, kind))?;
+
+    let named = game.as_deref().and_then(|game| {
+        NAMED_GAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(game))
+            .map(|(_, rule)| (*rule).to_owned())
+    });
+    Ok(Pattern {
+        comments,
+        rule: named.or(rule),
+        ..Pattern::from_cells(cells)
+    })
+}
+
+/// Write a pattern out as `.mcl`, with the cell data split over `#L` lines
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = "#MCell 4.20\n".to_owned();
+    match pattern.rule.as_deref() {
+        Some(rule) => out.push_str(&game(rule).unwrap_or_default()),
+        None => out.push_str("#GAME Life\n"),
+    }
+    out.push_str(&format!("#BOARD {}x{}\n", pattern.width, pattern.height));
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#D {name}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#D {comment}\n"));
+    }
+
+    let mut cells = pattern.cells.clone();
+    cells.sort_unstable_by_key(|(p, _)| (p.1, p.0));
+    // Every run as (count, letters), merging neighbors that are the same
+    let mut runs: Vec<(i32, String)> = Vec::new();
+    let mut push = |count: i32, tag: String| match runs.last_mut() {
+        Some((n, last)) if *last == tag => *n += count,
+        _ => runs.push((count, tag)),
+    };
+    let (mut x, mut y) = (0, 0);
+    for (p, state) in cells.into_iter().filter(|(_, s)| *s != 0) {
+        if p.1 > y {
+            push(p.1 - y, "$".to_owned());
+            (x, y) = (0, p.1);
+        }
+        if p.0 > x {
+            push(p.0 - x, ".".to_owned());
+        }
+        push(1, state_tag(state));
+        x = p.0 + 1;
+    }
+
+    let mut line = String::new();
+    for (count, tag) in runs {
+        let token = if count == 1 {
+            tag
+        } else {
+            format!("{count}{tag}")
+        };
+        if line.len() + token.len() > LINE_LENGTH {
+            out.push_str(&format!("#L {line}\n"));
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    if !line.is_empty() {
+        out.push_str(&format!("#L {line}\n"));
+    }
+    out
+}
+
+/// The `#GAME` and `#RULE` lines for `rule`, if it's one Mirek's Cellebration can run
+fn game(rule: &str) -> Option<String> {
+    if let Some((game, _)) = NAMED_GAMES
+        .iter()
+        .find(|(_, named)| named.eq_ignore_ascii_case(rule))
+    {
+        return Some(format!("#GAME {game}\n"));
+    }
+    let rule: Rule = rule.parse().ok()?;
+    if rule.range().is_some() {
+        return Some(format!("#GAME Larger than Life\n#RULE {rule}\n"));
+    }
+    if rule.neighborhood() != Neighborhood::Moore || !rule.is_totalistic() {
+        return None;
+    }
+    let counts = |on: &dyn Fn(usize) -> bool| {
+        (0..=8)
+            .filter(|&n| on(n))
+            .map(|n| n.to_string())
+            .collect::<String>()
+    };
+    let survival = counts(&|n| rule.survives_on(n));
+    let birth = counts(&|n| rule.births_on(n));
+    Some(if rule.is_generations() {
+        format!(
+            "#GAME Generations\n#RULE {survival}/{birth}/{}\n",
+            rule.states()
+        )
+    } else {
+        format!("#GAME Life\n#RULE {survival}/{birth}\n")
+    })
+}
+
+/// The letters for a cell in the given (non-zero) state
+fn state_tag(state: u8) -> String {
+    let index = state - 1;
+    let letter = char::from(b'A' + index % 24);
+    match index / 24 {
+        0 => letter.to_string(),
+        prefix => format!("{}{letter}", char::from(b'a' + prefix - 1)),
+    }
+}
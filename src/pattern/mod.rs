@@ -0,0 +1,120 @@
+//! Patterns as they're stored in files, independent of any map they end up on
+//!
+//! A [`Pattern`] is just a bag of live cells with their states, positioned relative to the top
+//! left corner of its bounding box, plus whatever metadata the file carried along with it.
+
+pub mod io;
+pub mod mcell;
+pub mod rle;
+mod runs;
+
+use std::fmt;
+
+use crate::Pos;
+
+/// Furthest a pattern read from a file can reach in either direction
+pub const MAX_SIZE: i32 = 1 << 20;
+/// Most live cells a pattern read from a file can have
+pub const MAX_CELLS: usize = 1 << 22;
+
+/// A pattern read from (or about to be written to) a file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    /// Name of the pattern (`#N` in RLE files)
+    pub name: Option<String>,
+    /// Who found/built it (`#O` in RLE files)
+    pub author: Option<String>,
+    /// Free-form comment lines
+    pub comments: Vec<String>,
+    /// Rulestring the pattern was made for, if the file says
+    pub rule: Option<String>,
+    /// Width of the bounding box
+    pub width: i32,
+    /// Height of the bounding box
+    pub height: i32,
+    /// Every non-empty cell and its state, relative to the top left of the bounding box
+    pub cells: Vec<(Pos, u8)>,
+}
+
+impl Pattern {
+    /// Build a pattern from cells at arbitrary positions, shifting them so the bounding box starts
+    /// at (0, 0). Cells come out sorted in reading order, so the result doesn't depend on the
+    /// iteration order of whatever they were collected from.
+    ///
+    /// The cells have to be less than `i32::MAX` apart either way, the readers make sure of that
+    /// by keeping patterns within [`MAX_SIZE`].
+    pub fn from_cells(cells: impl IntoIterator<Item = (Pos, u8)>) -> Self {
+        let mut cells: Vec<(Pos, u8)> = cells.into_iter().filter(|(_, s)| *s != 0).collect();
+        let min_x = cells.iter().map(|(p, _)| p.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|(p, _)| p.1).min().unwrap_or(0);
+        for (p, _) in &mut cells {
+            *p = Pos(p.0 - min_x, p.1 - min_y);
+        }
+        cells.sort_unstable_by_key(|(p, _)| (p.1, p.0));
+        let width = cells.iter().map(|(p, _)| p.0 + 1).max().unwrap_or(0);
+        let height = cells.iter().map(|(p, _)| p.1 + 1).max().unwrap_or(0);
+        Self {
+            width,
+            height,
+            cells,
+            ..Default::default()
+        }
+    }
+
+    /// Highest state used by any cell, 0 for an empty pattern
+    pub fn max_state(&self) -> u8 {
+        self.cells.iter().map(|(_, s)| *s).max().unwrap_or(0)
+    }
+}
+
+/// Something wrong with a pattern file, pointing at where it went wrong (both 1-based)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PatternErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    /// A character that doesn't belong in this part of the file
+    UnexpectedChar(char),
+    /// The `x = .., y = ..` header line couldn't be understood
+    BadHeader(String),
+    /// A run count that is zero, or that takes the pattern past [`MAX_SIZE`] or [`MAX_CELLS`]
+    BadCount,
+    /// A multi-state cell letter past the 255 states a cell can have
+    StateOutOfRange,
+    /// The file ended in the middle of something
+    UnexpectedEnd,
+    /// A coordinate or offset that should be a whole number
+    ExpectedNumber,
+    /// Cells further apart than [`MAX_SIZE`], or more of them than [`MAX_CELLS`]
+    TooBig,
+}
+
+impl PatternError {
+    pub fn new(line: usize, column: usize, kind: PatternErrorKind) -> Self {
+        Self { line, column, kind }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            PatternErrorKind::UnexpectedChar(ch) => write!(f, "unexpected character '{ch}'"),
+            PatternErrorKind::BadHeader(why) => write!(f, "bad header, {why}"),
+            PatternErrorKind::BadCount => write!(f, "invalid run count"),
+            PatternErrorKind::StateOutOfRange => write!(f, "cell state is out of range"),
+            PatternErrorKind::UnexpectedEnd => write!(f, "unexpected end of file"),
+            PatternErrorKind::ExpectedNumber => write!(f, "expected a number"),
+            PatternErrorKind::TooBig => write!(
+                f,
+                "patterns can only be {MAX_SIZE} cells across, with {MAX_CELLS} live cells"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
@@ -0,0 +1,214 @@
+//! Reader and writer for Run Length Encoded patterns, the format Golly and the Life wiki use
+//!
+//! A file looks something like this:
+//! ```text
+//! #N Glider
+//! #C The smallest spaceship
+//! x = 3, y = 3, rule = B3/S23
+//! bob$2bo$3o!
+//! ```
+//! Cells are written as runs: `b` (or `.`) is a dead cell, `o` a live one, `$` ends a row and `!`
+//! ends the pattern. Rules with more than two states use `A`..`X` for states 1 to 24, with a
+//! `p`..`y` prefix for the states above that (`pA` is 25, `yO` is 255).
+
+use super::runs::{Dialect, Read, Runs};
+use super::{MAX_SIZE, Pattern, PatternError, PatternErrorKind};
+
+/// Lines of cell data are wrapped at this length when writing, same as Golly does
+const LINE_LENGTH: usize = 70;
+
+/// Letters RLE cell data is written with
+const DIALECT: Dialect = Dialect {
+    dead: &['b', '.'],
+    alive: Some('o'),
+    prefixes: 'p'..='y',
+    end: Some('!'),
+};
+
+/// Parse the contents of an RLE file
+pub fn parse(src: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut size = None;
+    let mut runs = Runs::new(DIALECT);
+    let mut started = false;
+    let mut last = (1, 1);
+
+    'lines: for (line_idx, line) in src.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let trimmed = line.trim();
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            parse_comment(comment, &mut pattern);
+            continue;
+        }
+        let is_header = trimmed.starts_with('x') && trimmed.contains('=');
+        if size.is_none() && !started && is_header {
+            let (width, height, rule) = parse_header(trimmed)
+                .map_err(|why| PatternError::new(line_no, 1, PatternErrorKind::BadHeader(why)))?;
+            size = Some((width, height));
+            if rule.is_some() {
+                pattern.rule = rule;
+            }
+            continue;
+        }
+
+        for (col_idx, ch) in line.chars().enumerate() {
+            last = (line_no, col_idx + 1);
+            started |= !ch.is_whitespace();
+            match runs.read(ch) {
+                Ok(Read::More) => {}
+                Ok(Read::End) => break 'lines,
+                Err(kind) => return Err(PatternError::new(line_no, col_idx + 1, kind)),
+            }
+        }
+    }
+    let cells = runs
+        .finish()
+        .map_err(|kind| PatternError::new(last.0, last.1, kind))?;
+
+    // Trust the header for the size, unless the cells spill out of it
+    let (width, height) = size.unwrap_or((0, 0));
+    pattern.width = cells.iter().map(|(p, _)| p.0 + 1).fold(width, i32::max);
+    pattern.height = cells.iter().map(|(p, _)| p.1 + 1).fold(height, i32::max);
+    pattern.cells = cells;
+    Ok(pattern)
+}
+
+/// Handle a `#` line (without the `#`), the letter after it says what kind of line it is
+fn parse_comment(comment: &str, pattern: &mut Pattern) {
+    let mut chars = comment.chars();
+    let kind = chars.next();
+    let text = chars.as_str().trim().to_owned();
+    match kind {
+        Some('N') => pattern.name = Some(text),
+        Some('O') => pattern.author = Some(text),
+        Some('r') => pattern.rule = Some(text),
+        Some('C' | 'c') => pattern.comments.push(text),
+        // `#P`/`#R` positions and anything else we don't know about are dropped
+        _ => {}
+    }
+}
+
+/// Parse `x = 3, y = 3, rule = B3/S23` into its parts
+fn parse_header(line: &str) -> Result<(i32, i32, Option<String>), String> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+    let mut rest = line;
+    while !rest.is_empty() {
+        let (key, after) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("expected `key = value`, got `{}`", rest.trim()))?;
+        let key = key.trim();
+        if key == "rule" {
+            // Rulestrings can have commas in them (Larger than Life does), so the rule always
+            // takes the rest of the line
+            rule = Some(after.trim().to_owned());
+            break;
+        }
+        let (value, next) = after.split_once(',').unwrap_or((after, ""));
+        let value = value.trim();
+        let number = || {
+            value
+                .parse::<i32>()
+                .ok()
+                .filter(|n| (0..=MAX_SIZE).contains(n))
+                .ok_or_else(|| format!("`{key}` should be a size up to {MAX_SIZE}, got `{value}`"))
+        };
+        match key {
+            "x" => width = Some(number()?),
+            "y" => height = Some(number()?),
+            // Golly doesn't write anything else, but be forgiving about extra keys
+            _ => {}
+        }
+        rest = next;
+    }
+    match (width, height) {
+        (Some(w), Some(h)) => Ok((w, h, rule)),
+        _ => Err("both `x` and `y` are needed".to_owned()),
+    }
+}
+
+/// Write a pattern out as RLE, comments first, then the header, then the cell data
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#N {name}\n"));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("#O {author}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {comment}\n"));
+    }
+    out.push_str(&format!("x = {}, y = {}", pattern.width, pattern.height));
+    if let Some(rule) = &pattern.rule {
+        out.push_str(&format!(", rule = {rule}"));
+    }
+    out.push('\n');
+
+    let multi_state = pattern.max_state() > 1;
+    let mut cells = pattern.cells.clone();
+    cells.sort_unstable_by_key(|(p, _)| (p.1, p.0));
+
+    let mut tokens = Vec::new();
+    let mut pending: Option<(String, i32)> = None;
+    let mut emit = |tag: String, n: i32, tokens: &mut Vec<String>| match &mut pending {
+        Some((pending_tag, count)) if *pending_tag == tag => *count += n,
+        _ => {
+            if let Some((tag, count)) = pending.replace((tag, n)) {
+                tokens.push(run_token(&tag, count));
+            }
+        }
+    };
+    let (mut x, mut y) = (0, 0);
+    for (p, state) in cells.into_iter().filter(|(_, s)| *s != 0) {
+        if p.1 > y {
+            emit("$".to_owned(), p.1 - y, &mut tokens);
+            (x, y) = (0, p.1);
+        }
+        if p.0 > x {
+            let dead = if multi_state { "." } else { "b" };
+            emit(dead.to_owned(), p.0 - x, &mut tokens);
+        }
+        emit(state_tag(state, multi_state), 1, &mut tokens);
+        x = p.0 + 1;
+    }
+    emit("!".to_owned(), 1, &mut tokens);
+    if let Some((tag, count)) = pending {
+        tokens.push(run_token(&tag, count));
+    }
+
+    // Wrap the data without ever splitting a run in half
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len + token.len() > LINE_LENGTH {
+            out.push('\n');
+            line_len = 0;
+        }
+        line_len += token.len();
+        out.push_str(&token);
+    }
+    out.push('\n');
+    out
+}
+
+fn run_token(tag: &str, count: i32) -> String {
+    if count == 1 || tag == "!" {
+        tag.to_owned()
+    } else {
+        format!("{count}{tag}")
+    }
+}
+
+/// The letters for a live cell in the given state
+fn state_tag(state: u8, multi_state: bool) -> String {
+    if !multi_state {
+        return "o".to_owned();
+    }
+    let index = state - 1;
+    let letter = char::from(b'A' + index % 24);
+    match index / 24 {
+        0 => letter.to_string(),
+        prefix => format!("{}{letter}", char::from(b'p' + prefix - 1)),
+    }
+}
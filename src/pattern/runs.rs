@@ -0,0 +1,133 @@
+//! The run length encoded cell data that RLE and Mirek's Cellebration files both use
+//!
+//! Each run is an optional count followed by what it counts: a dead cell, a live cell, a
+//! multi-state letter (`A`..`X`, maybe after a prefix letter for states above 24) or `$` for the
+//! end of a row. The formats only differ in which letters they use, see [`Dialect`].
+//!
+//! Every run is checked against [`MAX_SIZE`] and [`MAX_CELLS`] before anything is made, so a
+//! count like `2000000000o` is an error instead of an allocation that never finishes.
+
+use std::ops::RangeInclusive;
+
+use super::{MAX_CELLS, MAX_SIZE, PatternErrorKind};
+use crate::Pos;
+
+/// The letters one format's cell data is written with
+pub struct Dialect {
+    /// Letters for a dead cell
+    pub dead: &'static [char],
+    /// Letter for a live cell in a two state pattern, if the format has one
+    pub alive: Option<char>,
+    /// Letters that go in front of `A`..`X` for states above 24, the first one adds 24
+    pub prefixes: RangeInclusive<char>,
+    /// Letter the cell data ends with, if the format has one
+    pub end: Option<char>,
+}
+
+/// Whether there's more cell data to come after a character
+pub enum Read {
+    More,
+    End,
+}
+
+/// Cells read so far, and where the next run goes
+pub struct Runs {
+    dialect: Dialect,
+    x: i32,
+    y: i32,
+    /// A run count we've read digits for, but not the cell it applies to yet
+    count: Option<i32>,
+    /// Prefix letter that is still waiting for its `A`..`X`
+    prefix: Option<char>,
+    cells: Vec<(Pos, u8)>,
+}
+
+impl Runs {
+    pub fn new(dialect: Dialect) -> Self {
+        Self {
+            dialect,
+            x: 0,
+            y: 0,
+            count: None,
+            prefix: None,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Take the next character of cell data
+    pub fn read(&mut self, ch: char) -> Result<Read, PatternErrorKind> {
+        if self.prefix.is_some() && !ch.is_ascii_uppercase() {
+            return Err(PatternErrorKind::UnexpectedChar(ch));
+        }
+        if let Some(digit) = ch.to_digit(10) {
+            let count = self
+                .count
+                .unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit as i32))
+                .filter(|&n| n <= MAX_SIZE)
+                .ok_or(PatternErrorKind::BadCount)?;
+            self.count = Some(count);
+            return Ok(Read::More);
+        }
+        if ch.is_whitespace() {
+            return Ok(Read::More);
+        }
+
+        let run = self.count.take().unwrap_or(1);
+        if run == 0 {
+            return Err(PatternErrorKind::BadCount);
+        }
+        let state = match ch {
+            _ if self.dialect.dead.contains(&ch) => 0,
+            _ if self.dialect.alive == Some(ch) => 1,
+            _ if self.dialect.end == Some(ch) => return Ok(Read::End),
+            '$' => {
+                self.y = advance(self.y, run)?;
+                self.x = 0;
+                return Ok(Read::More);
+            }
+            'A'..='X' => {
+                let base = self.prefix.take().map_or(0, |prefix| {
+                    24 * (u32::from(prefix) - u32::from(*self.dialect.prefixes.start()) + 1)
+                });
+                let state = base + u32::from(ch) - u32::from('A') + 1;
+                u8::try_from(state).map_err(|_err| PatternErrorKind::StateOutOfRange)?
+            }
+            _ if self.dialect.prefixes.contains(&ch) => {
+                if run != 1 {
+                    // The count belongs in front of the prefix, not between it and its letter
+                    self.count = Some(run);
+                }
+                self.prefix = Some(ch);
+                return Ok(Read::More);
+            }
+            _ => return Err(PatternErrorKind::UnexpectedChar(ch)),
+        };
+        let end = advance(self.x, run)?;
+        if state != 0 {
+            if self.y >= MAX_SIZE || self.cells.len() + run as usize > MAX_CELLS {
+                return Err(PatternErrorKind::BadCount);
+            }
+            let y = self.y;
+            self.cells.extend((self.x..end).map(|x| (Pos(x, y), state)));
+        }
+        self.x = end;
+        Ok(Read::More)
+    }
+
+    /// Every live cell read, as long as the data didn't stop halfway through a run
+    pub fn finish(self) -> Result<Vec<(Pos, u8)>, PatternErrorKind> {
+        if self.count.is_some() || self.prefix.is_some() {
+            return Err(PatternErrorKind::UnexpectedEnd);
+        }
+        Ok(self.cells)
+    }
+}
+
+/// Move `run` cells along from `from`, as long as that stays inside [`MAX_SIZE`]
+fn advance(from: i32, run: i32) -> Result<i32, PatternErrorKind> {
+    from.checked_add(run)
+        .filter(|&to| to <= MAX_SIZE)
+        .ok_or(PatternErrorKind::BadCount)
+}
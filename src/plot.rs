@@ -0,0 +1,189 @@
+//! Bare-bones line plots and bar charts, drawn straight onto an egui painter
+//!
+//! Just enough for watching numbers change over a run: every series is scaled to fit the same
+//! box, with the range written in the corners and the latest values in a legend underneath.
+//! Hovering shows the values at that point. Bar charts are for counts, like a histogram.
+
+use egui::{Align2, Color32, FontId, Sense, Shape, Stroke, StrokeKind, emath, pos2, vec2};
+
+/// One line on a plot
+pub struct Series<'a> {
+    pub name: &'a str,
+    pub color: Color32,
+    /// `[x, y]` points, in order of increasing x
+    pub points: Vec<[f64; 2]>,
+}
+
+/// Draw the series as lines in a box `height` points tall, filling the available width
+pub fn line_plot(ui: &mut egui::Ui, series: &[Series<'_>], height: f32) {
+    let size = vec2(ui.available_width(), height);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals();
+    let text_color = visuals.weak_text_color();
+    let font = FontId::monospace(10.0);
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    painter.rect_stroke(
+        rect,
+        2.0,
+        visuals.widgets.noninteractive.bg_stroke,
+        StrokeKind::Inside,
+    );
+
+    let points = || series.iter().flat_map(|s| s.points.iter());
+    let (Some(x_min), Some(x_max)) = (
+        points().map(|p| p[0]).reduce(f64::min),
+        points().map(|p| p[0]).reduce(f64::max),
+    ) else {
+        painter.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            "No data yet",
+            font,
+            text_color,
+        );
+        return;
+    };
+    // Counts, so the bottom of the plot is zero unless something goes negative
+    let y_min = points().map(|p| p[1]).fold(0.0, f64::min);
+    let mut y_max = points().map(|p| p[1]).fold(f64::MIN, f64::max);
+    if y_max <= y_min {
+        y_max = y_min + 1.0;
+    }
+    let x_max = if x_max > x_min { x_max } else { x_min + 1.0 };
+
+    let inner = rect.shrink(4.0);
+    let to_screen = |[x, y]: [f64; 2]| {
+        pos2(
+            emath::remap(
+                x,
+                x_min..=x_max,
+                f64::from(inner.left())..=f64::from(inner.right()),
+            ) as f32,
+            emath::remap(
+                y,
+                y_min..=y_max,
+                f64::from(inner.bottom())..=f64::from(inner.top()),
+            ) as f32,
+        )
+    };
+    for s in series {
+        let line: Vec<_> = s.points.iter().map(|&p| to_screen(p)).collect();
+        painter.add(Shape::line(line, Stroke::new(1.5, s.color)));
+    }
+
+    painter.text(
+        rect.left_top() + vec2(4.0, 2.0),
+        Align2::LEFT_TOP,
+        format!("{y_max}"),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        rect.left_bottom() + vec2(4.0, -2.0),
+        Align2::LEFT_BOTTOM,
+        format!("{y_min}"),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        rect.right_bottom() + vec2(-4.0, -2.0),
+        Align2::RIGHT_BOTTOM,
+        format!("{x_min} – {x_max}"),
+        font,
+        text_color,
+    );
+
+    if let Some(hover) = response.hover_pos() {
+        // Values of every series at the nearest x to the pointer
+        let x = emath::remap(
+            f64::from(hover.x),
+            f64::from(inner.left())..=f64::from(inner.right()),
+            x_min..=x_max,
+        );
+        painter.vline(hover.x, rect.y_range(), Stroke::new(1.0, text_color));
+        let text = series
+            .iter()
+            .filter_map(|s| {
+                let nearest = s
+                    .points
+                    .iter()
+                    .min_by(|a, b| (a[0] - x).abs().total_cmp(&(b[0] - x).abs()))?;
+                Some(format!("{}: {} at {}", s.name, nearest[1], nearest[0]))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        response.on_hover_text_at_pointer(text);
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        for s in series {
+            let latest = s.points.last().map_or(0.0, |p| p[1]);
+            ui.colored_label(s.color, format!("■ {} {latest}", s.name));
+        }
+    });
+}
+
+/// Draw one bar per value in a box `height` points tall, filling the available width. Hovering
+/// over a bar shows its label and value
+pub fn bar_plot(ui: &mut egui::Ui, bars: &[(String, u64)], color: Color32, height: f32) {
+    let size = vec2(ui.available_width(), height);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals();
+    let text_color = visuals.weak_text_color();
+    let font = FontId::monospace(10.0);
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    painter.rect_stroke(
+        rect,
+        2.0,
+        visuals.widgets.noninteractive.bg_stroke,
+        StrokeKind::Inside,
+    );
+
+    let Some(max) = bars
+        .iter()
+        .map(|&(_, value)| value)
+        .max()
+        .filter(|&max| max > 0)
+    else {
+        painter.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            "No data yet",
+            font,
+            text_color,
+        );
+        return;
+    };
+    let inner = rect.shrink(4.0);
+    let width = inner.width() / bars.len() as f32;
+    let mut hovered = None;
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let left = inner.left() + i as f32 * width;
+        let top = emath::remap(
+            *value as f32,
+            0.0..=max as f32,
+            inner.bottom()..=inner.top(),
+        );
+        let bar =
+            egui::Rect::from_x_y_ranges(left + 1.0..=left + width - 1.0, top..=inner.bottom());
+        painter.rect_filled(bar, 0.0, color);
+        if response
+            .hover_pos()
+            .is_some_and(|hover| (left..left + width).contains(&hover.x))
+        {
+            hovered = Some(format!("{label}: {value}"));
+        }
+    }
+    painter.text(
+        rect.left_top() + vec2(4.0, 2.0),
+        Align2::LEFT_TOP,
+        format!("{max}"),
+        font,
+        text_color,
+    );
+    if let Some(text) = hovered {
+        response.on_hover_text_at_pointer(text);
+    }
+}
@@ -0,0 +1,313 @@
+//! The abelian sandpile
+//!
+//! Every cell holds a pile of grains. Once a pile reaches the threshold it topples, losing that
+//! many grains and passing one to each of its neighbors, which may set them toppling in turn.
+//! Grains passed over the edge of the grid are lost. With the threshold equal to the number of
+//! neighbors (4 for von Neumann, 8 for Moore) nothing else is lost, and the pile it settles into
+//! doesn't depend on the order the topples happen in, hence abelian. A threshold higher than
+//! that throws the extra grains away on every topple.
+//!
+//! Grains get added one drop at a time, and after every drop the grid is relaxed until nothing
+//! is left to topple. How many topples that took is the size of the avalanche, and a histogram
+//! of them, binned by powers of two, is kept to show off the power law they follow.
+
+use rand::{Rng as _, SeedableRng as _};
+use rand_chacha::ChaCha8Rng;
+
+use crate::grid::{Grid, Neighborhood};
+use crate::timing::Pace;
+
+pub const DEFAULT_SIZE: usize = 200;
+
+/// Where grains go each step
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Drop {
+    /// All of them on the middle cell
+    #[default]
+    Point,
+    /// Each on a cell picked at random, relaxing after every one
+    Random,
+    /// The same number on every cell at once
+    Layer,
+}
+
+impl Drop {
+    pub const ALL: [Self; 3] = [Self::Point, Self::Random, Self::Layer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Point => "Point",
+            Self::Random => "Random",
+            Self::Layer => "Layer",
+        }
+    }
+}
+
+/// What the cells are colored by
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Coloring {
+    /// How many grains are on each cell
+    #[default]
+    Grains,
+    /// How many times each cell toppled in the last avalanche
+    Topples,
+}
+
+impl Coloring {
+    pub const ALL: [Self; 2] = [Self::Grains, Self::Topples];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Grains => "Grains",
+            Self::Topples => "Topples",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Sandpile {
+    pub neighborhood: Neighborhood,
+    /// Grains a pile needs to topple, never fewer than it has neighbors
+    threshold: u32,
+    pub drop: Drop,
+    /// Grains dropped each step, and with every click
+    pub grains_per_drop: u32,
+    pub coloring: Coloring,
+    /// Every cell's pile
+    grains: Grid<u32>,
+    /// Steps per second
+    pub pace: Pace,
+    /// How many times each cell toppled in the last avalanche
+    #[serde(skip)]
+    topples: Grid<u32>,
+    /// How many avalanches there have been of each size. The first bin is drops that toppled
+    /// nothing, after that bin `k` holds sizes from 2^(k - 1) up to 2^k
+    #[serde(skip)]
+    histogram: Vec<u64>,
+    /// Topples in the last avalanche
+    #[serde(skip)]
+    last_avalanche: u64,
+    #[serde(skip)]
+    steps: u64,
+    /// Where random drops land
+    #[serde(skip, default = "ChaCha8Rng::from_entropy")]
+    rng: ChaCha8Rng,
+}
+
+impl Default for Sandpile {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE, DEFAULT_SIZE)
+    }
+}
+
+impl Sandpile {
+    /// An empty `width`×`height` grid with the usual four neighbor, four grain rule
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut sandpile = Self {
+            neighborhood: Neighborhood::VonNeumann,
+            threshold: 4,
+            drop: Drop::Point,
+            grains_per_drop: 1,
+            coloring: Coloring::Grains,
+            grains: Grid::default(),
+            pace: Pace::new(60),
+            topples: Grid::default(),
+            histogram: Vec::new(),
+            last_avalanche: 0,
+            steps: 0,
+            rng: ChaCha8Rng::from_entropy(),
+        };
+        sandpile.resize(width, height);
+        sandpile
+    }
+
+    /// Steps since the grid was last cleared
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Every cell's pile
+    pub fn grains(&self) -> &Grid<u32> {
+        &self.grains
+    }
+
+    /// How many times each cell toppled in the last avalanche
+    pub fn topples(&self) -> &Grid<u32> {
+        &self.topples
+    }
+
+    /// Grains on the whole grid
+    pub fn total(&self) -> u64 {
+        self.grains.cells().iter().map(|&g| u64::from(g)).sum()
+    }
+
+    /// How many avalanches of each size there have been, see [`Sandpile::histogram_label`]
+    pub fn histogram(&self) -> &[u64] {
+        &self.histogram
+    }
+
+    /// The range of avalanche sizes in bin `bin` of the histogram
+    pub fn histogram_label(bin: usize) -> String {
+        match bin {
+            0 => "0".to_owned(),
+            1 => "1".to_owned(),
+            _ => format!("{}–{}", 1u64 << (bin - 1), (1u64 << bin) - 1),
+        }
+    }
+
+    /// Topples in the last avalanche
+    pub fn last_avalanche(&self) -> u64 {
+        self.last_avalanche
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Fewest grains a pile can topple at, one for each neighbor
+    pub fn min_threshold(&self) -> u32 {
+        self.neighborhood.offsets(1).len() as u32
+    }
+
+    pub fn set_threshold(&mut self, threshold: u32) {
+        self.threshold = threshold.max(self.min_threshold());
+        self.relax_everywhere();
+    }
+
+    /// Switch neighborhoods, taking the threshold to the new one's neighbor count
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+        self.threshold = self.min_threshold();
+        self.relax_everywhere();
+    }
+
+    /// Change the size of the grid, wiping it
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grains = Grid::new(width.max(1), height.max(1));
+        self.clear();
+    }
+
+    /// Take every grain away and forget the avalanches so far
+    pub fn clear(&mut self) {
+        self.grains.clear();
+        self.topples = Grid::new(self.grains.width(), self.grains.height());
+        self.histogram.clear();
+        self.last_avalanche = 0;
+        self.steps = 0;
+    }
+
+    /// Start the random drops over from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    /// Drop `grains` on the cell at column `x` of row `y` and let it all settle, returning how
+    /// many topples that took. Nothing happens past the edges
+    pub fn drop_at(&mut self, x: usize, y: usize, grains: u32) -> u64 {
+        let Some(i) = self.grains.index(x, y) else {
+            return 0;
+        };
+        let pile = &mut self.grains.cells_mut()[i];
+        *pile = pile.saturating_add(grains);
+        self.avalanche([i])
+    }
+
+    /// Drop `grains` on every cell at once and let it all settle, returning how many topples
+    /// that took
+    pub fn drop_layer(&mut self, grains: u32) -> u64 {
+        for pile in self.grains.cells_mut() {
+            *pile = pile.saturating_add(grains);
+        }
+        self.avalanche(0..self.grains.cells().len())
+    }
+
+    /// Drop this step's grains the way `drop` says
+    pub fn step(&mut self) {
+        match self.drop {
+            Drop::Point => {
+                let (width, height) = self.grains.size();
+                self.drop_at(width / 2, height / 2, self.grains_per_drop);
+            }
+            Drop::Random => {
+                for _ in 0..self.grains_per_drop {
+                    let x = self.rng.gen_range(0..self.grains.width());
+                    let y = self.rng.gen_range(0..self.grains.height());
+                    self.drop_at(x, y, 1);
+                }
+            }
+            Drop::Layer => {
+                self.drop_layer(self.grains_per_drop);
+            }
+        }
+        self.steps += 1;
+    }
+
+    /// Settle whatever's over the threshold after a change to the rule, without counting it as
+    /// an avalanche
+    fn relax_everywhere(&mut self) {
+        self.topples.clear();
+        self.relax(0..self.grains.cells().len());
+    }
+
+    /// Relax starting from the piles at `start` and record the avalanche
+    fn avalanche(&mut self, start: impl IntoIterator<Item = usize>) -> u64 {
+        self.topples.clear();
+        let size = self.relax(start);
+        let bin = if size == 0 {
+            0
+        } else {
+            size.ilog2() as usize + 1
+        };
+        if self.histogram.len() <= bin {
+            self.histogram.resize(bin + 1, 0);
+        }
+        self.histogram[bin] += 1;
+        self.last_avalanche = size;
+        size
+    }
+
+    /// Topple piles until none are left over the threshold, starting from the ones at `start`,
+    /// and return how many topples it took. A pile with several times the threshold on it
+    /// topples that many times in one go
+    fn relax(&mut self, start: impl IntoIterator<Item = usize>) -> u64 {
+        let threshold = self.threshold;
+        let offsets = self.neighborhood.offsets(1);
+        let (width, height) = (self.grains.width() as i64, self.grains.height() as i64);
+        let grains = self.grains.cells_mut();
+        let topples = self.topples.cells_mut();
+        let mut unstable: Vec<usize> = start
+            .into_iter()
+            .filter(|&i| grains[i] >= threshold)
+            .collect();
+        let mut size = 0;
+        while let Some(i) = unstable.pop() {
+            let times = grains[i] / threshold;
+            if times == 0 {
+                continue;
+            }
+            grains[i] -= times * threshold;
+            topples[i] += times;
+            size += u64::from(times);
+            let (x, y) = (i as i64 % width, i as i64 / width);
+            for &(dx, dy) in &offsets {
+                let (nx, ny) = (x + i64::from(dx), y + i64::from(dy));
+                if !(0..width).contains(&nx) || !(0..height).contains(&ny) {
+                    continue;
+                }
+                let n = (ny * width + nx) as usize;
+                let before = grains[n];
+                grains[n] = before.saturating_add(times);
+                // Only just tipped over, otherwise it's already waiting
+                if before < threshold && grains[n] >= threshold {
+                    unstable.push(n);
+                }
+            }
+        }
+        size
+    }
+}
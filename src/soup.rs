@@ -0,0 +1,203 @@
+//! Random soups to start a pattern off from, shared by every mode that has states to fill in
+//!
+//! The 2D map's soups are seeded: the seed is any text, hashed into the number the generator
+//! starts from, so a soup can be written down and made again later. Like apgsearch a soup can be
+//! made symmetric, by only rolling the dice for one cell of each set the symmetry maps onto each
+//! other and copying it to the rest.
+//!
+//! The same seed and settings give the same soup on every platform and with every version of
+//! `rand`. The generator is `ChaCha8`, whose output is pinned down (`StdRng` is free to change
+//! algorithm between releases), it's seeded with a hash that doesn't depend on the platform, and
+//! nothing is drawn as a `usize`, which `rand` draws differently on 32 bit targets like wasm.
+
+use std::ops::RangeInclusive;
+
+use rand::{Rng, SeedableRng as _};
+use rand_chacha::ChaCha8Rng;
+
+use crate::Pos;
+
+/// A state for every cell of a `width`×`height` grid, row by row. Each cell is filled in with
+/// chance `density`, and then gets a state picked uniformly from `states`. Cells left empty are 0
+pub fn soup(
+    width: usize,
+    height: usize,
+    density: f64,
+    states: RangeInclusive<u8>,
+    rng: &mut impl Rng,
+) -> Vec<u8> {
+    let density = density.clamp(0.0, 1.0);
+    (0..width * height)
+        .map(|_| {
+            if rng.gen_bool(density) {
+                rng.gen_range(states.clone())
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/// Symmetries a soup can have, named the way apgsearch names them
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Symmetry {
+    /// No symmetry at all
+    #[default]
+    C1,
+    /// The same turned half way round
+    C2,
+    /// The same turned a quarter of the way round, so the region has to be square
+    C4,
+    /// The same reflected left to right and top to bottom
+    D4,
+    /// The same turned or reflected any way a square can be, so the region has to be square
+    D8,
+}
+
+impl Symmetry {
+    pub const ALL: [Self; 5] = [Self::C1, Self::C2, Self::C4, Self::D4, Self::D8];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::C1 => "C1",
+            Self::C2 => "C2",
+            Self::C4 => "C4",
+            Self::D4 => "D4",
+            Self::D8 => "D8",
+        }
+    }
+
+    pub fn about(self) -> &'static str {
+        match self {
+            Self::C1 => "no symmetry",
+            Self::C2 => "the same turned half way round",
+            Self::C4 => "the same turned a quarter of the way round",
+            Self::D4 => "mirrored left to right and top to bottom",
+            Self::D8 => "mirrored and turned every way a square can be",
+        }
+    }
+
+    /// Whether the symmetry only works on a square
+    pub fn is_square(self) -> bool {
+        matches!(self, Self::C4 | Self::D8)
+    }
+
+    /// Every cell `(x, y)` is mapped onto in a `width`×`height` region, itself included
+    fn images(self, (x, y): (u32, u32), (width, height): (u32, u32)) -> Vec<(u32, u32)> {
+        let (right, bottom) = (width - 1 - x, height - 1 - y);
+        match self {
+            Self::C1 => vec![(x, y)],
+            Self::C2 => vec![(x, y), (right, bottom)],
+            Self::C4 => vec![(x, y), (bottom, x), (right, bottom), (y, right)],
+            Self::D4 => vec![(x, y), (right, y), (x, bottom), (right, bottom)],
+            Self::D8 => vec![
+                (x, y),
+                (bottom, x),
+                (right, bottom),
+                (y, right),
+                (y, x),
+                (right, y),
+                (bottom, right),
+                (x, bottom),
+            ],
+        }
+    }
+}
+
+/// How the 2D map's random soups are made
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Soup {
+    /// Any text, the same seed and settings always give the same soup
+    pub seed: String,
+    /// Chance of each cell being filled in, as a percentage
+    pub density: f64,
+    /// Size of the region in the middle of the map the soup covers
+    pub width: u32,
+    pub height: u32,
+    pub symmetry: Symmetry,
+}
+
+impl Default for Soup {
+    fn default() -> Self {
+        Self {
+            seed: new_seed(),
+            density: 25.0,
+            width: 64,
+            height: 64,
+            symmetry: Symmetry::C1,
+        }
+    }
+}
+
+/// A seed nobody has used before, most likely
+pub fn new_seed() -> String {
+    format!("{:016x}", rand::thread_rng().r#gen::<u64>())
+}
+
+impl Soup {
+    /// The generator this soup's random numbers come from
+    pub fn rng(&self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(fnv1a(self.seed.as_bytes()))
+    }
+
+    /// How big the soup comes out, which is the region at most `max` in either direction, and
+    /// square for the symmetries that need one
+    pub fn size(&self, (max_width, max_height): (u32, u32)) -> (u32, u32) {
+        let (width, height) = (self.width.min(max_width), self.height.min(max_height));
+        if self.symmetry.is_square() {
+            let side = width.min(height);
+            (side, side)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// The filled in cells of a soup at most `max` cells in either direction, relative to its
+    /// top left corner. Filled cells get a state picked uniformly from `states`
+    pub fn cells(&self, max: (u32, u32), states: RangeInclusive<u8>) -> Vec<(Pos, u8)> {
+        let size @ (width, height) = self.size(max);
+        let density = (self.density / 100.0).clamp(0.0, 1.0);
+        let mut rng = self.rng();
+        let mut grid = vec![0u8; width as usize * height as usize];
+        let at = |(x, y): (u32, u32)| y as usize * width as usize + x as usize;
+        for y in 0..height {
+            for x in 0..width {
+                // The first of the cells this one maps onto in reading order rolls the dice, the
+                // rest copy it
+                let images = self.symmetry.images((x, y), size);
+                let first = images
+                    .iter()
+                    .copied()
+                    .min_by_key(|&(x, y)| (y, x))
+                    .unwrap_or((x, y));
+                grid[at((x, y))] = if first == (x, y) {
+                    if rng.gen_bool(density) {
+                        rng.gen_range(states.clone())
+                    } else {
+                        0
+                    }
+                } else {
+                    grid[at(first)]
+                };
+            }
+        }
+        grid.into_iter()
+            .enumerate()
+            .filter(|&(_, state)| state != 0)
+            .map(|(i, state)| {
+                let (x, y) = (i % width as usize, i / width as usize);
+                (Pos(x as i32, y as i32), state)
+            })
+            .collect()
+    }
+}
+
+/// 64 bit FNV-1a, which hashes the same everywhere, unlike the standard library's hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
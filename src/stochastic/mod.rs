@@ -0,0 +1,289 @@
+//! Probabilistic cellular automata: the Drossel–Schwabl forest fire, and an SIR epidemic
+//!
+//! Both have three states, and every cell rolls the dice once a generation:
+//!
+//! - In the forest fire empty ground grows a tree with chance `growth`, a tree next to a fire
+//!   catches, any other tree is struck by lightning with chance `lightning`, and fires burn out
+//!   into empty ground. With lightning much rarer than growth the forest builds up and burns down
+//!   in fires of every size.
+//! - In the epidemic a susceptible cell catches it from each infected neighbor with chance
+//!   `infection`, so with k of them it stays healthy with chance (1 - infection)^k. Infected cells
+//!   recover with chance `recovery`, and recovered cells are immune from then on.
+//!
+//! Every random number comes from one generator seeded with `seed`, drawn in the same order every
+//! time, so the same seed and settings always give the same run. How many cells are in each state
+//! gets recorded every generation for plotting.
+//!
+//! Like the other grid modes the edges wrap round.
+
+use rand::{Rng as _, SeedableRng as _};
+use rand_chacha::ChaCha8Rng;
+
+use egui::Color32;
+
+use crate::grid::{Grid, Neighborhood};
+use crate::timing::Pace;
+
+pub const DEFAULT_SIZE: usize = 200;
+/// Most generations of counts kept for plotting
+pub const MAX_HISTORY: usize = 10_000;
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Kind {
+    #[default]
+    ForestFire,
+    Epidemic,
+}
+
+impl Kind {
+    pub const ALL: [Self; 2] = [Self::ForestFire, Self::Epidemic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ForestFire => "Forest fire",
+            Self::Epidemic => "SIR epidemic",
+        }
+    }
+
+    /// What states 0, 1 and 2 are called
+    pub fn compartments(self) -> [&'static str; 3] {
+        match self {
+            Self::ForestFire => ["empty", "trees", "fire"],
+            Self::Epidemic => ["susceptible", "infected", "recovered"],
+        }
+    }
+
+    /// The color of states 0, 1 and 2, on the grid and in the plots
+    pub fn colors(self) -> [Color32; 3] {
+        match self {
+            Self::ForestFire => [
+                Color32::from_rgb(60, 45, 30),
+                Color32::from_rgb(40, 160, 60),
+                Color32::from_rgb(255, 120, 20),
+            ],
+            Self::Epidemic => [
+                Color32::from_rgb(70, 130, 220),
+                Color32::from_rgb(230, 60, 50),
+                Color32::from_rgb(150, 150, 150),
+            ],
+        }
+    }
+}
+
+/// States in the forest fire
+pub mod forest {
+    pub const EMPTY: u8 = 0;
+    pub const TREE: u8 = 1;
+    pub const FIRE: u8 = 2;
+}
+
+/// States in the epidemic
+pub mod epidemic {
+    pub const SUSCEPTIBLE: u8 = 0;
+    pub const INFECTED: u8 = 1;
+    pub const RECOVERED: u8 = 2;
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ForestFire {
+    /// Chance of a tree growing on empty ground, p
+    pub growth: f64,
+    /// Chance of lightning setting a tree alight, f
+    pub lightning: f64,
+    /// How much of the grid starts off as trees
+    pub density: f64,
+}
+
+impl Default for ForestFire {
+    fn default() -> Self {
+        Self {
+            growth: 0.01,
+            lightning: 1e-5,
+            density: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Epidemic {
+    /// Chance of catching it from each infected neighbor
+    pub infection: f64,
+    /// Chance of an infected cell recovering
+    pub recovery: f64,
+    /// How much of the grid starts off infected
+    pub infected: f64,
+}
+
+impl Default for Epidemic {
+    fn default() -> Self {
+        Self {
+            infection: 0.2,
+            recovery: 0.1,
+            infected: 0.001,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Stochastic {
+    pub kind: Kind,
+    pub forest_fire: ForestFire,
+    pub epidemic: Epidemic,
+    pub neighborhood: Neighborhood,
+    /// Where every random number in a run comes from
+    pub seed: u64,
+    /// Every cell's state
+    grid: Grid<u8>,
+    /// Generations per second
+    pub pace: Pace,
+    /// How many cells were in each state, every generation since the start
+    #[serde(skip)]
+    history: Vec<[u64; 3]>,
+    #[serde(skip)]
+    generation: u64,
+    #[serde(skip, default = "ChaCha8Rng::from_entropy")]
+    rng: ChaCha8Rng,
+}
+
+impl Default for Stochastic {
+    fn default() -> Self {
+        Self::new(Kind::default(), DEFAULT_SIZE, DEFAULT_SIZE, 0)
+    }
+}
+
+impl Stochastic {
+    /// A `width`×`height` grid of `kind`, started off from `seed`
+    pub fn new(kind: Kind, width: usize, height: usize, seed: u64) -> Self {
+        let mut stochastic = Self {
+            kind,
+            forest_fire: ForestFire::default(),
+            epidemic: Epidemic::default(),
+            neighborhood: Neighborhood::VonNeumann,
+            seed,
+            grid: Grid::new(width.max(1), height.max(1)),
+            pace: Pace::new(30),
+            history: Vec::new(),
+            generation: 0,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+        stochastic.restart();
+        stochastic
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Every cell's state
+    pub fn grid(&self) -> &Grid<u8> {
+        &self.grid
+    }
+
+    /// Set the cell at column `x` of row `y`, to the last state at most
+    pub fn set_cell(&mut self, x: usize, y: usize, state: u8) {
+        self.grid.set(x, y, state.min(2));
+    }
+
+    /// How many cells were in each state, one entry per generation from the start
+    pub fn history(&self) -> &[[u64; 3]] {
+        &self.history
+    }
+
+    /// How many cells are in each state right now
+    pub fn counts(&self) -> [u64; 3] {
+        let mut counts = [0; 3];
+        for &cell in self.grid.cells() {
+            counts[usize::from(cell)] += 1;
+        }
+        counts
+    }
+
+    /// Change the size of the grid and start over
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid = Grid::new(width.max(1), height.max(1));
+        self.restart();
+    }
+
+    /// Start over from `seed`, with the grid filled in the way the kind starts off
+    pub fn restart(&mut self) {
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let (fill, state) = match self.kind {
+            Kind::ForestFire => (self.forest_fire.density, forest::TREE),
+            Kind::Epidemic => (self.epidemic.infected, epidemic::INFECTED),
+        };
+        let fill = fill.clamp(0.0, 1.0);
+        for cell in self.grid.cells_mut() {
+            *cell = if self.rng.gen_bool(fill) { state } else { 0 };
+        }
+        self.generation = 0;
+        self.history = vec![self.counts()];
+    }
+
+    pub fn step(&mut self) {
+        let (width, height) = (self.grid.width() as i32, self.grid.height() as i32);
+        let offsets = self.neighborhood.offsets(1);
+        // Only the neighbors in the state that spreads matter to either model
+        let spreads = match self.kind {
+            Kind::ForestFire => forest::FIRE,
+            Kind::Epidemic => epidemic::INFECTED,
+        };
+        let cells = self.grid.cells();
+        let mut next = cells.to_vec();
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                let spreading = offsets
+                    .iter()
+                    .filter(|&&(dx, dy)| {
+                        let (nx, ny) = ((x + dx).rem_euclid(width), (y + dy).rem_euclid(height));
+                        cells[(ny * width + nx) as usize] == spreads
+                    })
+                    .count() as i32;
+                // One draw per cell whatever happens, so what a cell does never changes what
+                // the cells after it get
+                let roll: f64 = self.rng.r#gen();
+                next[i] = match self.kind {
+                    Kind::ForestFire => self.forest_fire.next(cells[i], spreading, roll),
+                    Kind::Epidemic => self.epidemic.next(cells[i], spreading, roll),
+                };
+            }
+        }
+        self.grid.replace(next);
+        self.generation += 1;
+        if self.history.len() >= MAX_HISTORY {
+            self.history.drain(..MAX_HISTORY / 10);
+        }
+        self.history.push(self.counts());
+    }
+}
+
+impl ForestFire {
+    /// What a cell in `state` turns into with `burning` neighbors on fire, given a `roll` from 0
+    /// to 1
+    fn next(&self, state: u8, burning: i32, roll: f64) -> u8 {
+        match state {
+            forest::EMPTY if roll < self.growth => forest::TREE,
+            forest::TREE if burning > 0 || roll < self.lightning => forest::FIRE,
+            forest::FIRE => forest::EMPTY,
+            _ => state,
+        }
+    }
+}
+
+impl Epidemic {
+    /// What a cell in `state` turns into with `infected` neighbors, given a `roll` from 0 to 1
+    fn next(&self, state: u8, infected: i32, roll: f64) -> u8 {
+        match state {
+            epidemic::SUSCEPTIBLE if roll >= (1.0 - self.infection).powi(infected) => {
+                epidemic::INFECTED
+            }
+            epidemic::INFECTED if roll < self.recovery => epidemic::RECOVERED,
+            _ => state,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! How fast a mode steps along on its own while it's playing
+
+use instant::{Duration, Instant};
+
+/// Steps per second, and when the last one was
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Pace {
+    /// Steps per second
+    pub fps: u32,
+    #[serde(skip)]
+    last_step: Instant,
+}
+
+impl Default for Pace {
+    fn default() -> Self {
+        Self::new(20)
+    }
+}
+
+impl Pace {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            fps,
+            last_step: Instant::now(),
+        }
+    }
+
+    /// Whether it's been long enough since the last step for another one, counting it as taken
+    /// if so
+    pub fn due(&mut self) -> bool {
+        let interval = Duration::from_secs_f32(1.0 / self.fps.max(1) as f32);
+        let due = self.last_step.elapsed() >= interval;
+        if due {
+            self.last_step = Instant::now();
+        }
+        due
+    }
+}
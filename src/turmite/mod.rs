@@ -0,0 +1,199 @@
+//! Langton's Ant and the turmites that generalize it
+//!
+//! Rather than every cell updating at once, one or more ants walk around the grid, each changing
+//! just the cell it's standing on every step. Simple as they are they do surprising things: the
+//! original `RL` ant makes a mess for about 10,000 steps and then suddenly starts building a
+//! "highway", a pattern that repeats every 104 steps and heads off diagonally forever. See
+//! [`rule`] for how they're written down.
+//!
+//! The grid wraps around at the edges, so highways eventually come back round and run into
+//! whatever was left behind.
+
+use crate::grid::Grid;
+use crate::turmite::rule::{Rule, RuleParseError, Turn};
+
+pub mod rule;
+
+pub const DEFAULT_SIZE: usize = 256;
+
+/// Which way an ant is facing
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Heading {
+    #[default]
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    /// Clockwise from north
+    pub const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::North => "North",
+            Self::East => "East",
+            Self::South => "South",
+            Self::West => "West",
+        }
+    }
+
+    pub fn turn(self, turn: Turn) -> Self {
+        Self::ALL[(self as usize + usize::from(turn.quarters())) % 4]
+    }
+
+    /// One step forward, with y going down the screen
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Self::North => (0, -1),
+            Self::East => (1, 0),
+            Self::South => (0, 1),
+            Self::West => (-1, 0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Ant {
+    pub x: usize,
+    pub y: usize,
+    pub heading: Heading,
+    /// Which row of the state table the ant is on, always 0 for plain ants
+    pub state: u8,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Turmites {
+    rule: Rule,
+    /// Color of every cell
+    grid: Grid<u8>,
+    /// Steps run every frame while playing
+    pub steps_per_frame: u32,
+    /// The rule as typed into the side panel, which may not be a valid one
+    #[serde(skip)]
+    pub rule_text: String,
+    /// What's wrong with `rule_text`
+    #[serde(skip)]
+    pub rule_error: Option<RuleParseError>,
+    #[serde(skip)]
+    ants: Vec<Ant>,
+    #[serde(skip)]
+    steps: u64,
+}
+
+impl Default for Turmites {
+    fn default() -> Self {
+        Self::new(Rule::default(), DEFAULT_SIZE, DEFAULT_SIZE)
+    }
+}
+
+impl Turmites {
+    /// A blank `width`×`height` grid with a single ant in the middle
+    pub fn new(rule: Rule, width: usize, height: usize) -> Self {
+        let mut turmites = Self {
+            rule_text: rule.to_string(),
+            rule,
+            grid: Grid::new(width, height),
+            steps_per_frame: 100,
+            rule_error: None,
+            ants: Vec::new(),
+            steps: 0,
+        };
+        turmites.restart();
+        turmites
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Steps taken since the last restart, every ant moves once a step
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Color of every cell
+    pub fn grid(&self) -> &Grid<u8> {
+        &self.grid
+    }
+
+    pub fn ants(&self) -> &[Ant] {
+        &self.ants
+    }
+
+    /// Wipe the grid and start over with a single ant in the middle, facing north
+    pub fn restart(&mut self) {
+        self.grid.clear();
+        self.ants = vec![Ant {
+            x: self.grid.width() / 2,
+            y: self.grid.height() / 2,
+            heading: Heading::North,
+            state: 0,
+        }];
+        self.steps = 0;
+    }
+
+    /// Switch to a different rule and start over
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule_text = rule.to_string();
+        self.rule_error = None;
+        self.rule = rule;
+        self.restart();
+    }
+
+    /// Change the size of the grid and start over
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid = Grid::new(width.max(1), height.max(1));
+        self.restart();
+    }
+
+    /// Put another ant down at column `x` of row `y`, unless it's past the edges
+    pub fn add_ant(&mut self, x: usize, y: usize, heading: Heading) {
+        if self.grid.index(x, y).is_some() {
+            self.ants.push(Ant {
+                x,
+                y,
+                heading,
+                state: 0,
+            });
+        }
+    }
+
+    /// Pick up every ant on the cell at column `x` of row `y`
+    pub fn remove_ants_at(&mut self, x: usize, y: usize) {
+        self.ants.retain(|ant| (ant.x, ant.y) != (x, y));
+    }
+
+    /// Move every ant once, in the order they were put down
+    pub fn step(&mut self) {
+        let (width, height) = (self.grid.width() as isize, self.grid.height() as isize);
+        let cells = self.grid.cells_mut();
+        for ant in &mut self.ants {
+            let cell = &mut cells[ant.y * width as usize + ant.x];
+            let transition = self.rule.transition(ant.state, *cell);
+            *cell = transition.write;
+            ant.state = transition.next;
+            ant.heading = ant.heading.turn(transition.turn);
+            let (dx, dy) = ant.heading.offset();
+            ant.x = (ant.x as isize + dx).rem_euclid(width) as usize;
+            ant.y = (ant.y as isize + dy).rem_euclid(height) as usize;
+        }
+        self.steps += 1;
+    }
+
+    /// Take `steps` steps in one go
+    pub fn run(&mut self, steps: u64) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Take this frame's steps
+    pub fn update(&mut self) {
+        self.run(u64::from(self.steps_per_frame));
+    }
+}
@@ -0,0 +1,340 @@
+//! Turmite rules, written either as a string of turns or as a full state table
+//!
+//! A turmite looks at the color of the cell it's on and, depending on which state it's in, paints
+//! the cell a new color, turns, and goes into a new state before stepping forward. The table is
+//! written in Ed Pegg's notation, the one Golly and the Wikipedia article use: one `{color, turn,
+//! state}` triple for every color in every state, so Langton's Ant is `{{{1, 2, 0}, {0, 8, 0}}}`.
+//! Turns are 1 for none, 2 for right, 4 to turn around and 8 for left.
+//!
+//! Ants are turmites with just the one state that always paint the next color along, so all they
+//! need is the turn for each color: `RL` is Langton's Ant again. Letters are `L` and `R`, `N` to
+//! go straight on and `U` to turn around.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Most colors (and most states) a turmite can have, every cell is a `u8`
+pub const MAX_COLORS: usize = 255;
+
+/// Which way to face after painting a cell
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Turn {
+    #[default]
+    None,
+    Right,
+    Around,
+    Left,
+}
+
+impl Turn {
+    pub const ALL: [Self; 4] = [Self::None, Self::Right, Self::Around, Self::Left];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "Straight on",
+            Self::Right => "Right",
+            Self::Around => "U-turn",
+            Self::Left => "Left",
+        }
+    }
+
+    /// Letter for the turn in an ant's rule string
+    pub fn letter(self) -> char {
+        match self {
+            Self::None => 'N',
+            Self::Right => 'R',
+            Self::Around => 'U',
+            Self::Left => 'L',
+        }
+    }
+
+    /// Number for the turn in Ed Pegg's notation
+    pub fn code(self) -> u32 {
+        match self {
+            Self::None => 1,
+            Self::Right => 2,
+            Self::Around => 4,
+            Self::Left => 8,
+        }
+    }
+
+    /// Quarter turns clockwise
+    pub fn quarters(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Right => 1,
+            Self::Around => 2,
+            Self::Left => 3,
+        }
+    }
+}
+
+/// What a turmite does in one state on one color
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Transition {
+    /// Color the cell gets painted
+    pub write: u8,
+    pub turn: Turn,
+    /// State the turmite goes into
+    pub next: u8,
+}
+
+/// Why a rule couldn't be read, shown to the user as-is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleParseError {
+    /// Nothing was typed in
+    Empty,
+    /// A letter in an ant's rule string that isn't a turn
+    UnknownTurn(char),
+    /// An ant needs at least two colors to get anywhere
+    TooFewColors,
+    /// More colors or states than fit in a cell
+    TooMany(usize),
+    /// Something in a state table that isn't a number, comma or brace
+    UnexpectedChar(char),
+    /// Braces that don't open and close in three levels
+    Unbalanced,
+    /// A `{..}` in the table that doesn't have exactly three numbers
+    NotATriple(usize),
+    /// Some states have more colors than others
+    Ragged,
+    /// A turn number that isn't 1, 2, 4 or 8
+    BadTurn(u32),
+    /// A triple paints a color that isn't in the table
+    NoSuchColor(u32),
+    /// A triple goes into a state that isn't in the table
+    NoSuchState(u32),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "rule is empty"),
+            Self::UnknownTurn(ch) => write!(f, "'{ch}' isn't a turn, use L, R, N or U"),
+            Self::TooFewColors => write!(f, "an ant needs at least two colors"),
+            Self::TooMany(n) => write!(f, "{n} is too many, at most {MAX_COLORS} fit"),
+            Self::UnexpectedChar(ch) => write!(f, "unexpected character '{ch}' in the table"),
+            Self::Unbalanced => write!(f, "braces don't match up, the table is {{{{{{..}}}}}}"),
+            Self::NotATriple(n) => write!(f, "expected {{color, turn, state}}, got {n} numbers"),
+            Self::Ragged => write!(f, "every state needs the same number of colors"),
+            Self::BadTurn(turn) => write!(f, "turn {turn} should be 1, 2, 4 or 8"),
+            Self::NoSuchColor(color) => write!(f, "color {color} isn't in the table"),
+            Self::NoSuchState(state) => write!(f, "state {state} isn't in the table"),
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+/// A turmite's state table
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rule {
+    states: u8,
+    colors: u8,
+    /// `table[state * colors + color]`
+    table: Vec<Transition>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::ant(&[Turn::Right, Turn::Left]).expect("Langton's Ant is a valid rule")
+    }
+}
+
+impl Rule {
+    /// The ant that turns `turns[c]` on color `c`, then paints it the next color along
+    pub fn ant(turns: &[Turn]) -> Result<Self, RuleParseError> {
+        if turns.len() < 2 {
+            return Err(RuleParseError::TooFewColors);
+        }
+        if turns.len() > MAX_COLORS {
+            return Err(RuleParseError::TooMany(turns.len()));
+        }
+        let colors = turns.len();
+        let table = turns
+            .iter()
+            .enumerate()
+            .map(|(color, &turn)| Transition {
+                write: ((color + 1) % colors) as u8,
+                turn,
+                next: 0,
+            })
+            .collect();
+        Ok(Self {
+            states: 1,
+            colors: colors as u8,
+            table,
+        })
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn colors(&self) -> u8 {
+        self.colors
+    }
+
+    /// What a turmite in `state` does on a cell of `color`
+    pub fn transition(&self, state: u8, color: u8) -> Transition {
+        self.table[usize::from(state) * usize::from(self.colors) + usize::from(color)]
+    }
+
+    /// The turns of an ant, `None` for turmites that need the whole table
+    pub fn turns(&self) -> Option<Vec<Turn>> {
+        let colors = usize::from(self.colors);
+        let is_ant = self.states == 1
+            && self
+                .table
+                .iter()
+                .enumerate()
+                .all(|(color, t)| usize::from(t.write) == (color + 1) % colors && t.next == 0);
+        is_ant.then(|| self.table.iter().map(|t| t.turn).collect())
+    }
+
+    /// Read a table in Ed Pegg's notation
+    fn parse_table(src: &str) -> Result<Self, RuleParseError> {
+        let mut states: Vec<Vec<[u32; 3]>> = Vec::new();
+        let mut state: Vec<[u32; 3]> = Vec::new();
+        let mut triple: Vec<u32> = Vec::new();
+        let mut number: Option<u32> = None;
+        let mut depth = 0;
+        let mut closed = false;
+        for ch in src.chars() {
+            if closed && !ch.is_whitespace() {
+                return Err(RuleParseError::Unbalanced);
+            }
+            if let Some(digit) = ch.to_digit(10) {
+                if depth != 3 {
+                    return Err(RuleParseError::Unbalanced);
+                }
+                number = Some(number.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                continue;
+            }
+            triple.extend(number.take());
+            match ch {
+                '{' if depth < 3 => depth += 1,
+                '}' => {
+                    match depth {
+                        3 => match <[u32; 3]>::try_from(triple.as_slice()) {
+                            Ok(t) => state.push(t),
+                            Err(_) => return Err(RuleParseError::NotATriple(triple.len())),
+                        },
+                        2 => states.push(std::mem::take(&mut state)),
+                        1 => closed = true,
+                        _ => return Err(RuleParseError::Unbalanced),
+                    }
+                    triple.clear();
+                    depth -= 1;
+                }
+                '{' => return Err(RuleParseError::Unbalanced),
+                ',' => {}
+                ch if ch.is_whitespace() => {}
+                ch => return Err(RuleParseError::UnexpectedChar(ch)),
+            }
+        }
+        if !closed {
+            return Err(RuleParseError::Unbalanced);
+        }
+
+        let colors = states.first().map_or(0, Vec::len);
+        if states.iter().any(|s| s.len() != colors) {
+            return Err(RuleParseError::Ragged);
+        }
+        if colors > MAX_COLORS || states.len() > MAX_COLORS {
+            return Err(RuleParseError::TooMany(colors.max(states.len())));
+        }
+        if colors == 0 {
+            return Err(RuleParseError::Empty);
+        }
+        let mut table = Vec::with_capacity(states.len() * colors);
+        for [write, turn, next] in states.iter().flatten().copied() {
+            let turn = Turn::ALL
+                .into_iter()
+                .find(|t| t.code() == turn)
+                .ok_or(RuleParseError::BadTurn(turn))?;
+            if write as usize >= colors {
+                return Err(RuleParseError::NoSuchColor(write));
+            }
+            if next as usize >= states.len() {
+                return Err(RuleParseError::NoSuchState(next));
+            }
+            table.push(Transition {
+                write: write as u8,
+                turn,
+                next: next as u8,
+            });
+        }
+        Ok(Self {
+            states: states.len() as u8,
+            colors: colors as u8,
+            table,
+        })
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    /// An ant's turns like `RLR`, or a whole table like `{{{1, 2, 0}, {0, 8, 0}}}`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+        if s.starts_with('{') {
+            return Self::parse_table(s);
+        }
+        let turns = s
+            .chars()
+            .map(|ch| match ch.to_ascii_uppercase() {
+                'L' => Ok(Turn::Left),
+                'R' => Ok(Turn::Right),
+                'N' => Ok(Turn::None),
+                'U' => Ok(Turn::Around),
+                _ => Err(RuleParseError::UnknownTurn(ch)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::ant(&turns)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(turns) = self.turns() {
+            return turns.iter().try_for_each(|t| write!(f, "{}", t.letter()));
+        }
+        write!(f, "{{")?;
+        for (state, transitions) in self.table.chunks(usize::from(self.colors)).enumerate() {
+            if state > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{{")?;
+            for (color, t) in transitions.iter().enumerate() {
+                if color > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{{{}, {}, {}}}", t.write, t.turn.code(), t.next)?;
+            }
+            write!(f, "}}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = RuleParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
@@ -0,0 +1,229 @@
+//! Wireworld, Brian Silverman's cellular automaton for building digital circuits
+//!
+//! Every cell is one of four things. Empty cells stay empty and conductors are the wires. Signals
+//! are electrons made of a head and a tail, and they move along the wires like this:
+//! * a head always turns into a tail
+//! * a tail always turns back into conductor
+//! * a conductor turns into a head if exactly one or two of its eight neighbors are heads
+//!
+//! The tail behind every head keeps electrons moving one way, which is what makes diodes, gates
+//! and clocks possible.
+//!
+//! Cells are numbered the way Golly and Mirek's Cellebration number them, so patterns made for
+//! either load as they are.
+
+use crate::Pos;
+use crate::grid::{self, Grid};
+use crate::pattern::Pattern;
+use crate::timing::Pace;
+
+/// What Golly and Mirek's Cellebration call the rule, in pattern files
+pub const RULE_NAME: &str = "WireWorld";
+
+/// Empty cells kept around a loaded pattern, so it isn't jammed against the edges
+const PATTERN_MARGIN: usize = 10;
+
+pub const DEFAULT_WIDTH: usize = 120;
+pub const DEFAULT_HEIGHT: usize = 80;
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Default,
+)]
+pub enum Cell {
+    #[default]
+    Empty = 0,
+    /// Front of an electron
+    Head = 1,
+    /// Back of an electron, where it has just been
+    Tail = 2,
+    /// Wire for electrons to run along
+    Conductor = 3,
+}
+
+impl Cell {
+    pub const ALL: [Self; 4] = [Self::Empty, Self::Conductor, Self::Head, Self::Tail];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::Head => "Electron head",
+            Self::Tail => "Electron tail",
+            Self::Conductor => "Conductor",
+        }
+    }
+
+    /// The cell stored as `state` in a pattern file, if it's one of the four
+    pub fn from_state(state: u8) -> Option<Self> {
+        match state {
+            0 => Some(Self::Empty),
+            1 => Some(Self::Head),
+            2 => Some(Self::Tail),
+            3 => Some(Self::Conductor),
+            _ => None,
+        }
+    }
+
+    /// What the cell becomes next generation, given how many of its neighbors are heads
+    pub fn next(self, heads: usize) -> Self {
+        match self {
+            Self::Head => Self::Tail,
+            Self::Tail => Self::Conductor,
+            Self::Conductor if matches!(heads, 1 | 2) => Self::Head,
+            Self::Empty | Self::Conductor => self,
+        }
+    }
+}
+
+/// Whether a rule named in a pattern file means Wireworld
+pub fn is_wireworld_rule(rule: &str) -> bool {
+    rule.trim().eq_ignore_ascii_case(RULE_NAME)
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WireWorld {
+    /// Every cell, saved along with the app so circuits aren't lost
+    #[serde(with = "grid::with_cells")]
+    grid: Grid<Cell>,
+    /// Whether the edges wrap around to the other side, rather than being empty
+    pub wrap: bool,
+    /// Generations per second
+    pub pace: Pace,
+    /// What gets drawn with the left mouse button
+    pub paint: Cell,
+    #[serde(skip)]
+    generation: u64,
+}
+
+impl Default for WireWorld {
+    fn default() -> Self {
+        Self::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+}
+
+impl WireWorld {
+    /// An empty `width`×`height` board
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            grid: Grid::new(width, height),
+            wrap: false,
+            pace: Pace::new(10),
+            paint: Cell::Conductor,
+            generation: 0,
+        }
+    }
+
+    /// Generations run since the board was last cleared or loaded
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Every cell on the board
+    pub fn grid(&self) -> &Grid<Cell> {
+        &self.grid
+    }
+
+    /// Put `cell` at column `x` of row `y`, anything past the edges is ignored
+    pub fn set_cell(&mut self, x: usize, y: usize, cell: Cell) {
+        self.grid.set(x, y, cell);
+    }
+
+    /// Change the size of the board, keeping whatever still fits from the top left corner
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid.resize(width, height);
+    }
+
+    /// Empty every cell
+    pub fn clear(&mut self) {
+        self.grid.clear();
+        self.generation = 0;
+    }
+
+    /// Turn every electron back into plain wire, leaving the circuit itself alone
+    pub fn strip_electrons(&mut self) {
+        for cell in self.grid.cells_mut() {
+            if matches!(cell, Cell::Head | Cell::Tail) {
+                *cell = Cell::Conductor;
+            }
+        }
+    }
+
+    /// Electron heads in the eight cells around column `x` of row `y`
+    fn heads_around(&self, x: usize, y: usize) -> usize {
+        let (width, height) = (self.grid.width() as isize, self.grid.height() as isize);
+        let mut heads = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let (mut nx, mut ny) = (x as isize + dx, y as isize + dy);
+                if self.wrap {
+                    (nx, ny) = (nx.rem_euclid(width), ny.rem_euclid(height));
+                }
+                if (0..width).contains(&nx)
+                    && (0..height).contains(&ny)
+                    && self.grid.get(nx as usize, ny as usize) == Cell::Head
+                {
+                    heads += 1;
+                }
+            }
+        }
+        heads
+    }
+
+    /// Work out the next generation
+    pub fn step(&mut self) {
+        let (width, height) = self.grid.size();
+        let next = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| match self.grid.get(x, y) {
+                // Only wires need their neighbors counted
+                Cell::Conductor => Cell::Conductor.next(self.heads_around(x, y)),
+                cell => cell.next(0),
+            })
+            .collect();
+        self.grid.replace(next);
+        self.generation += 1;
+    }
+
+    /// Clear the board and put `pattern` in the middle of it, growing the board if it doesn't fit.
+    /// Cells in states past the four Wireworld has are left empty
+    pub fn load_pattern(&mut self, pattern: &Pattern) {
+        let width = self
+            .grid
+            .width()
+            .max(pattern.width.max(0) as usize + 2 * PATTERN_MARGIN);
+        let height = self
+            .grid
+            .height()
+            .max(pattern.height.max(0) as usize + 2 * PATTERN_MARGIN);
+        self.resize(width, height);
+        self.clear();
+        let dx = (self.grid.width() - pattern.width.max(0) as usize) / 2;
+        let dy = (self.grid.height() - pattern.height.max(0) as usize) / 2;
+        for &(p, state) in &pattern.cells {
+            if let Some(cell) = Cell::from_state(state) {
+                self.set_cell(p.0 as usize + dx, p.1 as usize + dy, cell);
+            }
+        }
+    }
+
+    /// Everything on the board as a pattern, under the Wireworld rule
+    pub fn to_pattern(&self) -> Pattern {
+        let width = self.grid.width();
+        let cells = self
+            .grid
+            .cells()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell != Cell::Empty)
+            .map(|(i, &cell)| {
+                let p = Pos((i % width) as i32, (i / width) as i32);
+                (p, cell as u8)
+            });
+        let mut pattern = Pattern::from_cells(cells);
+        pattern.rule = Some(RULE_NAME.to_owned());
+        pattern
+    }
+}
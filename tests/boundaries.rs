@@ -0,0 +1,121 @@
+//! Where cells past the edge of the map end up under each topology
+
+use std::collections::HashSet;
+
+use cellular_automata::Pos;
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::{Map, ResizeAnchor};
+
+const WIDTH: i32 = 10;
+const HEIGHT: i32 = 6;
+
+fn wrap(boundary: Boundary, x: i32, y: i32) -> Option<(i32, i32)> {
+    boundary
+        .wrap(Pos(x, y), WIDTH, HEIGHT)
+        .map(|Pos(x, y)| (x, y))
+}
+
+#[test]
+fn cells_on_the_map_stay_put() {
+    for boundary in Boundary::ALL {
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                assert_eq!(wrap(boundary, x, y), Some((x, y)), "{boundary:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn each_edge_is_glued_its_own_way() {
+    use Boundary::{CrossSurface, Dead, KleinBottle, Mirror, Plane, Torus};
+    // Just past the right edge, then just past the bottom edge
+    for (boundary, right, below) in [
+        (Dead, None, None),
+        (Torus, Some((0, 2)), Some((3, 0))),
+        // Only crossing the top or bottom twists
+        (KleinBottle, Some((0, 2)), Some((6, 0))),
+        (CrossSurface, Some((0, 3)), Some((6, 0))),
+        (Mirror, Some((9, 2)), Some((3, 5))),
+        (Plane, Some((10, 2)), Some((3, 6))),
+    ] {
+        assert_eq!(wrap(boundary, WIDTH, 2), right, "{boundary:?} right");
+        assert_eq!(wrap(boundary, 3, HEIGHT), below, "{boundary:?} below");
+    }
+    // Crossing the top of a Klein bottle twice untwists it
+    assert_eq!(wrap(KleinBottle, 3, 2 * HEIGHT + 1), Some((3, 1)));
+    assert_eq!(wrap(KleinBottle, -1, -1), Some((0, 5)));
+    assert_eq!(wrap(CrossSurface, -1, -1), Some((0, 0)));
+    assert_eq!(wrap(Mirror, -1, -1), Some((0, 0)));
+}
+
+#[test]
+fn coordinates_far_out_of_range_still_wrap() {
+    // Wrapping used to only handle cells one step past an edge
+    assert_eq!(
+        wrap(Boundary::Torus, 3 * WIDTH + 4, -5 * HEIGHT - 1),
+        Some((4, 5))
+    );
+    assert_eq!(wrap(Boundary::Torus, -1_000_001, 1_000_000), Some((9, 4)));
+    assert_eq!(wrap(Boundary::Mirror, 3 * WIDTH + 4, 2), Some((5, 2)));
+    assert_eq!(wrap(Boundary::Dead, -WIDTH, 0), None);
+    for boundary in Boundary::ALL
+        .into_iter()
+        .filter(|boundary| boundary.is_wrapping())
+    {
+        for (x, y) in [(-57, 93), (i32::MAX, i32::MIN), (1234, -4321)] {
+            let (x, y) = wrap(boundary, x, y).expect("wrapping edges never drop cells");
+            assert!(
+                (0..WIDTH).contains(&x) && (0..HEIGHT).contains(&y),
+                "{boundary:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn tiles_show_what_wrapping_sees() {
+    for boundary in Boundary::ALL
+        .into_iter()
+        .filter(|boundary| boundary.is_wrapping())
+    {
+        for tile in [(-2, -1), (-1, 0), (0, 0), (1, 0), (0, 1), (1, 1), (3, -3)] {
+            for (x, y) in [(0, 0), (2, 5), (9, 1), (7, 4)] {
+                let image = boundary.tile_image(Pos(x, y), tile, WIDTH, HEIGHT);
+                assert_eq!(
+                    boundary.wrap(image, WIDTH, HEIGHT),
+                    Some(Pos(x, y)),
+                    "{boundary:?} tile {tile:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn maps_count_neighbors_across_the_edges() {
+    let mut map = Map::new();
+    map.resize(WIDTH, HEIGHT, ResizeAnchor::TopLeft);
+    map.set_cell_alive(Pos(WIDTH - 1, HEIGHT - 1));
+    for (boundary, neighbors) in [
+        (Boundary::Dead, 0),
+        (Boundary::Torus, 1),
+        // Its image across the top edge is on the other side, right above the corner
+        (Boundary::KleinBottle, 1),
+        (Boundary::Plane, 0),
+    ] {
+        map.boundary = boundary;
+        assert_eq!(map.neighbors(&Pos(0, 0)), neighbors, "{boundary:?}");
+    }
+
+    // A blinker split across the left and right edges of a torus keeps blinking
+    map.boundary = Boundary::Torus;
+    map.clear();
+    map.max_speed = true;
+    for x in [WIDTH - 1, 0, 1] {
+        map.set_cell_alive(Pos(x, 2));
+    }
+    map.update();
+    let column: HashSet<Pos> = [1, 2, 3].into_iter().map(|y| Pos(0, y)).collect();
+    assert_eq!(*map.cells(), column);
+}
@@ -0,0 +1,149 @@
+//! Cyclic cellular automata and Rock-Paper-Scissors, and the multi-state soups they start from
+
+use cellular_automata::conway::conway_map::Map;
+use cellular_automata::cyclic::{Cyclic, Kind, PRESETS};
+use cellular_automata::grid::Neighborhood;
+use cellular_automata::soup::soup;
+use rand::SeedableRng as _;
+use rand::rngs::StdRng;
+
+fn preset(name: &str) -> &'static cellular_automata::cyclic::Preset {
+    PRESETS
+        .iter()
+        .find(|p| p.name == name)
+        .expect("the preset exists")
+}
+
+#[test]
+fn neighborhoods_reach_out_to_the_range() {
+    assert_eq!(Neighborhood::Moore.offsets(1).len(), 8);
+    assert_eq!(Neighborhood::Moore.offsets(2).len(), 24);
+    assert_eq!(Neighborhood::VonNeumann.offsets(1).len(), 4);
+    assert_eq!(Neighborhood::VonNeumann.offsets(3).len(), 24);
+    assert!(!Neighborhood::Moore.offsets(3).contains(&(0, 0)));
+}
+
+#[test]
+fn cells_move_on_once_enough_neighbors_are_ahead() {
+    let mut ca = Cyclic::new(8, 8);
+    ca.load_preset(preset("313"));
+    // Three cells in state 1 round (3, 3), which is still in state 0
+    for x in 2..5 {
+        ca.set_cell(x, 2, 1);
+    }
+    ca.step();
+    assert_eq!(ca.grid().get(3, 3), 1, "three neighbors ahead is enough");
+    assert_eq!(ca.grid().get(2, 3), 0, "two isn't");
+    // The 1s have nothing in state 2 round them, so they stay put
+    assert_eq!(ca.grid().get(3, 2), 1);
+    // The edges wrap round
+    assert_eq!(ca.grid().get(3, 1), 1);
+    assert_eq!(ca.grid().get(3, 7), 0);
+
+    // The last state is eaten by the first
+    let mut ca = Cyclic::new(8, 8);
+    ca.load_preset(preset("Griffeath's CCA"));
+    ca.set_cell(4, 4, 13);
+    ca.step();
+    assert_eq!(ca.grid().get(4, 4), 0);
+    assert_eq!(ca.generation(), 1);
+
+    // States wrap round when there are fewer of them
+    ca.set_states(5);
+    ca.set_cell(1, 1, 7);
+    assert_eq!(ca.grid().get(1, 1), 2);
+}
+
+#[test]
+fn soups_fill_every_state_evenly() {
+    let mut rng = StdRng::seed_from_u64(22);
+    let cells = soup(100, 100, 1.0, 0..=3, &mut rng);
+    assert_eq!(cells.len(), 10_000);
+    for state in 0..=3 {
+        let count = cells.iter().filter(|&&s| s == state).count();
+        assert!(
+            (2300..2700).contains(&count),
+            "{count} cells in state {state}"
+        );
+    }
+    let sparse = soup(100, 100, 0.25, 1..=1, &mut rng);
+    let alive = sparse.iter().filter(|&&s| s == 1).count();
+    assert!((2200..2800).contains(&alive), "{alive} cells alive");
+
+    // Rules with more states get soups in all of them
+    let mut map = Map::new();
+    map.rule = "B2/S/C5".parse().expect("test rules are valid");
+    map.soup.density = 50.0;
+    map.gen_random();
+    let states: Vec<u8> = map.cell_states().map(|(_, state)| state).collect();
+    assert!(!states.is_empty());
+    for state in 1..5 {
+        assert!(states.contains(&state), "no cells in state {state}");
+    }
+}
+
+#[test]
+fn soups_turn_into_spirals_that_keep_turning() {
+    let mut ca = Cyclic::new(64, 64);
+    ca.load_preset(preset("Griffeath's CCA"));
+    ca.soup.seed = "22".to_owned();
+    ca.randomize();
+    for _ in 0..400 {
+        ca.step();
+    }
+    // Once the spirals take over every cell keeps moving on, a state at a time
+    let before = ca.grid().cells().to_vec();
+    ca.step();
+    let moved = before
+        .iter()
+        .zip(ca.grid().cells())
+        .filter(|&(a, b)| a != b)
+        .count();
+    assert!(moved > 64 * 64 / 20, "only {moved} cells changed");
+    assert!(
+        before
+            .iter()
+            .zip(ca.grid().cells())
+            .all(|(a, b)| a == b || (a + 1) % 14 == *b)
+    );
+}
+
+#[test]
+fn rock_paper_scissors_runs_the_same_from_the_same_seed() {
+    let run = |seed: &str| {
+        let mut ca = Cyclic::new(48, 48);
+        ca.load_preset(preset("Rock-Paper-Scissors"));
+        assert_eq!(ca.kind, Kind::RockPaperScissors);
+        ca.soup.seed = seed.to_owned();
+        ca.randomize();
+        for _ in 0..30 {
+            ca.step();
+        }
+        ca.grid().cells().to_vec()
+    };
+    assert_eq!(run("1"), run("1"));
+    assert_ne!(run("1"), run("2"));
+}
+
+#[test]
+fn soups_come_from_the_seed_and_density() {
+    let mut ca = Cyclic::new(64, 64);
+    ca.load_preset(preset("Griffeath's CCA"));
+    ca.soup.seed = "spirals".to_owned();
+    ca.randomize();
+    let first = ca.grid().cells().to_vec();
+    ca.randomize();
+    assert_eq!(
+        ca.grid().cells(),
+        first,
+        "the same seed gives the same soup"
+    );
+
+    ca.soup.density = 20.0;
+    ca.randomize();
+    let empty = ca.grid().cells().iter().filter(|&&s| s == 0).count();
+    assert!(
+        (3200..3700).contains(&empty),
+        "{empty} cells in state 0, expected about 80% plus a 14th of the rest"
+    );
+}
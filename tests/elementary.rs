@@ -0,0 +1,208 @@
+//! One dimensional automata: rule numbers, truth tables, and the rows they grow
+
+use cellular_automata::elementary::rule::{CodeError, CodeKind, Rule};
+use cellular_automata::elementary::{Edges, Elementary, InitialRow};
+use rand::SeedableRng as _;
+use rand::rngs::StdRng;
+
+/// 3^243, one more than the biggest general 3 color, radius 2 rule
+const THREE_TO_THE_243: &str = "87189642485960958202911070585860771696964072404731750085525219437990967093723439943475549906831683116791055225665627";
+
+/// A row written out with `#` for live cells and `.` for blank ones
+fn row(cells: &str) -> Vec<u8> {
+    cells.chars().map(|ch| u8::from(ch == '#')).collect()
+}
+
+/// The first `rows.len()` generations of `rule` from a single cell in the middle
+fn grows_into(rule: Rule, rows: &[&str]) {
+    let name = rule.to_string();
+    let mut elementary = Elementary::new();
+    elementary.width = rows[0].len();
+    elementary.initial = InitialRow::SingleCell;
+    elementary.set_rule(rule);
+    elementary.restart();
+    for _ in 1..rows.len() {
+        elementary.step();
+    }
+    let expected: Vec<Vec<u8>> = rows.iter().map(|r| row(r)).collect();
+    assert_eq!(
+        elementary.rows().iter().cloned().collect::<Vec<_>>(),
+        expected,
+        "{name}"
+    );
+    assert_eq!(elementary.generation(), rows.len() as u64 - 1, "{name}");
+}
+
+#[test]
+fn rule_30_grows_its_chaotic_triangle() {
+    grows_into(
+        Rule::elementary(30),
+        &[
+            ".......#.......",
+            "......###......",
+            ".....##..#.....",
+            "....##.####....",
+            "...##..#...#...",
+            "..##.####.###..",
+            ".##..#....#..#.",
+        ],
+    );
+}
+
+#[test]
+fn rule_90_grows_sierpinskis_triangle() {
+    grows_into(
+        Rule::elementary(90),
+        &[
+            ".......#.......",
+            "......#.#......",
+            ".....#...#.....",
+            "....#.#.#.#....",
+            "...#.......#...",
+            "..#.#.....#.#..",
+            ".#...#...#...#.",
+            "#.#.#.#.#.#.#.#",
+        ],
+    );
+}
+
+#[test]
+fn the_truth_table_and_the_number_stay_in_sync() {
+    let mut rule = Rule::elementary(30);
+    // Entry 4 is the neighborhood 100, which rule 30 turns on
+    assert_eq!(rule.table(), [0, 1, 1, 1, 1, 0, 0, 0]);
+    assert_eq!(rule.neighborhood(4), [1, 0, 0]);
+    assert_eq!(rule.output([1, 0, 0].into_iter()), 1);
+    assert_eq!(rule.code(), "30");
+    assert_eq!(rule.to_string(), "Rule 30");
+
+    // Flipping a box changes the number, and the other way around
+    rule.set_output(7, 1);
+    assert_eq!(rule.code(), "158");
+    assert_eq!(rule, Rule::elementary(158));
+    for number in 0..=255 {
+        let rule = Rule::elementary(number);
+        let from_table = (rule.table().iter().enumerate())
+            .map(|(entry, &output)| u32::from(output) << entry)
+            .sum::<u32>();
+        assert_eq!(from_table, u32::from(number));
+        assert_eq!(rule.code(), number.to_string());
+    }
+}
+
+#[test]
+fn edges_either_wrap_or_stay_blank() {
+    let rule = Rule::elementary(90);
+    let start = row("#....");
+    assert_eq!(rule.next_row(&start, Edges::Wrap), row(".#..#"));
+    assert_eq!(rule.next_row(&start, Edges::Fixed), row(".#..."));
+}
+
+#[test]
+fn painting_starts_over_from_the_painted_row() {
+    let mut elementary = Elementary::new();
+    elementary.width = 5;
+    elementary.set_rule(Rule::elementary(90));
+    elementary.restart();
+    elementary.step();
+    // Painting picks up the first row showing, then changes it
+    elementary.cycle_painted(0);
+    assert_eq!(elementary.initial, InitialRow::Painted);
+    assert_eq!(elementary.rows().len(), 1);
+    assert_eq!(elementary.rows()[0], row("#.#.."));
+    elementary.cycle_painted(0);
+    assert_eq!(elementary.rows()[0], row("..#.."));
+}
+
+#[test]
+fn codes_read_back_the_way_theyre_written() {
+    // 777 is 1001210 in base 3, read from the lowest digit up
+    let rule = Rule::from_code(3, 1, CodeKind::Totalistic, "777").expect("valid code");
+    assert_eq!(rule.table(), [0, 1, 2, 1, 0, 0, 1]);
+    assert_eq!(rule.code(), "777");
+    assert_eq!(rule.to_string(), "Totalistic code 777, k = 3, r = 1");
+    assert_eq!(
+        rule.next_row(&[0, 0, 1, 0, 0], Edges::Fixed),
+        [0, 1, 1, 1, 0]
+    );
+    assert_eq!(
+        rule.next_row(&[0, 1, 1, 1, 0], Edges::Fixed),
+        [1, 2, 1, 2, 1]
+    );
+
+    // Codes far too big for any machine integer go through unchanged
+    let mut rng = StdRng::seed_from_u64(12);
+    for (states, radius, kind) in [
+        (3, 2, CodeKind::General),
+        (4, 1, CodeKind::General),
+        (2, 5, CodeKind::General),
+        (8, 5, CodeKind::Totalistic),
+        (5, 3, CodeKind::Totalistic),
+    ] {
+        let rule = Rule::random(states, radius, kind, &mut rng).expect("rules this size fit");
+        let read = Rule::from_code(states, radius, kind, &rule.code()).expect("valid code");
+        assert_eq!(read, rule, "{rule}");
+    }
+    let mut biggest = Rule::from_code(3, 2, CodeKind::General, "0").expect("valid code");
+    for entry in 0..biggest.table().len() {
+        biggest.set_output(entry, 2);
+    }
+    let code = biggest.code();
+    // All 2s is 3^243 - 1, which only differs from 3^243 in its last digit
+    assert_eq!(code[..115], THREE_TO_THE_243[..115]);
+    assert!(code.ends_with('6'), "{code}");
+    assert_eq!(Rule::from_code(3, 2, CodeKind::General, &code), Ok(biggest));
+}
+
+#[test]
+fn bad_codes_say_whats_wrong() {
+    for (states, radius, kind, code, error) in [
+        (2, 1, CodeKind::General, "  ", CodeError::Empty),
+        (
+            2,
+            1,
+            CodeKind::General,
+            "12x4",
+            CodeError::UnexpectedChar { ch: 'x', index: 2 },
+        ),
+        (
+            2,
+            1,
+            CodeKind::General,
+            "256",
+            CodeError::TooBig {
+                states: 2,
+                entries: 8,
+            },
+        ),
+        (
+            3,
+            2,
+            CodeKind::General,
+            THREE_TO_THE_243,
+            CodeError::TooBig {
+                states: 3,
+                entries: 243,
+            },
+        ),
+        (
+            8,
+            5,
+            CodeKind::General,
+            "0",
+            CodeError::TableTooBig {
+                entries: 8_589_934_592,
+            },
+        ),
+    ] {
+        assert_eq!(
+            Rule::from_code(states, radius, kind, code),
+            Err(error),
+            "{code:?}"
+        );
+    }
+    assert_eq!(
+        CodeError::UnexpectedChar { ch: 'x', index: 2 }.to_string(),
+        "unexpected character 'x' at position 3"
+    );
+}
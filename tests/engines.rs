@@ -0,0 +1,260 @@
+//! Every engine has to agree with the original sparse set algorithm, cell for cell
+
+use std::collections::{HashMap, HashSet};
+
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::Map;
+use cellular_automata::conway::engine::dense::DenseEngine;
+use cellular_automata::conway::engine::hashlife::HashLife;
+use cellular_automata::conway::engine::sparse::SparseEngine;
+use cellular_automata::conway::engine::{Engine, EngineKind, Universe};
+use cellular_automata::conway::rule::Rule;
+use cellular_automata::{Pos, RunStatistics};
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+/// A random soup filling a `width`×`height` map about a third of the way
+fn soup(seed: u64, width: i32, height: i32) -> HashSet<Pos> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| Pos(x, y)))
+        .filter(|_| rng.gen_bool(0.35))
+        .collect()
+}
+
+/// A glider sitting on the bottom right corner, so its first few generations cross both edges
+fn corner_glider(width: i32, height: i32) -> HashSet<Pos> {
+    [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        .into_iter()
+        .map(|(x, y)| Pos(width - 3 + x, height - 3 + y))
+        .collect()
+}
+
+/// An R-pentomino with a glider heading away from it, so there's both chaos and something
+/// travelling off into empty space
+fn r_pentomino_and_glider() -> HashSet<Pos> {
+    let r_pentomino = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+    let glider = [(31, 30), (32, 31), (30, 32), (31, 32), (32, 32)];
+    r_pentomino
+        .into_iter()
+        .chain(glider)
+        .map(|(x, y)| Pos(x, y))
+        .collect()
+}
+
+/// Run `engine` on `cells` for `generations` steps
+fn run(
+    engine: &mut dyn Engine,
+    cells: &HashSet<Pos>,
+    rule: &Rule,
+    boundary: Boundary,
+    (width, height): (i32, i32),
+    generations: usize,
+) -> HashSet<Pos> {
+    let mut cells = cells.clone();
+    for _ in 0..generations {
+        cells = engine.step(
+            &Universe {
+                cells: &cells,
+                dying: &HashMap::new(),
+                rule,
+                boundary,
+                width,
+                height,
+            },
+            0,
+        );
+    }
+    cells
+}
+
+#[test]
+fn multithreaded_dense_matches_single_threaded() {
+    let rule = Rule::conway();
+    let size = (300, 211);
+    for boundary in Boundary::ALL {
+        let cells = soup(1, size.0, size.1);
+        let single = run(
+            &mut DenseEngine { threads: 1 },
+            &cells,
+            &rule,
+            boundary,
+            size,
+            16,
+        );
+        for threads in [2, 3, 8, 64] {
+            let multi = run(
+                &mut DenseEngine { threads },
+                &cells,
+                &rule,
+                boundary,
+                size,
+                16,
+            );
+            assert_eq!(single, multi, "{threads} threads on {boundary:?} edges");
+        }
+    }
+}
+
+#[test]
+fn multithreaded_sparse_matches_single_threaded() {
+    let size = (90, 71);
+    // B0 rules look at the whole map rather than around the live cells
+    for rule in ["B3/S23", "B0/S8", "B2/S34H"] {
+        let rule: Rule = rule.parse().expect("test rules are valid");
+        for boundary in Boundary::ALL {
+            let step = |cells: &HashSet<Pos>, threads| {
+                let universe = Universe {
+                    cells,
+                    dying: &HashMap::new(),
+                    rule: &rule,
+                    boundary,
+                    width: size.0,
+                    height: size.1,
+                };
+                SparseEngine::generation_on(&universe, threads)
+            };
+            let mut single = soup(1, size.0, size.1);
+            let mut multi = single.clone();
+            for generation in 0..4 {
+                single = step(&single, 1);
+                for threads in [2, 3, 8] {
+                    assert_eq!(
+                        step(&multi, threads),
+                        single,
+                        "{rule} with {threads} threads on {boundary:?} edges, generation {generation}"
+                    );
+                }
+                multi = step(&multi, 64);
+                assert_eq!(
+                    multi, single,
+                    "{rule} with 64 threads on {boundary:?} edges"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn dense_matches_sparse() {
+    let rules = [
+        "B3/S23",
+        "B36/S23",
+        "B2/S",
+        "B3678/S34678",
+        "B1357/S1357",
+        "B0/S8",
+        "B2/S2V",
+        "B2/S34H",
+    ];
+    for rule in rules {
+        let rule: Rule = rule.parse().expect("preset rules are valid");
+        for boundary in Boundary::ALL {
+            let mut dense = DenseEngine::default();
+            if !dense.supports(&rule, boundary) {
+                continue;
+            }
+            for size in [(64, 64), (70, 45), (1, 9)] {
+                let cells = soup(2, size.0, size.1);
+                let sparse = run(&mut SparseEngine, &cells, &rule, boundary, size, 8);
+                let dense = run(&mut dense, &cells, &rule, boundary, size, 8);
+                assert_eq!(sparse, dense, "{rule} on {boundary:?} edges, {size:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn dense_matches_sparse_across_word_edges() {
+    // With the ghost border a row is `width + 2` bits, so these end just before, on, and just
+    // past the edge of a word
+    let widths = [61, 62, 63, 65, 127, 129, 190];
+    for rule in ["B3/S23", "B2/S", "B36/S125"] {
+        let rule: Rule = rule.parse().expect("test rules are valid");
+        for boundary in Boundary::ALL {
+            let mut dense = DenseEngine { threads: 1 };
+            if !dense.supports(&rule, boundary) {
+                continue;
+            }
+            for width in widths {
+                let size = (width, 13);
+                for cells in [soup(3, width, 13), corner_glider(width, 13)] {
+                    let sparse = run(&mut SparseEngine, &cells, &rule, boundary, size, 12);
+                    let dense = run(&mut dense, &cells, &rule, boundary, size, 12);
+                    assert_eq!(sparse, dense, "{rule} on {boundary:?} edges, {size:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn hashlife_matches_sparse_on_big_steps() {
+    let rule = Rule::conway();
+    let cells = r_pentomino_and_glider();
+    for step_log2 in 0..=7 {
+        let universe = Universe {
+            cells: &cells,
+            dying: &HashMap::new(),
+            rule: &rule,
+            boundary: Boundary::Plane,
+            width: 64,
+            height: 64,
+        };
+        let hashlife = HashLife::default().step(&universe, step_log2);
+        let sparse = run(
+            &mut SparseEngine,
+            &cells,
+            &rule,
+            Boundary::Plane,
+            (64, 64),
+            1 << step_log2,
+        );
+        assert_eq!(hashlife, sparse, "2^{step_log2} generations");
+    }
+}
+
+/// A map on the plane running `engine`, starting out with `cells`
+fn map(engine: EngineKind, step_log2: u32, cells: &HashSet<Pos>) -> Map {
+    let mut map = Map::new();
+    map.boundary = Boundary::Plane;
+    map.engine = engine;
+    map.step_log2 = step_log2;
+    map.max_speed = true;
+    map.set_cells(cells.clone(), HashMap::new());
+    map
+}
+
+#[test]
+fn hashlife_maps_keep_their_tree_between_updates() {
+    let cells = r_pentomino_and_glider();
+    for step_log2 in [0, 3, 7] {
+        let mut sparse = map(EngineKind::Sparse, 0, &cells);
+        let mut hashlife = map(EngineKind::Hashlife, step_log2, &cells);
+        for update in 0..6 {
+            let before = sparse.cells().clone();
+            for _ in 0..1 << step_log2 {
+                sparse.update();
+            }
+            // Statistics come straight from the tree, without the cells being flattened out
+            assert!(hashlife.update_deferred());
+            let stats = RunStatistics::of_step(&before, sparse.cells(), sparse.stats().generations);
+            assert_eq!(*hashlife.stats(), stats, "2^{step_log2}, update {update}");
+
+            hashlife.catch_up();
+            assert_eq!(
+                hashlife.cells(),
+                sparse.cells(),
+                "2^{step_log2}, update {update}"
+            );
+            // Edits in between updates are picked up rather than run over
+            if update == 2 {
+                for map in [&mut sparse, &mut hashlife] {
+                    map.toggle_cell(Pos(-20, 5), 1);
+                    map.toggle_cell(Pos(-20, 6), 1);
+                    map.toggle_cell(Pos(-20, 7), 1);
+                }
+            }
+        }
+    }
+}
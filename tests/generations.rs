@@ -0,0 +1,168 @@
+//! Generations rules, where cells that die fade out through refractory states first
+
+use std::collections::{HashMap, HashSet};
+
+use cellular_automata::Pos;
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::Map;
+use cellular_automata::conway::engine::sparse::SparseEngine;
+use cellular_automata::conway::engine::{EngineKind, Universe};
+use cellular_automata::conway::rule::Rule;
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+fn rule(rule: &str) -> Rule {
+    rule.parse().expect("test rules are valid")
+}
+
+fn cells(cells: &[(i32, i32)]) -> HashSet<Pos> {
+    cells.iter().map(|&(x, y)| Pos(x, y)).collect()
+}
+
+/// One generation of `rule` on an unbounded plane
+fn step(
+    rule: &Rule,
+    cells: &HashSet<Pos>,
+    dying: &HashMap<Pos, u8>,
+) -> (HashSet<Pos>, HashMap<Pos, u8>) {
+    let universe = Universe {
+        cells,
+        dying,
+        rule,
+        boundary: Boundary::Plane,
+        width: 32,
+        height: 32,
+    };
+    SparseEngine::generations(&universe, 0)
+}
+
+/// A random soup of live cells
+fn soup(seed: u64) -> HashSet<Pos> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..24)
+        .flat_map(|y| (0..24).map(move |x| Pos(x, y)))
+        .filter(|_| rng.gen_bool(0.4))
+        .collect()
+}
+
+#[test]
+fn the_state_count_prints_back_as_c() {
+    for (rulestring, printed, states) in [
+        ("B2/S/C3", "B2/S/C3", 3),
+        ("/2/3", "B2/S/C3", 3),
+        ("345/2/4", "B2/S345/C4", 4),
+        ("B34678/S234/C24", "B34678/S234/C24", 24),
+        ("B2/S/C3H", "B2/S/C3H", 3),
+    ] {
+        let parsed = rule(rulestring);
+        assert_eq!(parsed.to_string(), printed, "{rulestring}");
+        assert_eq!(rule(printed), parsed, "{rulestring}");
+        assert_eq!(parsed.states(), states, "{rulestring}");
+        assert!(parsed.is_generations(), "{rulestring}");
+    }
+    assert!(!rule("B3/S23").is_generations());
+}
+
+#[test]
+fn brians_brain_never_keeps_a_cell_alive() {
+    let brain = rule("B2/S/C3");
+    // A domino sprouts one on each side, and leaves itself behind as refractory cells
+    let (alive, dying) = step(&brain, &cells(&[(0, 0), (1, 0)]), &HashMap::new());
+    assert_eq!(alive, cells(&[(0, -1), (1, -1), (0, 1), (1, 1)]));
+    assert_eq!(dying, HashMap::from([(Pos(0, 0), 2), (Pos(1, 0), 2)]));
+    // Refractory cells are gone after a generation, and can't be born on it
+    let (alive, dying) = step(&brain, &alive, &dying);
+    assert_eq!(
+        alive,
+        cells(&[(0, -2), (1, -2), (0, 2), (1, 2), (-1, 0), (2, 0)])
+    );
+    assert_eq!(
+        dying,
+        HashMap::from([
+            (Pos(0, -1), 2),
+            (Pos(1, -1), 2),
+            (Pos(0, 1), 2),
+            (Pos(1, 1), 2)
+        ])
+    );
+
+    let (mut alive, mut dying) = (soup(1), HashMap::new());
+    for generation in 0..20 {
+        let (next, next_dying) = step(&brain, &alive, &dying);
+        assert!(next.is_disjoint(&alive), "generation {generation}");
+        assert_eq!(
+            next_dying.keys().copied().collect::<HashSet<_>>(),
+            alive,
+            "generation {generation}"
+        );
+        (alive, dying) = (next, next_dying);
+    }
+}
+
+#[test]
+fn star_wars_cells_fade_through_every_state() {
+    let star_wars = rule("B2/S345/C4");
+    // Every cell of a block has three neighbors, so it survives, and each side has a pair to be
+    // born next to
+    let block = cells(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+    let (alive, dying) = step(&star_wars, &block, &HashMap::new());
+    let sides = cells(&[
+        (-1, 0),
+        (-1, 1),
+        (2, 0),
+        (2, 1),
+        (0, -1),
+        (1, -1),
+        (0, 2),
+        (1, 2),
+    ]);
+    assert_eq!(alive, &block | &sides);
+    assert!(dying.is_empty());
+
+    let (mut alive, mut dying) = (soup(2), HashMap::new());
+    for generation in 0..20 {
+        let (next, next_dying) = step(&star_wars, &alive, &dying);
+        // Cells that just died start out in state 2, and the others move one state along
+        let faded = |(p, &state): (&Pos, &u8)| {
+            let before = if alive.contains(p) { 1 } else { dying[p] };
+            state == before + 1
+        };
+        assert!(next_dying.iter().all(faded), "generation {generation}");
+        // Cells in their last state are gone next time, and nothing refractory comes back to life
+        assert!(
+            dying
+                .iter()
+                .all(|(p, &state)| state != 3 || !next_dying.contains_key(p))
+        );
+        assert!(dying.keys().all(|p| !next.contains(p)));
+        (alive, dying) = (next, next_dying);
+    }
+}
+
+#[test]
+fn every_engine_choice_runs_generations_on_the_sparse_set() {
+    let mut expected = None;
+    for engine in EngineKind::ALL {
+        let mut map = Map::new();
+        map.boundary = Boundary::Plane;
+        map.max_speed = true;
+        map.rule = rule("B2/S345/C4");
+        map.engine = engine;
+        // Only the sparse set admits to running it, so the UI can say the choice is ignored
+        assert_eq!(
+            map.engine_supported(),
+            engine == EngineKind::Sparse,
+            "{engine:?}"
+        );
+        map.set_cells(soup(3), HashMap::new());
+        for _ in 0..5 {
+            map.update();
+        }
+        let result = (map.cells().clone(), map.dying().clone());
+        assert_eq!(
+            expected.get_or_insert_with(|| result.clone()),
+            &result,
+            "{engine:?}"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Hexagonal rules: the neighborhood they run on and the hexagons they're drawn with
+
+use cellular_automata::Pos;
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::Map;
+use cellular_automata::conway::rule::{Neighborhood, Rule};
+use egui::{Rect, pos2};
+
+#[test]
+fn hexagonal_suffix() {
+    let rule: Rule = "b2/s34h".parse().expect("test rules are valid");
+    assert_eq!(rule.neighborhood(), Neighborhood::Hexagonal);
+    assert_eq!(rule.to_string(), "B2/S34H");
+    assert_eq!(
+        "B2/S/C3H"
+            .parse::<Rule>()
+            .expect("test rules are valid")
+            .to_string(),
+        "B2/S/C3H"
+    );
+    assert!("B7/S23H".parse::<Rule>().is_err());
+    assert!("B2a/S34H".parse::<Rule>().is_err());
+}
+
+#[test]
+fn clicks_land_on_the_hexagon_under_them() {
+    let mut map = Map::new();
+    map.rule = "B2/S34H".parse().expect("test rules are valid");
+    map.boundary = Boundary::Plane;
+    map.width = 20;
+    map.height = 15;
+    map.cell_size = 17.0;
+    map.x_axis = 3;
+    map.y_axis = -2;
+    let rect = Rect::from_min_max(pos2(30.0, 40.0), pos2(630.0, 440.0));
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let cell = Pos(x, y);
+            let center = map.grid_to_screen(cell, rect).center();
+            assert_eq!(map.screen_to_grid(center, rect), Some(cell));
+            // Just inside every corner of the hexagon, which pokes out of the cell's row
+            let radius = map.cell_size * 2.0 / 3.0;
+            let half_width = map.cell_width() / 2.0;
+            for (dx, dy) in [
+                (0.0, -radius),
+                (half_width, -radius / 2.0),
+                (half_width, radius / 2.0),
+                (0.0, radius),
+                (-half_width, radius / 2.0),
+                (-half_width, -radius / 2.0),
+            ] {
+                let point = center + egui::vec2(dx, dy) * 0.95;
+                assert_eq!(map.screen_to_grid(point, rect), Some(cell), "{dx}, {dy}");
+            }
+        }
+    }
+}
+
+#[test]
+fn neighbors_are_the_hexagons_touching() {
+    let mut map = Map::new();
+    map.rule = "B2/S34H".parse().expect("test rules are valid");
+    let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(800.0, 800.0));
+    let cell = Pos(10, 10);
+    let center = map.grid_to_screen(cell, rect).center();
+    for &(dx, dy) in Neighborhood::Hexagonal.offsets() {
+        let neighbor = map
+            .grid_to_screen(Pos(cell.0 + dx, cell.1 + dy), rect)
+            .center();
+        // Centers of touching regular hexagons are all the same distance apart
+        assert!((center.distance(neighbor) - map.cell_width()).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,159 @@
+//! Lenia and Smooth Life, and the FFT convolution under them
+
+use cellular_automata::continuous::fft::{Complex, Fft, Fft2d};
+use cellular_automata::continuous::{Continuous, Kind, PRESETS, Preset};
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+fn preset(name: &str) -> &'static Preset {
+    PRESETS
+        .iter()
+        .find(|p| p.name == name)
+        .expect("the preset exists")
+}
+
+/// Where the mass of the grid is centred, wrapping round relative to `near`
+fn centre(ca: &Continuous, near: (f32, f32)) -> (f32, f32) {
+    let size = ca.size() as f32;
+    let wrap = |d: f32| d - size * (d / size).round();
+    let (mut x, mut y, mut total) = (0.0, 0.0, 0.0);
+    for (i, &v) in ca.grid().cells().iter().enumerate() {
+        let (cx, cy) = ((i % ca.size()) as f32, (i / ca.size()) as f32);
+        x += v * wrap(cx - near.0);
+        y += v * wrap(cy - near.1);
+        total += v;
+    }
+    (near.0 + x / total, near.1 + y / total)
+}
+
+#[test]
+fn fft_convolution_matches_doing_it_by_hand() {
+    // A transform and back gives the same numbers
+    let fft = Fft::new(16);
+    let mut rng = StdRng::seed_from_u64(20);
+    let start: Vec<Complex> = (0..16)
+        .map(|_| Complex::new(rng.r#gen(), rng.r#gen()))
+        .collect();
+    let mut data = start.clone();
+    fft.transform(&mut data, false);
+    fft.transform(&mut data, true);
+    for (a, b) in data.iter().zip(&start) {
+        assert!((a.re / 16.0 - b.re).abs() < 1e-5 && (a.im / 16.0 - b.im).abs() < 1e-5);
+    }
+
+    // Wrapped convolution of a random grid with a random lopsided kernel
+    let n = 16;
+    let grid: Vec<f32> = (0..n * n).map(|_| rng.r#gen()).collect();
+    let kernel: Vec<f32> = (0..n * n).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let fft = Fft2d::new(n);
+    let fast = fft.convolve(&fft.forward_real(&grid), &fft.forward_real(&kernel));
+    for y in 0..n {
+        for x in 0..n {
+            let mut slow = 0.0;
+            for ky in 0..n {
+                for kx in 0..n {
+                    let (gx, gy) = ((x + n - kx) % n, (y + n - ky) % n);
+                    slow += grid[gy * n + gx] * kernel[ky * n + kx];
+                }
+            }
+            assert!((fast[y * n + x] - slow).abs() < 1e-3, "({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn orbium_swims_without_falling_apart() {
+    let mut ca = Continuous::new(Kind::Lenia, 128);
+    ca.load_preset(preset("Orbium"), &mut StdRng::seed_from_u64(20));
+    let kernel: f32 = ca.lenia.kernel(128).iter().sum();
+    assert!((kernel - 1.0).abs() < 1e-4);
+
+    let mass = ca.mass();
+    let start = centre(&ca, (64.0, 64.0));
+    for _ in 0..300 {
+        ca.step();
+    }
+    let end = centre(&ca, start);
+    let moved = (end.0 - start.0).hypot(end.1 - start.1);
+    assert!(
+        (ca.mass() / mass - 1.0).abs() < 0.25,
+        "mass went from {mass} to {}",
+        ca.mass()
+    );
+    assert!(moved > 5.0, "only moved {moved} cells");
+    assert_eq!(ca.generation(), 300);
+}
+
+#[test]
+fn hydrogeminium_cell_holds_together_in_place() {
+    let mut ca = Continuous::new(Kind::Lenia, 128);
+    ca.load_preset(preset("Hydrogeminium cell"), &mut StdRng::seed_from_u64(20));
+    let mass = ca.mass();
+    let start = centre(&ca, (64.0, 64.0));
+    for _ in 0..300 {
+        ca.step();
+    }
+    let end = centre(&ca, start);
+    let moved = (end.0 - start.0).hypot(end.1 - start.1);
+    assert!(
+        (ca.mass() / mass - 1.0).abs() < 0.1,
+        "mass went from {mass} to {}",
+        ca.mass()
+    );
+    assert!(moved < 5.0, "wandered {moved} cells");
+}
+
+#[test]
+fn smooth_life_keeps_to_its_states_and_the_grid() {
+    // Whatever a soup turns into, every cell stays between 0 and 1
+    for seed in 0..4 {
+        let mut ca = Continuous::new(Kind::SmoothLife, 128);
+        ca.load_preset(
+            preset("SmoothLife gliders"),
+            &mut StdRng::seed_from_u64(seed),
+        );
+        assert_eq!(ca.kind, Kind::SmoothLife);
+        for _ in 0..20 {
+            ca.step();
+        }
+        assert!(
+            ca.grid().cells().iter().all(|v| (0.0..=1.0).contains(v)),
+            "seed {seed}"
+        );
+    }
+
+    // Nothing depends on where on the grid it happens, so a shifted soup runs shifted
+    let mut ca = Continuous::new(Kind::SmoothLife, 128);
+    ca.load_preset(preset("SmoothLife gliders"), &mut StdRng::seed_from_u64(5));
+    let n = ca.size();
+    let mut shifted = Continuous::new(Kind::SmoothLife, n);
+    shifted.load_preset(preset("SmoothLife gliders"), &mut StdRng::seed_from_u64(5));
+    shifted.clear();
+    let (dx, dy) = (17, 40);
+    for y in 0..n {
+        for x in 0..n {
+            shifted.set_cell((x + dx) % n, (y + dy) % n, ca.grid().get(x, y));
+        }
+    }
+    for _ in 0..5 {
+        ca.step();
+        shifted.step();
+    }
+    for y in 0..n {
+        for x in 0..n {
+            let (a, b) = (
+                ca.grid().get(x, y),
+                shifted.grid().get((x + dx) % n, (y + dy) % n),
+            );
+            assert!((a - b).abs() < 1e-3, "({x}, {y}): {a} against {b}");
+        }
+    }
+
+    // An empty grid stays empty, and a full disk with nothing round it dies
+    let mut empty = Continuous::new(Kind::SmoothLife, 64);
+    empty.step();
+    assert!(empty.mass() < 1e-3);
+    empty.paint(32, 32, 7.0, 1.0);
+    empty.step();
+    assert!(empty.mass() < 1.0, "{}", empty.mass());
+}
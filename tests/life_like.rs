@@ -0,0 +1,90 @@
+//! Life-like rulestrings, read in every notation and printed back the one way
+
+use cellular_automata::conway::rule::{Neighborhood, PRESETS, Rule, RuleParseError};
+
+fn rule(rule: &str) -> Rule {
+    rule.parse().expect("test rules are valid")
+}
+
+#[test]
+fn every_notation_prints_back_as_b_s() {
+    for (rulestring, printed) in [
+        ("B3/S23", "B3/S23"),
+        ("b36/s23", "B36/S23"),
+        // Sections can come in either order
+        ("S23/B3", "B3/S23"),
+        // S/B notation puts survival first
+        ("23/3", "B3/S23"),
+        ("/2", "B2/S"),
+        ("012345678/3", "B3/S012345678"),
+        // Counts are printed in order, whatever order they're written in
+        ("B63/S32", "B36/S23"),
+        ("  B3/S23  ", "B3/S23"),
+        ("B3/S23V", "B3/S23V"),
+        ("B/S", "B/S"),
+    ] {
+        assert_eq!(rule(rulestring).to_string(), printed, "{rulestring}");
+        assert_eq!(rule(printed), rule(rulestring), "{rulestring}");
+    }
+    assert_eq!(rule("B3/S23"), Rule::conway());
+    assert_eq!(rule("B3/S23V").neighborhood(), Neighborhood::VonNeumann);
+}
+
+#[test]
+fn rules_say_who_is_born_and_who_survives() {
+    let highlife = rule("B36/S23");
+    for n in 0..=8 {
+        assert_eq!(
+            highlife.next_state(false, n),
+            n == 3 || n == 6,
+            "birth on {n}"
+        );
+        assert_eq!(
+            highlife.next_state(true, n),
+            n == 2 || n == 3,
+            "survival on {n}"
+        );
+    }
+    assert!(rule("B0/S8").births_from_nothing());
+    assert!(!highlife.births_from_nothing());
+}
+
+#[test]
+fn presets_are_all_valid_and_print_the_way_theyre_written() {
+    for (name, rulestring) in PRESETS {
+        assert_eq!(&rule(rulestring).to_string(), rulestring, "{name}");
+    }
+}
+
+#[test]
+fn bad_rulestrings_say_whats_wrong() {
+    for (rulestring, error) in [
+        ("", RuleParseError::Empty),
+        ("   ", RuleParseError::Empty),
+        ("B3S23", RuleParseError::MissingSlash),
+        ("B3/B23", RuleParseError::DuplicateSection('B')),
+        (
+            "B3/S2x",
+            RuleParseError::UnexpectedChar { ch: 'x', index: 5 },
+        ),
+        (
+            " B3/Q23",
+            RuleParseError::UnexpectedChar { ch: 'Q', index: 4 },
+        ),
+        (
+            "B9/S23",
+            RuleParseError::CountOutOfRange { count: 9, max: 8 },
+        ),
+        (
+            "B5/S23V",
+            RuleParseError::CountOutOfRange { count: 5, max: 4 },
+        ),
+    ] {
+        assert_eq!(rulestring.parse::<Rule>(), Err(error), "{rulestring:?}");
+    }
+    // Errors read as sentences, with positions counted from one
+    assert_eq!(
+        "B3/S2x".parse::<Rule>().map_err(|err| err.to_string()),
+        Err("unexpected character 'x' at position 6".to_owned())
+    );
+}
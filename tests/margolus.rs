@@ -0,0 +1,98 @@
+//! Margolus block rules, and running them backwards
+
+use cellular_automata::margolus::{Margolus, PRESETS, Rule, RuleParseError};
+use rand::SeedableRng as _;
+use rand::rngs::StdRng;
+
+fn preset(name: &str) -> Rule {
+    PRESETS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .and_then(|(_, rule, _)| rule.parse().ok())
+        .expect("presets are valid rules")
+}
+
+fn live(ca: &Margolus) -> Vec<(usize, usize)> {
+    (0..ca.grid().height())
+        .flat_map(|y| (0..ca.grid().width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| ca.cell(x, y))
+        .collect()
+}
+
+#[test]
+fn rules_print_the_same_way_back() {
+    for (name, rule, _) in PRESETS {
+        let parsed: Rule = rule.parse().expect("presets are valid rules");
+        assert_eq!(parsed.to_string(), rule);
+        assert!(parsed.is_reversible(), "{name}");
+    }
+    // Separators don't matter much
+    assert_eq!(
+        "0 8 4 3 2 5 9 7 1 6 10 11 12 13 14 15".parse::<Rule>(),
+        Ok(preset("BBM"))
+    );
+    assert_eq!(
+        "MS,D1;2;3".parse::<Rule>(),
+        Err(RuleParseError::WrongLength(3))
+    );
+    assert_eq!(
+        "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;16".parse::<Rule>(),
+        Err(RuleParseError::BadEntry("16".to_owned()))
+    );
+
+    // Every empty block filling up means nothing can tell the two apart going backwards
+    let mut squash = preset("Tron");
+    squash.set(15, 15);
+    assert!(!squash.is_reversible());
+    let mut ca = Margolus::new(squash, 8, 8);
+    ca.step();
+    assert!(!ca.step_back());
+    assert_eq!(ca.generation(), 1);
+}
+
+#[test]
+fn billiard_balls_fly_diagonally() {
+    // A ball in the top left of a block goes to the bottom right, which is the top left of the
+    // next block along once the cut moves
+    let mut ca = Margolus::new(preset("BBM"), 16, 16);
+    ca.set_cell(2, 2, true);
+    for generation in 1..=5 {
+        ca.step();
+        assert_eq!(live(&ca), [(2 + generation, 2 + generation)]);
+    }
+    // Two balls meeting head on in a block both turn a corner
+    let mut ca = Margolus::new(preset("BBM"), 16, 16);
+    ca.set_cell(4, 4, true);
+    ca.set_cell(5, 5, true);
+    ca.step();
+    assert_eq!(live(&ca), [(5, 4), (4, 5)]);
+}
+
+#[test]
+fn stepping_back_undoes_every_step() {
+    let mut rng = StdRng::seed_from_u64(19);
+    for (name, _, _) in PRESETS {
+        let mut ca = Margolus::new(preset(name), 40, 30);
+        ca.randomize(0.6, 0.4, &mut rng);
+        let start = ca.grid().cells().to_vec();
+        for _ in 0..25 {
+            ca.step();
+        }
+        assert_ne!(ca.grid().cells(), start, "{name} did nothing");
+        for _ in 0..25 {
+            assert!(ca.step_back());
+        }
+        assert_eq!(ca.grid().cells(), start, "{name}");
+        assert_eq!(ca.generation(), 0);
+
+        // And past the start too, coming forward again to where it was
+        for _ in 0..7 {
+            ca.step_back();
+        }
+        assert_eq!(ca.generation(), -7);
+        for _ in 0..7 {
+            ca.step();
+        }
+        assert_eq!(ca.grid().cells(), start, "{name} from before the start");
+    }
+}
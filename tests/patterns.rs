@@ -0,0 +1,278 @@
+//! Pattern files: reading them, writing them back, and the errors for broken ones
+
+use cellular_automata::Pos;
+use cellular_automata::pattern::io::{self, Format};
+use cellular_automata::pattern::{MAX_CELLS, MAX_SIZE, Pattern, PatternErrorKind, rle};
+
+const GLIDER: &str = "\
+#N Glider
+#O Richard K. Guy
+#C The smallest, most common, and first discovered spaceship.
+#C www.conwaylife.com/wiki/index.php?title=Glider
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!
+";
+
+fn cells(cells: &[(i32, i32)]) -> Vec<(Pos, u8)> {
+    cells.iter().map(|&(x, y)| (Pos(x, y), 1)).collect()
+}
+
+/// What went wrong reading `src` as RLE, and where
+fn rle_error(src: &str) -> (usize, usize, PatternErrorKind) {
+    let err = rle::parse(src).expect_err("the pattern is broken");
+    (err.line, err.column, err.kind)
+}
+
+#[test]
+fn rle_reads_the_header_comments_and_cells() {
+    let glider = rle::parse(GLIDER).expect("the glider is valid");
+    assert_eq!(glider.name.as_deref(), Some("Glider"));
+    assert_eq!(glider.author.as_deref(), Some("Richard K. Guy"));
+    assert_eq!(glider.comments.len(), 2);
+    assert_eq!(glider.rule.as_deref(), Some("B3/S23"));
+    assert_eq!((glider.width, glider.height), (3, 3));
+    assert_eq!(
+        glider.cells,
+        cells(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)])
+    );
+}
+
+#[test]
+fn rle_round_trips() {
+    let glider = rle::parse(GLIDER).expect("the glider is valid");
+    let written = rle::write(&glider);
+    // `#N`, `#O` and `#C` lines all make it back out
+    assert!(written.starts_with("#N Glider\n#O Richard K. Guy\n#C The smallest"));
+    assert!(written.contains("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"));
+    assert_eq!(rle::parse(&written), Ok(glider));
+
+    // Long patterns get wrapped without splitting runs, and still read back the same
+    let checkerboard = Pattern::from_cells(
+        (0..200)
+            .flat_map(|y| (0..200).map(move |x| (x, y)))
+            .filter(|(x, y)| (x + y) % 2 == 0)
+            .map(|(x, y)| (Pos(x, y), 1)),
+    );
+    let written = rle::write(&checkerboard);
+    assert!(written.lines().all(|line| line.len() <= 70));
+    assert_eq!(
+        rle::parse(&written).map(|p| p.cells),
+        Ok(checkerboard.cells)
+    );
+}
+
+#[test]
+fn rle_multi_state_letters() {
+    let pattern = rle::parse("x = 5, y = 2, rule = B2/S/C255\n.A2B$pAxXyO!").expect("valid");
+    assert_eq!(
+        pattern.cells,
+        [
+            (Pos(1, 0), 1),
+            (Pos(2, 0), 2),
+            (Pos(3, 0), 2),
+            (Pos(0, 1), 25),
+            (Pos(1, 1), 240),
+            (Pos(2, 1), 255),
+        ]
+    );
+    assert_eq!(pattern.max_state(), 255);
+    let written = rle::write(&pattern);
+    assert!(written.contains(".A2B$pAxXyO!"), "{written}");
+    assert_eq!(rle::parse(&written), Ok(pattern));
+
+    // Counts go before the prefix and apply to the whole letter
+    let pattern = rle::parse("x = 3, y = 1\n3pB!").expect("valid");
+    assert_eq!(
+        pattern.cells,
+        [(Pos(0, 0), 26), (Pos(1, 0), 26), (Pos(2, 0), 26)]
+    );
+}
+
+#[test]
+fn broken_rle_says_where() {
+    assert_eq!(
+        rle_error("x = 3, y = 3\nbo$2bo$3z!"),
+        (2, 9, PatternErrorKind::UnexpectedChar('z'))
+    );
+    assert_eq!(
+        rle_error("x = 3, y = 3\n0o!"),
+        (2, 2, PatternErrorKind::BadCount)
+    );
+    assert_eq!(
+        rle_error("x = 3, y = 3\nbo$2bo$3"),
+        (2, 8, PatternErrorKind::UnexpectedEnd)
+    );
+    assert_eq!(
+        rle_error("x = 1, y = 1\nzA!"),
+        (2, 1, PatternErrorKind::UnexpectedChar('z'))
+    );
+    assert_eq!(
+        rle_error("x = 1, y = 1\nyZ!"),
+        (2, 2, PatternErrorKind::UnexpectedChar('Z'))
+    );
+    assert!(matches!(
+        rle_error("x = three, y = 3\nbo!"),
+        (1, 1, PatternErrorKind::BadHeader(_))
+    ));
+}
+
+#[test]
+fn huge_runs_are_errors_not_crashes() {
+    // Counts are cut off as soon as they get too big to be a size
+    assert_eq!(
+        rle_error("x = 0, y = 0\n2000000000b2000000000b!"),
+        (2, 7, PatternErrorKind::BadCount)
+    );
+    assert_eq!(
+        rle_error("x = 0, y = 0\n99999999999o!"),
+        (2, 7, PatternErrorKind::BadCount)
+    );
+    // Each run fits, but together they go off the edge
+    assert_eq!(
+        rle_error("x = 0, y = 0\n1000000b1000000b!"),
+        (2, 16, PatternErrorKind::BadCount)
+    );
+    assert_eq!(
+        rle_error("x = 0, y = 0\n1000000$1000000$o!"),
+        (2, 16, PatternErrorKind::BadCount)
+    );
+    // And there are only so many cells a pattern can have
+    let rows = "1000000o$".repeat(MAX_CELLS / 1_000_000 + 1);
+    assert_eq!(
+        rle_error(&format!("x = 0, y = 0\n{rows}!")),
+        (2, rows.len() - 1, PatternErrorKind::BadCount)
+    );
+    assert!(matches!(
+        rle_error(&format!("x = {}, y = 1\no!", MAX_SIZE + 1)),
+        (1, 1, PatternErrorKind::BadHeader(_))
+    ));
+
+    // Right up to the edge is fine
+    let edge = rle::parse(&format!("x = 0, y = 0\n{}bo!", MAX_SIZE - 1)).expect("valid");
+    assert_eq!(edge.width, MAX_SIZE);
+    assert_eq!(
+        rle_error(&format!("x = 0, y = 0\n{}b2o!", MAX_SIZE - 1)),
+        (2, 10, PatternErrorKind::BadCount)
+    );
+}
+
+#[test]
+fn formats_are_told_apart_by_whats_in_them() {
+    for (src, format) in [
+        (GLIDER, Format::Rle),
+        ("x = 1, y = 1\no!", Format::Rle),
+        ("!Name: Glider\n.O\n..O\nOOO\n", Format::Plaintext),
+        (".O\n..O\nOOO\n", Format::Plaintext),
+        ("#Life 1.05\n#P 0 0\n.*\n", Format::Life105),
+        ("\n#Life 1.06\n0 0\n", Format::Life106),
+        ("#MCell 4.20\n#GAME WireWorld\n#L 3A\n", Format::MCell),
+    ] {
+        assert_eq!(Format::detect(src), format, "{src:?}");
+    }
+}
+
+#[test]
+fn every_format_reads_the_same_glider() {
+    let glider = cells(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    let plaintext = io::read("!Name: Glider\n!The first spaceship\n.O\n..O\nOOO\n").expect("valid");
+    assert_eq!(plaintext.name.as_deref(), Some("Glider"));
+    assert_eq!(plaintext.comments, ["The first spaceship"]);
+    assert_eq!(plaintext.cells, glider);
+
+    // Life 1.05 blocks are placed relative to the middle
+    let life105 = io::read("#Life 1.05\n#D Glider\n#N\n#P -1 -1\n.*\n#P 1 0\n*\n#P -1 1\n***\n")
+        .expect("valid");
+    assert_eq!(life105.rule.as_deref(), Some("B3/S23"));
+    assert_eq!(life105.comments, ["Glider"]);
+    assert_eq!(life105.cells, glider);
+
+    let life106 = io::read("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").expect("valid");
+    assert_eq!(life106.cells, glider);
+    assert_eq!((life106.width, life106.height), (3, 3));
+
+    // And writes it back in every format
+    let pattern = rle::parse(GLIDER).expect("the glider is valid");
+    for format in Format::ALL {
+        let written = io::write(&pattern, format);
+        assert_eq!(Format::detect(&written), format, "{format:?}");
+        let read = io::read(&written).expect("written patterns are valid");
+        assert_eq!(read.cells, glider, "{format:?}");
+    }
+}
+
+#[test]
+fn broken_files_say_where() {
+    for (src, line, column, kind) in [
+        (
+            "!Glider\n.O\n.X\n",
+            3,
+            2,
+            PatternErrorKind::UnexpectedChar('X'),
+        ),
+        (
+            "!comment\n..O\nO.o",
+            3,
+            3,
+            PatternErrorKind::UnexpectedChar('o'),
+        ),
+        (
+            "#Life 1.05\n#P 0 zero\n*\n",
+            2,
+            6,
+            PatternErrorKind::ExpectedNumber,
+        ),
+        (
+            "#Life 1.05\n#P 0\n*\n",
+            2,
+            5,
+            PatternErrorKind::ExpectedNumber,
+        ),
+        (
+            "#Life 1.06\n0 0\n1 one\n",
+            3,
+            3,
+            PatternErrorKind::ExpectedNumber,
+        ),
+        (
+            "#Life 1.06\n0 0\n  7\n",
+            3,
+            4,
+            PatternErrorKind::ExpectedNumber,
+        ),
+    ] {
+        let err = io::read(src).expect_err("the pattern is broken");
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (line, column, kind),
+            "{src:?}"
+        );
+    }
+    let err = io::read("#Life 1.06\n0 0\n1 one\n").expect_err("the pattern is broken");
+    assert_eq!(err.to_string(), "line 3, column 3: expected a number");
+}
+
+#[test]
+fn patterns_too_far_apart_are_errors_not_crashes() {
+    for src in [
+        "#Life 1.06\n2147483647 0\n-2147483648 0\n",
+        "#Life 1.06\n0 -2147483648\n0 2147483647\n",
+        "#Life 1.06\n0 0\n0 1048576\n",
+    ] {
+        let err = io::read(src).expect_err("the pattern is too big");
+        assert_eq!(
+            (err.line, err.kind),
+            (3, PatternErrorKind::TooBig),
+            "{src:?}"
+        );
+    }
+    // Rows can't run off the end of the coordinates either
+    let err = io::read("#Life 1.05\n#P 2147483647 0\n***\n").expect_err("too big");
+    assert_eq!(
+        (err.line, err.column, err.kind),
+        (3, 2, PatternErrorKind::TooBig)
+    );
+
+    // Just inside the limit is fine, wherever it is
+    let far = io::read("#Life 1.06\n2147483647 5\n2146435072 5\n").expect("fits");
+    assert_eq!((far.width, far.height), (MAX_SIZE, 1));
+}
@@ -0,0 +1,74 @@
+//! Rectangular maps, and where the pattern ends up when one is resized
+
+use std::collections::HashSet;
+
+use cellular_automata::Pos;
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::{Map, ResizeAnchor};
+
+/// A 20×10 map with a cell in each corner and one in the middle
+fn map() -> Map {
+    let mut map = Map::new();
+    map.resize(20, 10, ResizeAnchor::TopLeft);
+    for p in [Pos(0, 0), Pos(19, 0), Pos(0, 9), Pos(19, 9), Pos(10, 5)] {
+        map.set_cell_alive(p);
+    }
+    map.cache_initial_state();
+    map
+}
+
+fn cells(cells: &[(i32, i32)]) -> HashSet<Pos> {
+    cells.iter().map(|&(x, y)| Pos(x, y)).collect()
+}
+
+#[test]
+fn growing_keeps_the_pattern_by_the_anchor() {
+    for (anchor, moved) in [
+        (ResizeAnchor::TopLeft, (0, 0)),
+        (ResizeAnchor::TopRight, (10, 0)),
+        (ResizeAnchor::BottomLeft, (0, 4)),
+        (ResizeAnchor::BottomRight, (10, 4)),
+        (ResizeAnchor::Center, (5, 2)),
+    ] {
+        let mut map = map();
+        let before = map.cells().clone();
+        map.resize(30, 14, anchor);
+        assert_eq!((map.width, map.height), (30, 14));
+        let (dx, dy) = moved;
+        let expected: HashSet<Pos> = before.iter().map(|p| Pos(p.0 + dx, p.1 + dy)).collect();
+        assert_eq!(*map.cells(), expected, "{anchor:?}");
+        // The state to reset to moves along with it
+        map.restore_initial_state();
+        assert_eq!(*map.cells(), expected, "{anchor:?} initial state");
+    }
+}
+
+#[test]
+fn shrinking_drops_whatever_falls_off() {
+    for (anchor, kept) in [
+        (ResizeAnchor::TopLeft, cells(&[(0, 0)])),
+        (ResizeAnchor::TopRight, cells(&[(9, 0), (0, 5)])),
+        (ResizeAnchor::BottomLeft, cells(&[(0, 5)])),
+        (ResizeAnchor::BottomRight, cells(&[(9, 5), (0, 1)])),
+        (ResizeAnchor::Center, cells(&[(5, 3)])),
+    ] {
+        let mut map = map();
+        map.resize(10, 6, anchor);
+        assert_eq!(*map.cells(), kept, "{anchor:?}");
+    }
+
+    // The unbounded plane has nowhere for cells to fall off
+    let mut map = map();
+    map.boundary = Boundary::Plane;
+    map.resize(4, 4, ResizeAnchor::TopLeft);
+    assert_eq!(map.cells().len(), 5);
+}
+
+#[test]
+fn strips_wrap_each_way_on_their_own() {
+    let mut map = map();
+    map.resize(40, 3, ResizeAnchor::TopLeft);
+    assert_eq!(map.wrap(Pos(40, 3)), Some(Pos(0, 0)));
+    assert_eq!(map.wrap(Pos(-1, -1)), Some(Pos(39, 2)));
+    assert_eq!(map.wrap(Pos(25, 4)), Some(Pos(25, 1)));
+}
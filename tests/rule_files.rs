@@ -0,0 +1,263 @@
+//! Golly rule files: tables, trees and the colors and icons that come with them
+
+use std::collections::{HashMap, HashSet};
+
+use cellular_automata::Pos;
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::Map;
+use cellular_automata::conway::engine::sparse::SparseEngine;
+use cellular_automata::conway::engine::{Engine as _, Universe};
+use cellular_automata::conway::rule::{Neighborhood, Rule};
+use cellular_automata::conway::rule_file::{Icons, RuleFile, RuleFileError, Transitions};
+use egui::Color32;
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+const LIFE_TABLE: &str = "\
+@RULE LifeTable
+Conway's Life, spelled out one transition at a time
+
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:permute
+var a={0,1}
+var b={0,1}
+var c={0,1}
+var d={0,1}
+var e={0,1}
+var f={0,1}
+var g={0,1}
+var h={0,1}
+0,1,1,1,0,0,0,0,0,1  # birth on 3
+1,1,1,0,0,0,0,0,0,1  # survival on 2
+1,1,1,1,0,0,0,0,0,1  # and on 3
+1,a,b,c,d,e,f,g,h,0  # everything else dies
+
+@COLORS
+1 255 255 0
+";
+
+/// Each cell becomes how many of its four neighbors are alive, mod 2
+const PARITY_TREE: &str = "\
+@RULE Parity
+@TREE
+num_states=2
+num_neighbors=4
+num_nodes=9
+1 0 0
+1 1 1
+2 0 1
+2 1 0
+3 2 3
+3 3 2
+4 4 5
+4 5 4
+5 6 7
+";
+
+fn cells(cells: &[(i32, i32)]) -> HashSet<Pos> {
+    cells.iter().map(|&(x, y)| Pos(x, y)).collect()
+}
+
+fn universe<'a>(
+    cells: &'a HashSet<Pos>,
+    dying: &'a HashMap<Pos, u8>,
+    rule: &'a Rule,
+    boundary: Boundary,
+) -> Universe<'a> {
+    Universe {
+        cells,
+        dying,
+        rule,
+        boundary,
+        width: 24,
+        height: 24,
+    }
+}
+
+#[test]
+fn life_as_a_table_runs_like_life() {
+    let file = RuleFile::parse(LIFE_TABLE).expect("the table is valid");
+    assert_eq!(file.name(), "LifeTable");
+    assert_eq!(file.states(), 2);
+    assert_eq!(file.neighborhood(), Neighborhood::Moore);
+    assert!(file.description().starts_with("Conway's Life"));
+    let Transitions::Table(table) = file.transitions() else {
+        panic!("the file has a table");
+    };
+    // Every distinct way of filling the neighbors is a transition of its own
+    assert!(table.transitions() > 4);
+
+    let rule = Rule::conway();
+    let none = HashMap::new();
+    for boundary in [
+        Boundary::Plane,
+        Boundary::Torus,
+        Boundary::Dead,
+        Boundary::KleinBottle,
+    ] {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut expected: HashSet<Pos> = (0..200)
+            .map(|_| Pos(rng.gen_range(0..24), rng.gen_range(0..24)))
+            .collect();
+        let mut actual = expected.clone();
+        for _ in 0..20 {
+            expected = SparseEngine.step(&universe(&expected, &none, &rule, boundary), 0);
+            let (next, dying) = file.step(&universe(&actual, &none, &rule, boundary), 0);
+            assert!(dying.is_empty());
+            actual = next;
+            assert_eq!(actual, expected, "{boundary:?}");
+        }
+    }
+}
+
+#[test]
+fn trees_match_the_same_rule_as_a_table() {
+    let tree = RuleFile::parse(PARITY_TREE).expect("the tree is valid");
+    assert!(matches!(tree.transitions(), Transitions::Tree(_)));
+    assert_eq!(tree.neighborhood(), Neighborhood::VonNeumann);
+    let table = RuleFile::parse(
+        "@RULE Parity\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:permute\n\
+         var a={0,1}\na,1,0,0,0,1\na,1,1,1,0,1\na,0,0,0,0,0\na,1,1,0,0,0\na,1,1,1,1,0\n",
+    )
+    .expect("the table is valid");
+
+    let rule = Rule::conway();
+    let none = HashMap::new();
+    // A single cell hands itself on to its four neighbors and goes out
+    let dot = cells(&[(5, 5)]);
+    let (next, _) = tree.step(&universe(&dot, &none, &rule, Boundary::Plane), 0);
+    assert_eq!(next, cells(&[(5, 4), (6, 5), (5, 6), (4, 5)]));
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let soup: HashSet<Pos> = (0..150)
+        .map(|_| Pos(rng.gen_range(0..24), rng.gen_range(0..24)))
+        .collect();
+    // Several generations at once, as stepping the tree by 2^3
+    let (by_tree, _) = tree.step(&universe(&soup, &none, &rule, Boundary::Torus), 3);
+    let (by_table, _) = table.step(&universe(&soup, &none, &rule, Boundary::Torus), 3);
+    assert_eq!(by_tree, by_table);
+}
+
+#[test]
+fn symmetries_fill_in_the_transitions_left_out() {
+    // Being born next to a live cell to the north, turned round to every side
+    let file = RuleFile::parse(
+        "@RULE Spread\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:rotate4\n\
+         0,1,0,0,0,1\n",
+    )
+    .expect("the table is valid");
+    let rule = Rule::conway();
+    let none = HashMap::new();
+    let (next, _) = file.step(
+        &universe(&cells(&[(3, 3)]), &none, &rule, Boundary::Plane),
+        0,
+    );
+    assert_eq!(next, cells(&[(3, 3), (3, 2), (4, 3), (3, 4), (2, 3)]));
+
+    // With no symmetry a transition only matches the way it's written
+    let file = RuleFile::parse(
+        "@RULE North\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:none\n0,1,0,0,0,1\n",
+    )
+    .expect("the table is valid");
+    let (next, _) = file.step(
+        &universe(&cells(&[(3, 3)]), &none, &rule, Boundary::Plane),
+        0,
+    );
+    // Only the cell below has the live one to its north, and nothing tells the live one to die
+    assert_eq!(next, cells(&[(3, 3), (3, 4)]));
+}
+
+#[test]
+fn colors_and_icons() {
+    let file = RuleFile::parse(LIFE_TABLE).expect("the table is valid");
+    assert_eq!(file.color(1), Some(Color32::from_rgb(255, 255, 0)));
+    assert_eq!(file.color(0), None);
+
+    let mut map = Map::new();
+    map.rule_file = Some(file);
+    assert_eq!(map.cell_color(1), Color32::from_rgb(255, 255, 0));
+
+    // A gradient runs from the first live state to the last
+    let gradient = RuleFile::parse(
+        "@RULE Fade\n@TABLE\nn_states:4\nneighborhood:Moore\nsymmetries:none\n\
+         @COLORS\n0 0 0 0\n255 0 0 0 0 255\n@ICONS\ncircles\n",
+    )
+    .expect("the rule is valid");
+    assert_eq!(gradient.color(1), Some(Color32::from_rgb(255, 0, 0)));
+    assert_eq!(gradient.color(3), Some(Color32::from_rgb(0, 0, 255)));
+    assert_eq!(gradient.icons(), Some(&Icons::Circles));
+
+    let icons = RuleFile::parse(
+        "@RULE Icons\n@TREE\nnum_states=2\nnum_neighbors=4\nnum_nodes=9\n\
+         1 0 0\n1 1 1\n2 0 1\n2 1 0\n3 2 3\n3 3 2\n4 4 5\n4 5 4\n5 6 7\n\
+         @ICONS\nXPM\n/* width height num_colors chars_per_pixel */\n\"3 3 2 1\"\n\
+         \". c #000000\"\n\"A c #808080\"\n\".A.\"\n\"AAA\"\n\".A.\"\n",
+    )
+    .expect("the rule is valid");
+    let icon = icons
+        .icons()
+        .and_then(|icons| icons.fitting(1, 10.0))
+        .expect("a 3 pixel icon fits a 10 pixel cell");
+    assert_eq!(icon.size, 3);
+    assert!(icon.grayscale);
+    assert_eq!(icon.pixels[0], None);
+    assert_eq!(icon.pixels[1], Some(Color32::from_gray(128)));
+    assert!(icons.icons().and_then(|i| i.fitting(1, 2.0)).is_none());
+}
+
+#[test]
+fn broken_files_say_where() {
+    let error = |source: &str| RuleFile::parse(source).expect_err("the file is broken");
+    assert_eq!(error("@TABLE\nn_states:2\n"), RuleFileError::MissingName);
+    assert_eq!(error("@RULE Nothing\n"), RuleFileError::NoTransitions);
+    assert!(matches!(
+        error("@RULE Short\n@TABLE\nn_states:2\nneighborhood:Moore\n0,1,1\n"),
+        RuleFileError::WrongLength {
+            line: 5,
+            expected: 10,
+            found: 3
+        }
+    ));
+    assert!(matches!(
+        error("@RULE Vars\n@TABLE\nn_states:2\nneighborhood:vonNeumann\n0,x,0,0,0,1\n"),
+        RuleFileError::UnknownVariable { line: 5, .. }
+    ));
+    assert!(matches!(
+        error("@RULE States\n@TABLE\nn_states:2\nneighborhood:vonNeumann\n0,2,0,0,0,1\n"),
+        RuleFileError::StateOutOfRange { line: 5, state: 2 }
+    ));
+    assert!(matches!(
+        error("@RULE Tree\n@TREE\nnum_states=2\nnum_neighbors=4\n2 0 1\n"),
+        RuleFileError::BadNode { line: 5 }
+    ));
+    // Errors read as sentences
+    assert!(!error("@RULE Nothing\n").to_string().is_empty());
+}
+
+#[test]
+fn maps_run_and_save_under_the_rule_file() {
+    let mut map = Map::new();
+    map.boundary = Boundary::Plane;
+    map.max_speed = true;
+    map.rule_file = Some(RuleFile::parse(PARITY_TREE).expect("the tree is valid"));
+    map.clear();
+    map.set_cell_alive(Pos(5, 5));
+    assert!(map.update());
+    assert_eq!(map.cells(), &cells(&[(5, 4), (6, 5), (5, 6), (4, 5)]));
+
+    // Patterns are saved under the rule file's name and load back onto it
+    let pattern = map.to_pattern();
+    assert_eq!(pattern.rule.as_deref(), Some("Parity"));
+    map.load_pattern(&pattern).expect("the rule file is loaded");
+    assert!(map.rule_file.is_some());
+    assert_eq!(map.cells().len(), 4);
+
+    // A rulestring takes over from the rule file
+    let mut pattern = pattern;
+    pattern.rule = Some("B36/S23".to_owned());
+    map.load_pattern(&pattern).expect("the rulestring is valid");
+    assert!(map.rule_file.is_none());
+    assert_eq!(map.rule.to_string(), "B36/S23");
+}
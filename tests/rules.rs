@@ -0,0 +1,364 @@
+//! Hensel notation rules, checked against patterns small enough to work out by hand
+
+use std::collections::{HashMap, HashSet};
+
+use cellular_automata::Pos;
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::engine::hashlife::HashLife;
+use cellular_automata::conway::engine::sparse::SparseEngine;
+use cellular_automata::conway::engine::{Engine, Universe};
+use cellular_automata::conway::hensel;
+use cellular_automata::conway::rule::Rule;
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+fn cells(cells: &[(i32, i32)]) -> HashSet<Pos> {
+    cells.iter().map(|&(x, y)| Pos(x, y)).collect()
+}
+
+/// Run `engine` on an unbounded plane for `generations` steps
+fn run(
+    engine: &mut dyn Engine,
+    cells: &HashSet<Pos>,
+    rule: &str,
+    generations: usize,
+) -> HashSet<Pos> {
+    let rule: Rule = rule.parse().expect("test rules are valid");
+    let mut cells = cells.clone();
+    for _ in 0..generations {
+        cells = engine.step(
+            &Universe {
+                cells: &cells,
+                dying: &HashMap::new(),
+                rule: &rule,
+                boundary: Boundary::Plane,
+                width: 64,
+                height: 64,
+            },
+            0,
+        );
+    }
+    cells
+}
+
+#[test]
+fn every_letter_is_spelled_out_once() {
+    let rule: Rule = "B3cekainyqjr/S2cekain3cekainyqjr"
+        .parse()
+        .expect("test rules are valid");
+    assert_eq!(rule, Rule::conway());
+    assert!(rule.is_totalistic());
+    assert_eq!(rule.to_string(), "B3/S23");
+
+    // 51 arrangements between all the counts
+    let classes: HashSet<_> = (0..=255).map(hensel::classify).collect();
+    assert_eq!(classes.len(), 51);
+}
+
+#[test]
+fn rules_print_the_same_way_back() {
+    for rule in [
+        "B2-ka3ijr/S1c23-ka",
+        "B3/S2-i34q",
+        "B2-a/S12",
+        "B2ce3-k/S1e",
+        "B2ce4twz/S",
+    ] {
+        let parsed: Rule = rule.parse().expect("test rules are valid");
+        assert!(!parsed.is_totalistic());
+        assert_eq!(parsed.to_string(), rule);
+    }
+    // Letters can come in any order and case, they're printed in the order of `hensel::letters`
+    let parsed: Rule = "b2-AK3rji/s1C23-ak".parse().expect("test rules are valid");
+    assert_eq!(parsed.to_string(), "B2-ka3ijr/S1c23-ka");
+    assert_eq!(
+        "B2cekai/S"
+            .parse::<Rule>()
+            .expect("test rules are valid")
+            .to_string(),
+        "B2-n/S"
+    );
+    assert!("B2x/S".parse::<Rule>().is_err());
+    assert!("B3/S2-".parse::<Rule>().is_ok());
+    assert!("B3/S2cV".parse::<Rule>().is_err());
+    assert!("B9a/S".parse::<Rule>().is_err());
+}
+
+#[test]
+fn letters_tell_arrangements_apart() {
+    // The cell between the two has them on opposite edges (2i), the cells above and below have
+    // them on the two corners of one side (2c)
+    let pair = cells(&[(0, 0), (2, 0)]);
+    assert_eq!(run(&mut SparseEngine, &pair, "B2i/S", 1), cells(&[(1, 0)]));
+    assert_eq!(
+        run(&mut SparseEngine, &pair, "B2c/S", 1),
+        cells(&[(1, -1), (1, 1)])
+    );
+    assert!(run(&mut SparseEngine, &pair, "B2-ci/S", 1).is_empty());
+
+    // Blinkers only need the straight line arrangements, 2i to survive and 3i to be born
+    let blinker = cells(&[(-1, 0), (0, 0), (1, 0)]);
+    assert_eq!(
+        run(&mut SparseEngine, &blinker, "B3i/S2i", 1),
+        cells(&[(0, -1), (0, 0), (0, 1)])
+    );
+    assert_eq!(run(&mut SparseEngine, &blinker, "B3i/S2i", 2), blinker);
+    assert!(run(&mut SparseEngine, &blinker, "B3-i/S2-i", 1).is_empty());
+}
+
+#[test]
+fn letters_match_gollys_neighborhoods() {
+    // Golly's table of one neighborhood per letter, letters in the order it lists them. Bits go
+    // row by row over the 3×3 block, so 1 is the top left corner, 2 the cell above and 16 the
+    // middle cell, which is never set
+    let golly: [(&str, &[u16]); 4] = [
+        ("ce", &[1, 2]),
+        ("ceaikn", &[5, 10, 3, 40, 33, 68]),
+        ("ceaiknjqry", &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97]),
+        (
+            "ceaiknjqrytwz",
+            &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+        ),
+    ];
+    let ring_bits = |block: u16| {
+        (0..9)
+            .filter(|bit| block & (1 << bit) != 0)
+            .map(|bit| (bit % 3 - 1, bit / 3 - 1))
+            .filter_map(|offset| hensel::RING.iter().position(|&o| o == offset))
+            .fold(0u8, |bits, i| bits | (1 << i))
+    };
+    let letter = |bits: u8| {
+        let (count, index) = hensel::classify(bits);
+        (count, hensel::letters(count).chars().nth(index))
+    };
+    for (count, (letters, blocks)) in golly.into_iter().enumerate() {
+        let count = count + 1;
+        for (name, &block) in letters.chars().zip(blocks) {
+            let bits = ring_bits(block);
+            assert_eq!(letter(bits), (count, Some(name)), "{count}{name}");
+            // Over four the letters go by the cells left dead
+            if count < 4 {
+                assert_eq!(
+                    letter(!bits),
+                    (8 - count, Some(name)),
+                    "{}{name}",
+                    8 - count
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn tlife_keeps_still_lifes_but_not_blinkers() {
+    // Block: every cell has three neighbors in 3a arrangement
+    let block = cells(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+    assert_eq!(run(&mut SparseEngine, &block, "B3/S2-i34q", 4), block);
+    // Beehive: every cell has two neighbors, arranged 2c or 2k, and only 2i is ruled out
+    let beehive = cells(&[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)]);
+    assert_eq!(run(&mut SparseEngine, &beehive, "B3/S2-i34q", 4), beehive);
+    // With 2i forbidden a blinker's middle cell dies
+    let blinker = cells(&[(-1, 0), (0, 0), (1, 0)]);
+    assert!(!run(&mut SparseEngine, &blinker, "B3/S2-i34q", 1).contains(&Pos(0, 0)));
+}
+
+#[test]
+fn hensel_rules_are_isotropic() {
+    let mut rng = StdRng::seed_from_u64(14);
+    for _ in 0..20 {
+        // A random rule, without B0 or B1 so the pattern stays put
+        let mut rule = String::from("B");
+        for (side, counts) in [("", 2..=8), ("/S", 0..=8)] {
+            rule.push_str(side);
+            for n in counts {
+                if rng.gen_bool(0.5) {
+                    rule.push(char::from(b'0' + n as u8));
+                    rule.extend(hensel::letters(n).chars().filter(|_| rng.gen_bool(0.5)));
+                }
+            }
+        }
+        let soup: HashSet<Pos> = (0..12)
+            .flat_map(|y| (0..12).map(move |x| Pos(x, y)))
+            .filter(|_| rng.gen_bool(0.4))
+            .collect();
+        let turned = |cells: &HashSet<Pos>| cells.iter().map(|p| Pos(-p.1, p.0)).collect();
+        let mirrored = |cells: &HashSet<Pos>| cells.iter().map(|p| Pos(-p.0, p.1)).collect();
+
+        let result = run(&mut SparseEngine, &soup, &rule, 3);
+        assert_eq!(
+            run(&mut SparseEngine, &turned(&soup), &rule, 3),
+            turned(&result),
+            "{rule} turned"
+        );
+        assert_eq!(
+            run(&mut SparseEngine, &mirrored(&soup), &rule, 3),
+            mirrored(&result),
+            "{rule} mirrored"
+        );
+        assert_eq!(
+            run(&mut HashLife::default(), &soup, &rule, 3),
+            result,
+            "{rule} hashlife"
+        );
+    }
+}
+
+#[test]
+fn larger_than_life_rules_print_the_same_way_back() {
+    for rule in [
+        "R5,C0,M1,S34..58,B34..45,NM",
+        "R7,C0,M1,S100..200,B75..170,NM",
+        "R3,C4,M0,S2..9,B5..8,NN",
+        "R10,C0,M0,S123..212,B123..170,NC",
+    ] {
+        let parsed: Rule = rule.parse().expect("test rules are valid");
+        assert!(parsed.range().is_some());
+        assert_eq!(parsed.to_string(), rule);
+    }
+    let defaults: Rule = "r2,s3..4,b3..3".parse().expect("test rules are valid");
+    assert_eq!(defaults.to_string(), "R2,C0,M0,S3..4,B3..3,NM");
+    assert!("R0,C0,M0,S1..2,B3..3,NM".parse::<Rule>().is_err());
+    assert!("R2,C0,M0,S1..2,NM".parse::<Rule>().is_err());
+    assert!("R2,C0,M2,S1..2,B3..3,NM".parse::<Rule>().is_err());
+    // Ranges run from the smaller count to the bigger one
+    assert!("R5,C0,M1,S58..34,B34..45,NM".parse::<Rule>().is_err());
+    assert!("R5,C0,M1,S34..58,B45..34,NM".parse::<Rule>().is_err());
+    // A range 1 von Neumann neighborhood only holds 4 other cells, or 5 with the middle one
+    assert!("R1,C0,M0,S5..5,B1..1,NN".parse::<Rule>().is_err());
+    assert!("R1,C0,M1,S5..5,B1..1,NN".parse::<Rule>().is_ok());
+}
+
+/// Run `rule` on a `width`×`height` map with the given boundary
+fn run_on(
+    cells: &HashSet<Pos>,
+    rule: &Rule,
+    boundary: Boundary,
+    (width, height): (i32, i32),
+    generations: usize,
+) -> HashSet<Pos> {
+    let mut cells = cells.clone();
+    for _ in 0..generations {
+        cells = SparseEngine::generation(&Universe {
+            cells: &cells,
+            dying: &HashMap::new(),
+            rule,
+            boundary,
+            width,
+            height,
+        });
+    }
+    cells
+}
+
+#[test]
+fn range_one_is_the_ordinary_neighborhood() {
+    let mut rng = StdRng::seed_from_u64(16);
+    let size = (23, 17);
+    let soup: HashSet<Pos> = (0..size.1)
+        .flat_map(|y| (0..size.0).map(move |x| Pos(x, y)))
+        .filter(|_| rng.gen_bool(0.4))
+        .collect();
+    let same = [
+        ("R1,C0,M0,S2..3,B3..3,NM", "B3/S23"),
+        // Counting the middle cell too shifts survival up by one
+        ("R1,C0,M1,S3..4,B3..3,NM", "B3/S23"),
+        ("R1,C0,M0,S1..2,B1..1,NN", "B1/S12V"),
+        ("R1,C0,M0,S0..3,B2..2,NM", "B2/S0123"),
+    ];
+    for (ltl, life) in same {
+        let ltl: Rule = ltl.parse().expect("test rules are valid");
+        let life: Rule = life.parse().expect("test rules are valid");
+        for boundary in Boundary::ALL {
+            assert_eq!(
+                run_on(&soup, &ltl, boundary, size, 6),
+                run_on(&soup, &life, boundary, size, 6),
+                "{ltl} against {life} on {boundary:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn summed_area_counts_match_counting_one_by_one() {
+    let mut rng = StdRng::seed_from_u64(61);
+    let size = (31, 26);
+    let soup: HashSet<Pos> = (0..size.1)
+        .flat_map(|y| (0..size.0).map(move |x| Pos(x, y)))
+        .filter(|_| rng.gen_bool(0.5))
+        .collect();
+    for rule in [
+        "R4,C0,M1,S20..40,B18..30,NM",
+        "R4,C0,M0,S10..20,B9..14,NN",
+        "R5,C0,M0,S30..50,B28..40,NC",
+    ] {
+        let rule: Rule = rule.parse().expect("test rules are valid");
+        let range = rule.range().expect("Larger than Life rule");
+        let radius = range.radius() as i32;
+        for boundary in Boundary::ALL {
+            let universe = Universe {
+                cells: &soup,
+                dying: &HashMap::new(),
+                rule: &rule,
+                boundary,
+                width: size.0,
+                height: size.1,
+            };
+            // Every cell that could be alive next generation, looked at the slow way
+            let area: Vec<Pos> = if boundary == Boundary::Plane {
+                (-radius..size.1 + radius)
+                    .flat_map(|y| (-radius..size.0 + radius).map(move |x| Pos(x, y)))
+                    .collect()
+            } else {
+                (0..size.1)
+                    .flat_map(|y| (0..size.0).map(move |x| Pos(x, y)))
+                    .collect()
+            };
+            let expected: HashSet<Pos> = area
+                .into_iter()
+                .filter(|&p| {
+                    let count = (-radius..=radius)
+                        .flat_map(|dy| {
+                            let half = range.shape().half_width(radius, dy);
+                            (-half..=half).map(move |dx| (dx, dy))
+                        })
+                        .filter(|&(dx, dy)| range.counts_middle() || (dx, dy) != (0, 0))
+                        .filter_map(|(dx, dy)| universe.wrap(Pos(p.0 + dx, p.1 + dy)))
+                        .filter(|n| soup.contains(n))
+                        .count();
+                    rule.next_state(soup.contains(&p), count)
+                })
+                .collect();
+            assert_eq!(
+                SparseEngine::generation(&universe),
+                expected,
+                "{rule} on {boundary:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn patterns_far_apart_on_the_plane_run_as_if_alone() {
+    let mut rng = StdRng::seed_from_u64(27);
+    // Straddling the corner where four tiles meet
+    let near: HashSet<Pos> = (100..150)
+        .flat_map(|y| (100..150).map(move |x| Pos(x, y)))
+        .filter(|_| rng.gen_bool(0.5))
+        .collect();
+    let offset = Pos(3_000_000, -2_000_000);
+    let far: HashSet<Pos> = near
+        .iter()
+        .map(|p| Pos(p.0 + offset.0, p.1 + offset.1))
+        .collect();
+    let rule: Rule = "R5,C0,M1,S34..58,B34..45,NM"
+        .parse()
+        .expect("test rules are valid");
+    let alone = run_on(&near, &rule, Boundary::Plane, (32, 32), 4);
+    assert!(!alone.is_empty());
+    let both = run_on(&(&near | &far), &rule, Boundary::Plane, (32, 32), 4);
+    let moved: HashSet<Pos> = alone
+        .iter()
+        .map(|p| Pos(p.0 + offset.0, p.1 + offset.1))
+        .collect();
+    assert_eq!(both, &alone | &moved);
+}
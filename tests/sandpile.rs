@@ -0,0 +1,110 @@
+//! The abelian sandpile: toppling, the grains it loses and the avalanches it records
+
+use cellular_automata::grid::Neighborhood;
+use cellular_automata::sandpile::{Drop, Sandpile};
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+#[test]
+fn piles_topple_onto_their_neighbors() {
+    let mut sandpile = Sandpile::new(5, 5);
+    assert_eq!(sandpile.drop_at(2, 2, 3), 0);
+    assert_eq!(sandpile.drop_at(2, 2, 1), 1);
+    assert_eq!(sandpile.grains().get(2, 2), 0);
+    for (x, y) in [(2, 1), (3, 2), (2, 3), (1, 2)] {
+        assert_eq!(sandpile.grains().get(x, y), 1);
+    }
+    assert_eq!(sandpile.total(), 4);
+
+    // Over the edge grains are lost
+    sandpile.clear();
+    sandpile.drop_at(0, 0, 4);
+    assert_eq!(sandpile.total(), 2);
+    assert_eq!(sandpile.drop_at(9, 9, 4), 0, "past the edge");
+
+    // Eight neighbors topple at eight, and a higher threshold throws the rest away
+    sandpile.clear();
+    sandpile.set_neighborhood(Neighborhood::Moore);
+    assert_eq!(sandpile.threshold(), 8);
+    assert_eq!(sandpile.drop_at(2, 2, 8), 1);
+    assert_eq!(sandpile.total(), 8);
+    sandpile.clear();
+    sandpile.set_threshold(10);
+    sandpile.drop_at(2, 2, 10);
+    assert_eq!(sandpile.total(), 8);
+    sandpile.set_threshold(2);
+    assert_eq!(sandpile.threshold(), 8, "no lower than one per neighbor");
+}
+
+#[test]
+fn a_big_pile_settles_into_a_symmetric_stable_pattern() {
+    let size = 101;
+    let mut sandpile = Sandpile::new(size, size);
+    let grains = 1 << 13;
+    let topples = sandpile.drop_at(size / 2, size / 2, grains);
+    assert!(topples > u64::from(grains), "{topples} topples");
+    // Far enough from the edges that nothing's lost
+    assert_eq!(sandpile.total(), u64::from(grains));
+    assert!(sandpile.grains().cells().iter().all(|&g| g < 4));
+    for y in 0..size {
+        for x in 0..size {
+            let g = sandpile.grains().get(x, y);
+            assert_eq!(g, sandpile.grains().get(size - 1 - x, y));
+            assert_eq!(g, sandpile.grains().get(x, size - 1 - y));
+            assert_eq!(g, sandpile.grains().get(y, x));
+        }
+    }
+    assert_eq!(sandpile.last_avalanche(), topples);
+    assert!(sandpile.topples().get(size / 2, size / 2) > 0);
+}
+
+#[test]
+fn the_order_grains_are_dropped_in_doesnt_matter() {
+    let mut rng = StdRng::seed_from_u64(23);
+    let drops: Vec<(usize, usize, u32)> = (0..300)
+        .map(|_| {
+            (
+                rng.gen_range(0..20),
+                rng.gen_range(0..20),
+                rng.gen_range(1..6),
+            )
+        })
+        .collect();
+    let mut forwards = Sandpile::new(20, 20);
+    let mut backwards = Sandpile::new(20, 20);
+    for &(x, y, grains) in &drops {
+        forwards.drop_at(x, y, grains);
+    }
+    for &(x, y, grains) in drops.iter().rev() {
+        backwards.drop_at(x, y, grains);
+    }
+    assert_eq!(forwards.grains(), backwards.grains());
+}
+
+#[test]
+fn avalanches_are_binned_by_powers_of_two() {
+    let mut sandpile = Sandpile::new(5, 5);
+    // Three layers settle without a topple, the fourth brings everything down
+    for _ in 0..3 {
+        assert_eq!(sandpile.drop_layer(1), 0);
+    }
+    let big = sandpile.drop_layer(1);
+    assert!(big >= 25);
+    let histogram = sandpile.histogram();
+    assert_eq!(histogram[0], 3);
+    assert_eq!(histogram.iter().sum::<u64>(), 4);
+    assert_eq!(histogram[big.ilog2() as usize + 1], 1);
+    assert_eq!(Sandpile::histogram_label(0), "0");
+    assert_eq!(Sandpile::histogram_label(1), "1");
+    assert_eq!(Sandpile::histogram_label(4), "8–15");
+
+    // Random drops land one grain at a time, each its own avalanche
+    sandpile.clear();
+    sandpile.drop = Drop::Random;
+    sandpile.grains_per_drop = 50;
+    sandpile.reseed(1);
+    sandpile.step();
+    assert_eq!(sandpile.steps(), 1);
+    assert_eq!(sandpile.histogram().iter().sum::<u64>(), 50);
+    assert!(sandpile.grains().cells().iter().all(|&g| g < 4));
+}
@@ -0,0 +1,110 @@
+//! Statistics kept on every update, and the CSV they're exported as
+
+use std::collections::HashSet;
+
+use cellular_automata::conway::boundary::Boundary;
+use cellular_automata::conway::conway_map::Map;
+use cellular_automata::{Pos, RunStatistics};
+
+fn cells(cells: &[(i32, i32)]) -> HashSet<Pos> {
+    cells.iter().map(|&(x, y)| Pos(x, y)).collect()
+}
+
+#[test]
+fn steps_count_who_was_born_and_who_died() {
+    let vertical = cells(&[(1, 0), (1, 1), (1, 2)]);
+    let horizontal = cells(&[(0, 1), (1, 1), (2, 1)]);
+    assert_eq!(
+        RunStatistics::of_step(&vertical, &horizontal, 7),
+        RunStatistics {
+            births: 2,
+            deaths: 2,
+            generations: 7,
+            population: 3,
+            bounding_box: (3, 1),
+        }
+    );
+
+    // Everything dying leaves an empty box
+    let gone = RunStatistics::of_step(&vertical, &HashSet::new(), 1);
+    assert_eq!((gone.births, gone.deaths), (0, 3));
+    assert_eq!((gone.population, gone.bounding_box), (0, (0, 0)));
+
+    // The box covers the far corners, negative coordinates and all
+    let spread = RunStatistics::of_cells(&cells(&[(-3, 4), (5, -2), (0, 0)]), 0);
+    assert_eq!(spread.bounding_box, (9, 7));
+    assert_eq!((spread.births, spread.deaths), (0, 0));
+}
+
+#[test]
+fn maps_keep_a_history_of_every_update() {
+    let mut map = Map::new();
+    map.boundary = Boundary::Plane;
+    map.max_speed = true;
+    for x in 0..3 {
+        map.set_cell_alive(Pos(x, 5));
+    }
+    for _ in 0..4 {
+        map.update();
+    }
+    // The state it started in, then one entry per update
+    let history = map.history();
+    assert_eq!(history.len(), 5);
+    assert_eq!(
+        history[0],
+        RunStatistics::of_cells(&cells(&[(0, 5), (1, 5), (2, 5)]), 0)
+    );
+    for (generation, stats) in history.iter().enumerate().skip(1) {
+        assert_eq!(stats.generations, generation as u64);
+        assert_eq!((stats.births, stats.deaths, stats.population), (2, 2, 3));
+    }
+    assert_eq!(history[1].bounding_box, (1, 3));
+    assert_eq!(map.stats(), &history[4]);
+
+    // Jumping ahead several generations at once counts all of them
+    map.step_log2 = 3;
+    map.update();
+    assert_eq!(map.stats().generations, 12);
+
+    map.clear();
+    assert!(map.history().is_empty());
+    assert_eq!(map.stats().generations, 0);
+}
+
+#[test]
+fn csv_has_a_header_and_a_row_per_update() {
+    let history = [
+        RunStatistics {
+            births: 0,
+            deaths: 0,
+            generations: 0,
+            population: 5,
+            bounding_box: (3, 3),
+        },
+        RunStatistics {
+            births: 2,
+            deaths: 2,
+            generations: 1,
+            population: 5,
+            bounding_box: (3, 3),
+        },
+        RunStatistics {
+            births: 0,
+            deaths: 5,
+            generations: 2,
+            population: 0,
+            bounding_box: (0, 0),
+        },
+    ];
+    assert_eq!(
+        RunStatistics::to_csv(&history),
+        "generation,population,births,deaths,bounding_width,bounding_height\n\
+         0,5,0,0,3,3\n\
+         1,5,2,2,3,3\n\
+         2,0,0,5,0,0\n"
+    );
+    assert_eq!(
+        RunStatistics::to_csv(&[]),
+        format!("{}\n", RunStatistics::CSV_HEADER)
+    );
+}
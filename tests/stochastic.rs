@@ -0,0 +1,93 @@
+//! The forest fire and SIR epidemic, with their chances pushed to the ends where they're sure
+//! things, and reruns from the same seed
+
+use cellular_automata::stochastic::{Kind, Stochastic, epidemic, forest};
+
+#[test]
+fn the_same_seed_runs_the_same_way() {
+    for kind in Kind::ALL {
+        let run = |seed: u64| {
+            let mut model = Stochastic::new(kind, 64, 64, seed);
+            model.epidemic.infected = 0.01;
+            model.forest_fire.lightning = 1e-3;
+            model.restart();
+            for _ in 0..50 {
+                model.step();
+            }
+            (model.grid().cells().to_vec(), model.history().to_vec())
+        };
+        assert_eq!(run(24), run(24), "{kind:?}");
+        assert_ne!(run(24), run(25), "{kind:?}");
+    }
+
+    // Restarting goes back to exactly where the seed started
+    let mut model = Stochastic::new(Kind::ForestFire, 32, 32, 7);
+    let start = model.grid().cells().to_vec();
+    model.step();
+    model.restart();
+    assert_eq!(model.grid().cells(), start);
+    assert_eq!(model.generation(), 0);
+    assert_eq!(model.history().len(), 1);
+}
+
+#[test]
+fn fire_spreads_through_the_forest_a_cell_at_a_time() {
+    let mut model = Stochastic::new(Kind::ForestFire, 21, 21, 0);
+    model.forest_fire.density = 1.0;
+    model.forest_fire.growth = 0.0;
+    model.forest_fire.lightning = 0.0;
+    model.restart();
+    assert_eq!(model.counts(), [0, 21 * 21, 0]);
+    model.set_cell(10, 10, forest::FIRE);
+    model.step();
+    // The fire burns out and its four neighbors catch
+    assert_eq!(model.grid().get(10, 10), forest::EMPTY);
+    assert_eq!(model.counts()[usize::from(forest::FIRE)], 4);
+    model.step();
+    assert_eq!(model.counts()[usize::from(forest::FIRE)], 8);
+    assert_eq!(model.grid().get(10, 8), forest::FIRE);
+    for _ in 0..30 {
+        model.step();
+    }
+    assert_eq!(model.counts(), [21 * 21, 0, 0], "everything burnt down");
+
+    // With no lightning and no fire, the forest just grows
+    let mut model = Stochastic::new(Kind::ForestFire, 16, 16, 1);
+    model.forest_fire.growth = 1.0;
+    model.forest_fire.lightning = 0.0;
+    model.step();
+    assert_eq!(model.counts(), [0, 256, 0]);
+}
+
+#[test]
+fn epidemics_burn_out_leaving_the_recovered() {
+    let mut model = Stochastic::new(Kind::Epidemic, 64, 64, 2);
+    model.epidemic.infected = 0.0;
+    model.epidemic.infection = 1.0;
+    model.epidemic.recovery = 0.0;
+    model.restart();
+    model.set_cell(5, 5, epidemic::INFECTED);
+    model.step();
+    assert_eq!(model.counts(), [64 * 64 - 5, 5, 0]);
+
+    let mut model = Stochastic::new(Kind::Epidemic, 64, 64, 3);
+    model.epidemic.infected = 0.005;
+    model.epidemic.infection = 0.5;
+    model.epidemic.recovery = 0.2;
+    model.restart();
+    for _ in 0..400 {
+        model.step();
+    }
+    let history = model.history();
+    assert_eq!(history.len(), 401);
+    assert!(
+        history
+            .iter()
+            .all(|counts| counts.iter().sum::<u64>() == 64 * 64)
+    );
+    // Nobody gets ill again once they've recovered
+    assert!(history.windows(2).all(|pair| pair[1][2] >= pair[0][2]));
+    let [_, infected, recovered] = model.counts();
+    assert_eq!(infected, 0, "the epidemic is over");
+    assert!(recovered > 64 * 64 / 2, "only {recovered} caught it");
+}
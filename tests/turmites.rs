@@ -0,0 +1,119 @@
+//! Langton's Ant and turmites, checked against what they're known to do
+
+use cellular_automata::turmite::rule::{Rule, RuleParseError, Turn};
+use cellular_automata::turmite::{Heading, Turmites};
+
+fn rule(rule: &str) -> Rule {
+    rule.parse().expect("test rules are valid")
+}
+
+#[test]
+fn rules_read_both_ways() {
+    // Langton's Ant as turns and as a table is the same rule, and prints as the shorter one
+    let table = rule("{{{1, 2, 0}, {0, 8, 0}}}");
+    assert_eq!(table, rule("RL"));
+    assert_eq!(table, Rule::default());
+    assert_eq!(table.to_string(), "RL");
+    assert_eq!(rule(" llrr ").to_string(), "LLRR");
+    assert_eq!(
+        rule("RNUL").turns(),
+        Some(vec![Turn::Right, Turn::None, Turn::Around, Turn::Left])
+    );
+
+    let fibonacci = "{{{1, 8, 1}, {1, 8, 1}}, {{1, 2, 1}, {0, 1, 0}}}";
+    let parsed = rule(fibonacci);
+    assert_eq!((parsed.states(), parsed.colors()), (2, 2));
+    assert_eq!(parsed.turns(), None);
+    assert_eq!(parsed.to_string(), fibonacci);
+    assert_eq!(rule("{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}"), parsed);
+
+    for (bad, err) in [
+        ("", RuleParseError::Empty),
+        ("R", RuleParseError::TooFewColors),
+        ("RLX", RuleParseError::UnknownTurn('X')),
+        ("{{{1, 2, 0}, {0, 8, 0}}", RuleParseError::Unbalanced),
+        ("{{{1, 2}, {0, 8, 0}}}", RuleParseError::NotATriple(2)),
+        ("{{{1, 3, 0}, {0, 8, 0}}}", RuleParseError::BadTurn(3)),
+        ("{{{2, 2, 0}, {0, 8, 0}}}", RuleParseError::NoSuchColor(2)),
+        ("{{{1, 2, 1}, {0, 8, 0}}}", RuleParseError::NoSuchState(1)),
+        (
+            "{{{1, 2, 0}, {0, 8, 0}}, {{1, 2, 0}}}",
+            RuleParseError::Ragged,
+        ),
+        (
+            "{{{1, 2, 0}; {0, 8, 0}}}",
+            RuleParseError::UnexpectedChar(';'),
+        ),
+    ] {
+        assert_eq!(bad.parse::<Rule>(), Err(err), "{bad:?}");
+    }
+}
+
+#[test]
+fn langtons_ant_builds_a_highway() {
+    let mut ant = Turmites::new(rule("RL"), 200, 200);
+    let position = |ant: &Turmites| {
+        let a = ant.ants()[0];
+        (a.x as i64, a.y as i64)
+    };
+    // Chaos for the first ten thousand or so steps, then the same 104 step cycle over and over,
+    // each one moving two cells diagonally
+    ant.run(11_000);
+    let mut last = position(&ant);
+    let mut moves = Vec::new();
+    for _ in 0..5 {
+        ant.run(104);
+        let now = position(&ant);
+        moves.push((now.0 - last.0, now.1 - last.1));
+        last = now;
+    }
+    assert!(moves.iter().all(|&m| m == moves[0]), "{moves:?}");
+    assert_eq!((moves[0].0.abs(), moves[0].1.abs()), (2, 2));
+    assert_eq!(ant.steps(), 11_520);
+
+    // Nowhere near a highway yet at the start
+    let mut early = Turmites::new(rule("RL"), 200, 200);
+    early.run(1_000);
+    let before = position(&early);
+    early.run(104);
+    let after = position(&early);
+    early.run(104);
+    assert_ne!(
+        (after.0 - before.0, after.1 - before.1),
+        (position(&early).0 - after.0, position(&early).1 - after.1)
+    );
+}
+
+#[test]
+fn every_ant_moves_each_step() {
+    let mut turmites = Turmites::new(rule("RL"), 32, 32);
+    turmites.add_ant(4, 4, Heading::East);
+    turmites.add_ant(40, 4, Heading::East);
+    assert_eq!(turmites.ants().len(), 2);
+
+    // Both turn right off a blank cell and leave it painted
+    turmites.step();
+    let ants = turmites.ants();
+    assert_eq!(
+        (ants[0].x, ants[0].y, ants[0].heading),
+        (17, 16, Heading::East)
+    );
+    assert_eq!(
+        (ants[1].x, ants[1].y, ants[1].heading),
+        (4, 5, Heading::South)
+    );
+    assert_eq!(
+        turmites.grid().cells().iter().filter(|&&c| c == 1).count(),
+        2
+    );
+
+    // Wrapping round the edge rather than falling off it
+    turmites.add_ant(0, 0, Heading::West);
+    turmites.step();
+    let ant = turmites.ants()[2];
+    assert_eq!((ant.x, ant.y, ant.heading), (0, 31, Heading::North));
+    assert_eq!(turmites.steps(), 2);
+
+    turmites.remove_ants_at(0, 31);
+    assert_eq!(turmites.ants().len(), 2);
+}
@@ -0,0 +1,179 @@
+//! Wireworld circuits small enough to follow by hand, and the files they come in
+
+use cellular_automata::conway::rule::Rule;
+use cellular_automata::pattern::io::{self, Format};
+use cellular_automata::pattern::{Pattern, PatternErrorKind};
+use cellular_automata::wireworld::{Cell, WireWorld, is_wireworld_rule};
+
+/// A board drawn as rows of `.` (empty), `#` (conductor), `H` (head) and `t` (tail)
+fn board(rows: &[&str]) -> WireWorld {
+    let mut ww = WireWorld::new(rows[0].len(), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            let cell = match ch {
+                '#' => Cell::Conductor,
+                'H' => Cell::Head,
+                't' => Cell::Tail,
+                _ => Cell::Empty,
+            };
+            ww.set_cell(x, y, cell);
+        }
+    }
+    ww
+}
+
+fn rows(ww: &WireWorld) -> Vec<String> {
+    (0..ww.grid().height())
+        .map(|y| {
+            (0..ww.grid().width())
+                .map(|x| match ww.grid().get(x, y) {
+                    Cell::Empty => '.',
+                    Cell::Conductor => '#',
+                    Cell::Head => 'H',
+                    Cell::Tail => 't',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn electrons_run_along_wires() {
+    let mut ww = board(&["tH####"]);
+    ww.step();
+    assert_eq!(rows(&ww), ["#tH###"]);
+    ww.step();
+    ww.step();
+    assert_eq!(rows(&ww), ["###tH#"]);
+    // Off the end of the wire it's gone, unless the edges wrap
+    ww.step();
+    ww.step();
+    ww.step();
+    assert_eq!(rows(&ww), ["######"]);
+    assert_eq!(ww.generation(), 6);
+
+    let mut ring = board(&["......", "tH####", "......"]);
+    ring.wrap = true;
+    for _ in 0..6 {
+        ring.step();
+    }
+    assert_eq!(rows(&ring), ["......", "tH####", "......"]);
+}
+
+/// Whether an electron ever shows up in column `x` of the middle row, over `generations` steps
+fn reaches(ww: &mut WireWorld, x: usize, generations: usize) -> bool {
+    (0..generations).any(|_| {
+        ww.step();
+        ww.grid().get(x, 1) == Cell::Head
+    })
+}
+
+#[test]
+fn diodes_only_let_electrons_through_one_way() {
+    // Coming from the left, the electron spreads into the blob and out the other side. From the
+    // right it hits the blob with two heads at once and the blob touches the wire with three
+    let mut forward = board(&["....##....", "tH###.####", "....##...."]);
+    assert!(reaches(&mut forward, 8, 20));
+    let mut backward = board(&["....##....", "#####.##Ht", "....##...."]);
+    assert!(!reaches(&mut backward, 1, 20));
+}
+
+#[test]
+fn stripping_electrons_keeps_the_circuit() {
+    let mut ww = board(&["tH##", "..#.", "..Ht"]);
+    ww.strip_electrons();
+    assert_eq!(rows(&ww), ["####", "..#.", "..##"]);
+}
+
+#[test]
+fn wireworld_files_load() {
+    let mcell = "#MCell 4.20\n\
+                 #GAME WireWorld\n\
+                 #BOARD 40x20\n\
+                 #D An electron on a wire\n\
+                 #L .BA3C$\n\
+                 #L 2.C\n";
+    assert_eq!(Format::detect(mcell), Format::MCell);
+    let pattern = io::read(mcell).expect("test patterns are valid");
+    assert!(pattern.rule.as_deref().is_some_and(is_wireworld_rule));
+    assert_eq!(pattern.comments, ["An electron on a wire"]);
+
+    let rle = "x = 5, y = 2, rule = WireWorld\nBA3C$.C!\n";
+    assert_eq!(
+        io::read(rle).expect("test patterns are valid"),
+        Pattern {
+            comments: Vec::new(),
+            ..pattern.clone()
+        }
+    );
+
+    let mut ww = WireWorld::new(5, 2);
+    ww.load_pattern(&pattern);
+    let loaded = ww.to_pattern();
+    assert_eq!(loaded.cells, pattern.cells);
+    assert_eq!(loaded.rule.as_deref(), Some("WireWorld"));
+
+    // And back out again the same way in either format
+    for format in [Format::MCell, Format::Rle] {
+        let written = io::write(&loaded, format);
+        assert_eq!(
+            io::read(&written).expect("written patterns are valid"),
+            loaded
+        );
+    }
+}
+
+#[test]
+fn mcell_runs_are_kept_in_bounds() {
+    // Same limits as RLE, so a silly count is an error rather than a huge allocation
+    let huge = "#MCell 4.20\n#GAME WireWorld\n#L 2000000000C\n";
+    let error = io::read(huge).expect_err("runs past the size limit are rejected");
+    assert_eq!(error.kind, PatternErrorKind::BadCount);
+    // Found as soon as the count gets too big, at the seventh digit
+    assert_eq!((error.line, error.column), (3, 10));
+    let unfinished = "#MCell 4.20\n#GAME WireWorld\n#L 3C$2b\n";
+    let error = io::read(unfinished).expect_err("a prefix needs its letter");
+    assert_eq!(error.kind, PatternErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn mcell_writes_rules_the_way_mcell_does() {
+    let rule_lines = |rule: &str| {
+        let pattern = Pattern {
+            rule: Some(rule.to_owned()),
+            ..Pattern::from_cells(vec![])
+        };
+        let written = io::write(&pattern, Format::MCell);
+        let lines: Vec<_> = written
+            .lines()
+            .filter(|line| line.starts_with("#GAME") || line.starts_with("#RULE"))
+            .map(str::to_owned)
+            .collect();
+        // Whatever gets written reads back as the same rule
+        let read = io::read(&written).expect("written patterns are valid");
+        if !lines.is_empty() {
+            assert_eq!(
+                read.rule.map(|rule| rule.parse::<Rule>()),
+                Some(rule.parse::<Rule>()),
+                "{rule}"
+            );
+        }
+        lines
+    };
+    assert_eq!(rule_lines("B3/S23"), ["#GAME Life", "#RULE 23/3"]);
+    assert_eq!(
+        rule_lines("B2/S345/C4"),
+        ["#GAME Generations", "#RULE 345/2/4"]
+    );
+    assert_eq!(
+        rule_lines("R5,C0,M1,S34..58,B34..45,NM"),
+        [
+            "#GAME Larger than Life",
+            "#RULE R5,C0,M1,S34..58,B34..45,NM"
+        ]
+    );
+    // Nothing MCell would read as the wrong rule
+    for rule in ["B2-a/S12", "B2/S34H", "B2/S3V", "no such rule"] {
+        assert!(rule_lines(rule).is_empty(), "{rule}");
+    }
+}
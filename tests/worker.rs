@@ -0,0 +1,104 @@
+//! Running the simulation on the background thread, and what the map is left with afterwards
+
+use std::thread;
+use std::time::Duration;
+
+use cellular_automata::Pos;
+use cellular_automata::conway::conway_map::{Map, ResizeAnchor};
+use cellular_automata::conway::worker::Worker;
+
+fn glider() -> Map {
+    let mut map = Map::new();
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        map.set_cell_alive(Pos(x + 10, y + 10));
+    }
+    map
+}
+
+/// Call `sync` once a frame for about `frames` frames, changing the map with `each_frame` first
+fn run(worker: &mut Worker, map: &mut Map, frames: usize, mut each_frame: impl FnMut(&mut Map)) {
+    for _ in 0..frames {
+        each_frame(map);
+        worker.sync(map, true);
+        thread::sleep(Duration::from_millis(10));
+    }
+    worker.sync(map, false);
+}
+
+/// The map `glider` turns into after `generations` updates
+fn glider_after(generations: u64) -> Map {
+    let mut map = glider();
+    map.max_speed = true;
+    for _ in 0..generations {
+        map.update();
+    }
+    map
+}
+
+#[test]
+fn pausing_keeps_every_generation_the_thread_ran() {
+    let mut worker = Worker::default();
+    let mut map = glider();
+    map.max_speed = true;
+    run(&mut worker, &mut map, 20, |_| {});
+
+    let generations = map.stats().generations;
+    assert!(generations > 0);
+    assert_eq!(worker.generations(), generations);
+    assert_eq!(map.cells(), glider_after(generations).cells());
+}
+
+#[test]
+fn changing_the_speed_doesnt_hold_the_thread_up() {
+    let mut worker = Worker::default();
+    let mut map = glider();
+    let mut frame = 0;
+    run(&mut worker, &mut map, 40, |map| {
+        // A slider being dragged back and forth
+        frame += 1;
+        map.fps = 200 + frame % 2;
+        map.update_speed();
+    });
+
+    // Restarting the thread would have dropped generations every frame
+    let generations = map.stats().generations;
+    assert!(generations >= 20, "only {generations} generations");
+    assert_eq!(worker.generations(), generations);
+    assert_eq!(map.cells(), glider_after(generations).cells());
+}
+
+#[test]
+fn generations_dropped_on_a_restart_arent_counted() {
+    let mut worker = Worker::default();
+    let mut map = glider();
+    map.max_speed = true;
+    let mut width = map.width;
+    run(&mut worker, &mut map, 20, |map| {
+        width += 1;
+        let height = map.height;
+        map.resize(width, height, ResizeAnchor::TopLeft);
+    });
+
+    // Only generations that made it back to the map count, whatever the thread got to after
+    assert_eq!(worker.generations(), map.stats().generations);
+}
+
+#[test]
+fn clearing_while_running_isnt_undone_by_pausing() {
+    let mut worker = Worker::default();
+    let mut map = glider();
+    map.max_speed = true;
+    for _ in 0..10 {
+        worker.sync(&mut map, true);
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // Cleared and paused in the same frame, before the thread hears about either
+    map.clear();
+    worker.sync(&mut map, false);
+    assert!(
+        map.cells().is_empty(),
+        "{} cells came back",
+        map.cells().len()
+    );
+}